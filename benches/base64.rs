@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::io::{self, Write};
+
+use ssl::base64::decoder::Decoder;
+use ssl::base64::encoder::Encoder;
+
+const SIZES: [usize; 4] = [64, 1024, 64 * 1024, 1024 * 1024];
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("base64 encode");
+    for size in SIZES {
+        let data = vec![0x41u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+            b.iter(|| {
+                let mut encoder = Encoder::new(io::sink());
+                encoder.write_all(black_box(data)).unwrap();
+                encoder.finish().unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("base64 decode");
+    for size in SIZES {
+        let mut data = Vec::new();
+        let mut encoder = Encoder::new(&mut data);
+        encoder.write_all(&vec![0x41u8; size]).unwrap();
+        encoder.finish().unwrap();
+        drop(encoder);
+        // throughput of the decoded bytes, like the encode group.
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+            b.iter(|| {
+                let mut decoder = Decoder::new(io::sink());
+                decoder.write_all(black_box(data)).unwrap();
+                decoder.finish().unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use ssl::libs::hash;
+
+const SIZES: [usize; 4] = [64, 1024, 64 * 1024, 1024 * 1024];
+
+fn digest(c: &mut Criterion) {
+    for f in [hash::Func::MD5, hash::Func::SHA256] {
+        let mut group = c.benchmark_group(f.to_string());
+        for size in SIZES {
+            let data = vec![0x41u8; size];
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &data, |b, data| {
+                b.iter(|| hash::digest(black_box(data.as_slice()), f).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, digest);
+criterion_main!(benches);
@@ -0,0 +1,154 @@
+use clap::Args;
+use std::error;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::hash::check;
+use crate::libs::hash::Func;
+use crate::libs::input;
+use crate::libs::json::Value;
+use crate::libs::pem;
+use crate::libs::report;
+use crate::libs::weak::{self, Finding, Severity};
+
+const PEM_BEGIN: &[u8] = b"-----BEGIN ";
+const DER_SEQUENCE: u8 = 0x30;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+#[derive(Args)]
+pub struct Audit {
+    /// certificates, keys and DH parameters (PEM, or a DER certificate)
+    /// and checksum files to inspect.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// print the findings as a JSON array.
+    #[arg(long)]
+    json: bool,
+    /// print the findings as csv or tsv rows (file, subject, severity,
+    /// message) after a header row.
+    #[arg(long, value_name = "FORMAT", conflicts_with = "json")]
+    format: Option<report::Format>,
+    /// exit with status 1 when a finding is at least SEVERITY
+    /// (info, low, medium or high).
+    #[arg(long, value_name = "SEVERITY", default_value = "high")]
+    fail_on: Severity,
+}
+
+/// finding of `subject` in `file`, like the 2nd certificate of a bundle.
+struct Report {
+    file: String,
+    subject: String,
+    finding: Finding,
+}
+
+impl Audit {
+    pub fn exec(self) -> Result<()> {
+        let now = SystemTime::now();
+        let mut reports = Vec::new();
+        let mut failed = 0;
+        for file in self.files.iter() {
+            match inspect(file, now) {
+                Ok(found) => reports.extend(found.into_iter().map(|(subject, finding)| Report {
+                    file: file.display().to_string(),
+                    subject,
+                    finding,
+                })),
+                Err(err) => {
+                    eprintln!("{:?}: {}", file, err);
+                    failed += 1;
+                }
+            }
+        }
+
+        if self.json {
+            println!("{}", Value::Array(reports.iter().map(json).collect()));
+        } else if let Some(format) = self.format {
+            let mut out = io::stdout().lock();
+            format.row(&mut out, &["file", "subject", "severity", "message"])?;
+            for r in reports.iter() {
+                let severity = r.finding.severity.to_string();
+                format.row(
+                    &mut out,
+                    &[&r.file, &r.subject, &severity, &r.finding.message],
+                )?;
+            }
+        } else {
+            for r in reports.iter() {
+                println!(
+                    "{}: {}: {}: {}",
+                    r.file, r.finding.severity, r.subject, r.finding.message
+                );
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("WARNING: {} FAILS", failed).into());
+        }
+        let failing = reports
+            .iter()
+            .filter(|r| r.finding.severity >= self.fail_on)
+            .count();
+        if failing > 0 {
+            return Err(
+                format!("WARNING: {} findings of {} or above", failing, self.fail_on).into(),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn json(r: &Report) -> Value {
+    Value::Object(vec![
+        ("file".into(), r.file.as_str().into()),
+        ("subject".into(), r.subject.as_str().into()),
+        (
+            "severity".into(),
+            r.finding.severity.to_string().as_str().into(),
+        ),
+        ("message".into(), r.finding.message.as_str().into()),
+    ])
+}
+
+/// findings of `file` with their subjects: PEM blocks, a DER certificate,
+/// or the lines of a checksum file.
+fn inspect(file: &PathBuf, now: SystemTime) -> Result<Vec<(String, Finding)>> {
+    let mut data = Vec::new();
+    input::Input::new(file)?.read_to_end(&mut data)?;
+
+    if data.windows(PEM_BEGIN.len()).any(|w| w == PEM_BEGIN) {
+        let mut found = Vec::new();
+        for (i, block) in pem::parse(&data[..])?.iter().enumerate() {
+            let subject = format!("{} {}", block.label, i + 1);
+            if let Some(findings) = weak::block(&block.label, &block.data, now) {
+                let findings = findings.map_err(|err| format!("{}: {}", subject, err))?;
+                found.extend(findings.into_iter().map(|f| (subject.clone(), f)));
+            }
+        }
+        return Ok(found);
+    }
+    if data.first() == Some(&DER_SEQUENCE) {
+        let findings = weak::certificate(&data, now)?;
+        return Ok(findings
+            .into_iter()
+            .map(|f| ("CERTIFICATE".to_string(), f))
+            .collect());
+    }
+
+    let text = String::from_utf8(data).map_err(|_| "neither PEM, DER nor a checksum file")?;
+    let md5 = text
+        .lines()
+        .filter_map(|line| check::parse(line).ok())
+        .filter(|entry| entry.digest.func() == Func::MD5)
+        .count();
+    if md5 == 0 {
+        return Ok(Vec::new());
+    }
+    let finding = Finding {
+        severity: Severity::Medium,
+        message: format!("{} MD5 digests, which are open to collisions", md5),
+    };
+    Ok(vec![("checksums".to_string(), finding)])
+}
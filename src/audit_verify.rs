@@ -0,0 +1,25 @@
+use clap::Args;
+use std::error;
+use std::io;
+use std::path::PathBuf;
+
+use crate::libs::audit;
+use crate::libs::input;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+#[derive(Args)]
+pub struct AuditVerify {
+    /// audit log written by --audit-log (optional; default is stdin).
+    file: Option<PathBuf>,
+}
+
+impl AuditVerify {
+    pub fn exec(self) -> Result<()> {
+        let f = self.file.unwrap_or(PathBuf::from(input::STDIN_PATH));
+        let r = io::BufReader::new(input::Input::new(&f)?);
+        let records = audit::verify(r).map_err(|err| format!("{:?}: {}", f, err))?;
+        println!("{:?}: {} records, chain intact", f, records);
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+pub mod decoder;
+pub mod encoder;
+
+use clap::Args;
+use std::error;
+use std::io::{self, Write};
+use std::path;
+
+use crate::base64::new_liner;
+use crate::libs::input;
+use crate::libs::output;
+
+/// symbols of the 32 values (RFC 4648).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alphabet {
+    /// 'A' to 'Z' and '2' to '7'.
+    Standard,
+    /// '0' to '9' and 'A' to 'V' of "base32hex" (RFC 4648 section 7).
+    Hex,
+}
+
+#[derive(Args)]
+pub struct Base32 {
+    #[arg(short, long)]
+    decode: bool,
+    /// when decoding, skip bytes which are not in the alphabet.
+    #[arg(short, long, requires = "decode")]
+    ignore_garbage: bool,
+
+    /// wrap encoded lines after COLS characters; 0 disables wrapping.
+    #[arg(short, long, value_name = "COLS", default_value_t = 76)]
+    wrap: usize,
+
+    /// use the extended hex alphabet ('0' to '9' and 'A' to 'V').
+    #[arg(long)]
+    hex: bool,
+    /// omit the trailing '=' padding when encoding.
+    #[arg(long)]
+    no_padding: bool,
+
+    /// File to encode (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    /// Use ./- for a file named -, and -- to end options before FILE starting with -.
+    file: Option<path::PathBuf>,
+
+    /// read inherited file descriptor N instead of FILE.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(i32).range(0..), conflicts_with = "file")]
+    fd: Option<i32>,
+
+    /// write output to FILE instead of standard output.
+    /// the file is replaced atomically when all input is processed.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<path::PathBuf>,
+    /// octal permissions of the --out file (before umask).
+    #[arg(long, value_name = "MODE", value_parser = output::parse_mode, default_value = "666")]
+    mode: u32,
+}
+
+impl Base32 {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let mut input = match self.fd {
+            Some(fd) => input::Input::from_fd(fd)?,
+            None => {
+                let f = self.file.unwrap_or(path::PathBuf::from(input::STDIN_PATH));
+                input::Input::new(&f)?
+            }
+        };
+
+        let out = self.out.unwrap_or(path::PathBuf::from(output::STDOUT_PATH));
+        let mut output = output::Output::with_mode(&out, self.mode)?;
+
+        let alphabet = match self.hex {
+            true => Alphabet::Hex,
+            false => Alphabet::Standard,
+        };
+        if self.decode {
+            let mut decoder = decoder::Decoder::with_alphabet(alphabet, &mut output)
+                .ignore_garbage(self.ignore_garbage);
+            io::copy(&mut input, &mut decoder)?;
+            decoder.finish()?;
+        } else {
+            let new_liner = new_liner::NewLiner::with_line_size(self.wrap, &mut output);
+            let mut encoder =
+                encoder::Encoder::with_alphabet(alphabet, !self.no_padding, new_liner);
+            io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+            drop(encoder);
+            if self.wrap > 0 {
+                writeln!(output)?;
+            }
+        }
+        output.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use std::io;
+
+use super::Alphabet;
+
+const INPUT_CHUNK_BYTE_SIZE: usize = 8;
+const OUTPUT_CHUNK_BYTE_SIZE: usize = 5;
+const PAD: u8 = b'=';
+
+/// value of base32 symbol or `None` if `c` is not in the alphabet.
+fn decode_symbol(c: u8, alphabet: Alphabet) -> Option<u8> {
+    match (c, alphabet) {
+        (b'A'..=b'Z', Alphabet::Standard) => Some(c - b'A'),
+        (b'2'..=b'7', Alphabet::Standard) => Some(c - b'2' + 26),
+        (b'0'..=b'9', Alphabet::Hex) => Some(c - b'0'),
+        (b'A'..=b'V', Alphabet::Hex) => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// bytes decoded from a last chunk of `symbols` symbols, or `None` when no
+/// encoder ends a chunk with that many.
+fn decoded_len(symbols: usize) -> Option<usize> {
+    match symbols {
+        2 => Some(1),
+        4 => Some(2),
+        5 => Some(3),
+        7 => Some(4),
+        8 => Some(5),
+        _ => None,
+    }
+}
+
+/// base32 decoder, which writes decoded data to the inner writer.
+/// line breaks in the input are skipped.
+pub struct Decoder<W: io::Write> {
+    buf: [u8; INPUT_CHUNK_BYTE_SIZE],
+    buf_seed: usize,
+    /// symbols of the current chunk before its padding.
+    symbols: usize,
+    alphabet: Alphabet,
+    ignore_garbage: bool,
+    writer: Option<W>,
+}
+
+impl<W: io::Write> io::Write for Decoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.writer.is_none() {
+            panic!("Writer must be present");
+        }
+
+        for &c in buf {
+            self.push(c)?;
+            if self.buf_seed == INPUT_CHUNK_BYTE_SIZE {
+                self.flush_chunk()?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer
+            .as_mut()
+            .expect("Writer must be present")
+            .flush()
+    }
+}
+
+impl<W: io::Write> Drop for Decoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+impl<W: io::Write> Decoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_alphabet(Alphabet::Standard, writer)
+    }
+
+    /// decoder of symbols of `alphabet`.
+    pub fn with_alphabet(alphabet: Alphabet, writer: W) -> Self {
+        Decoder {
+            buf: [0; INPUT_CHUNK_BYTE_SIZE],
+            buf_seed: 0,
+            symbols: 0,
+            alphabet,
+            ignore_garbage: false,
+            writer: Some(writer),
+        }
+    }
+
+    /// with `ignore` bytes which are not in the alphabet are skipped
+    /// instead of failing, like GNU base32 --ignore-garbage.
+    pub fn ignore_garbage(mut self, ignore: bool) -> Self {
+        self.ignore_garbage = ignore;
+        self
+    }
+
+    /// decode the rest of input. unpadded last chunk is accepted.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        if self.buf_seed != 0 {
+            self.buf[self.buf_seed..].fill(0);
+            self.buf_seed = INPUT_CHUNK_BYTE_SIZE;
+            if let Err(err) = self.flush_chunk() {
+                self.writer = None;
+                return Err(err);
+            }
+        }
+        self.writer.take().unwrap().flush()
+    }
+
+    fn push(&mut self, c: u8) -> io::Result<()> {
+        match c {
+            b'\n' | b'\r' => Ok(()),
+            PAD => {
+                if self.buf_seed == 0 {
+                    return Err(invalid_data("unexpected base32 padding"));
+                }
+                self.buf[self.buf_seed] = 0;
+                self.buf_seed += 1;
+                Ok(())
+            }
+            _ => {
+                let x = match decode_symbol(c, self.alphabet) {
+                    Some(x) => x,
+                    None if self.ignore_garbage => return Ok(()),
+                    None => return Err(invalid_data("invalid base32 character")),
+                };
+                if self.symbols != self.buf_seed {
+                    return Err(invalid_data("base32 data after padding"));
+                }
+                self.buf[self.buf_seed] = x;
+                self.buf_seed += 1;
+                self.symbols += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        let len =
+            decoded_len(self.symbols).ok_or_else(|| invalid_data("truncated base32 input"))?;
+        let bits = self.buf.iter().fold(0u64, |acc, &x| acc << 5 | x as u64);
+        let bytes = bits.to_be_bytes();
+        let decode_data = &bytes[bytes.len() - OUTPUT_CHUNK_BYTE_SIZE..];
+        self.buf_seed = 0;
+        self.symbols = 0;
+
+        let writer = self.writer.as_mut().unwrap();
+        writer.write_all(&decode_data[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::encoder::Encoder;
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::io::Write;
+    use std::vec::Vec;
+
+    fn decode(alphabet: Alphabet, data: &str) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::with_alphabet(alphabet, &mut out);
+        decoder.write_all(data.as_bytes())?;
+        decoder.finish()?;
+        drop(decoder);
+        Ok(out)
+    }
+
+    macro_rules! decoder {
+        ($name:ident,$alphabet:expr,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = decode($alphabet, $data)
+                    .ok()
+                    .map(|out| String::from_utf8(out).unwrap());
+                let expected: Option<&str> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected.map(String::from), actual);
+            }
+        };
+    }
+
+    decoder!(empty, Alphabet::Standard, "", Some(""));
+    decoder!(f, Alphabet::Standard, "MY======", Some("f"));
+    decoder!(foob, Alphabet::Standard, "MZXW6YQ=", Some("foob"));
+    decoder!(
+        foobar,
+        Alphabet::Standard,
+        "MZXW6YTB\nOI======\n",
+        Some("foobar")
+    );
+    decoder!(unpadded, Alphabet::Standard, "MZXW6YTBOI", Some("foobar"));
+    decoder!(hex, Alphabet::Hex, "CPNMUOJ1E8======", Some("foobar"));
+    decoder!(truncated, Alphabet::Standard, "MZX", None);
+    decoder!(invalid_char, Alphabet::Standard, "MZ1W6===", None);
+    decoder!(early_padding, Alphabet::Standard, "=Y======", None);
+    decoder!(data_after_padding, Alphabet::Standard, "MY=Y====", None);
+
+    #[test]
+    fn ignore_garbage() {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out).ignore_garbage(true);
+        decoder.write_all(b"\t: MZ XW*6===\n").unwrap();
+        decoder.finish().unwrap();
+        drop(decoder);
+        assert_eq!(out, b"foo");
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(data in vec(any::<u8>(), 0..256), hex: bool, pad: bool) {
+            let alphabet = if hex { Alphabet::Hex } else { Alphabet::Standard };
+            let mut encoded = Vec::new();
+            Encoder::with_alphabet(alphabet, pad, &mut encoded).write_all(&data).unwrap();
+
+            let actual = decode(alphabet, &String::from_utf8(encoded).unwrap()).unwrap();
+
+            prop_assert_eq!(data, actual);
+        }
+    }
+}
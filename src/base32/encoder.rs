@@ -0,0 +1,142 @@
+use std::io;
+
+use super::Alphabet;
+
+const INPUT_CHUNK_BYTE_SIZE: usize = 5;
+const OUTPUT_CHUNK_BYTE_SIZE: usize = 8;
+const CODE_VEC: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+/// "base32hex" alphabet of RFC 4648, which keeps the sort order of the data.
+const HEX_CODE_VEC: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// base32 encoder, which writes encoded data to the inner writer.
+pub struct Encoder<W: io::Write> {
+    buf: [u8; INPUT_CHUNK_BYTE_SIZE],
+    buf_seed: usize,
+    code_vec: &'static [u8; 32],
+    pad: bool,
+    writer: Option<W>,
+}
+
+impl<W: io::Write> io::Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(INPUT_CHUNK_BYTE_SIZE - self.buf_seed);
+        self.buf[self.buf_seed..self.buf_seed + n].copy_from_slice(&buf[..n]);
+        self.buf_seed += n;
+        if self.buf_seed == INPUT_CHUNK_BYTE_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer
+            .as_mut()
+            .expect("Writer must be present")
+            .flush()
+    }
+}
+
+impl<W: io::Write> Drop for Encoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+impl<W: io::Write> Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_alphabet(Alphabet::Standard, true, writer)
+    }
+
+    /// encoder with symbols of `alphabet`. without `pad` the last chunk
+    /// is not filled up with '='.
+    pub fn with_alphabet(alphabet: Alphabet, pad: bool, writer: W) -> Self {
+        Encoder {
+            buf: [0; INPUT_CHUNK_BYTE_SIZE],
+            buf_seed: 0,
+            code_vec: match alphabet {
+                Alphabet::Standard => CODE_VEC,
+                Alphabet::Hex => HEX_CODE_VEC,
+            },
+            pad,
+            writer: Some(writer),
+        }
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        if self.buf_seed != 0 {
+            self.flush_chunk()?;
+        }
+        self.writer.take().unwrap().flush()
+    }
+
+    /// encodes the buffered bytes, padding a short last chunk.
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        let len = self.buf_seed;
+        self.buf[len..].fill(0);
+        let bits = self.buf.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        let mut encode_data = [b'='; OUTPUT_CHUNK_BYTE_SIZE];
+        // symbols holding at least one bit of the data
+        let symbols = (len * 8).div_ceil(5);
+        for (i, c) in encode_data[..symbols].iter_mut().enumerate() {
+            *c = self.code_vec[(bits >> (35 - 5 * i) & 0x1F) as usize];
+        }
+        self.buf_seed = 0;
+
+        let end = match self.pad {
+            true => OUTPUT_CHUNK_BYTE_SIZE,
+            false => symbols,
+        };
+        let writer = self.writer.as_mut().expect("Writer must be present");
+        writer.write_all(&encode_data[..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::vec::Vec;
+
+    macro_rules! encoder {
+        ($name:ident,$alphabet:expr,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut out = Vec::new();
+                Encoder::with_alphabet($alphabet, true, &mut out)
+                    .write_all($data)
+                    .unwrap();
+
+                let actual = String::from_utf8(out).unwrap();
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", $expected);
+
+                assert_eq!($expected, actual);
+            }
+        };
+    }
+
+    // RFC 4648 section 10.
+    encoder!(empty, Alphabet::Standard, b"", "");
+    encoder!(f, Alphabet::Standard, b"f", "MY======");
+    encoder!(fo, Alphabet::Standard, b"fo", "MZXQ====");
+    encoder!(foo, Alphabet::Standard, b"foo", "MZXW6===");
+    encoder!(foob, Alphabet::Standard, b"foob", "MZXW6YQ=");
+    encoder!(fooba, Alphabet::Standard, b"fooba", "MZXW6YTB");
+    encoder!(foobar, Alphabet::Standard, b"foobar", "MZXW6YTBOI======");
+    encoder!(hex_f, Alphabet::Hex, b"f", "CO======");
+    encoder!(hex_foob, Alphabet::Hex, b"foob", "CPNMUOG=");
+    encoder!(hex_foobar, Alphabet::Hex, b"foobar", "CPNMUOJ1E8======");
+
+    #[test]
+    fn unpadded() {
+        let mut out = Vec::new();
+        Encoder::with_alphabet(Alphabet::Standard, false, &mut out)
+            .write_all(b"foobar")
+            .unwrap();
+        assert_eq!(out, b"MZXW6YTBOI");
+    }
+}
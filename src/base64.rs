@@ -1,33 +1,100 @@
-mod encoder;
-mod new_liner;
+pub mod decoder;
+pub mod encoder;
+pub mod new_liner;
 
 use clap::Args;
-use std::io;
-use std::path;
 use std::error;
+use std::io::{self, Write};
+use std::path;
 
 use crate::libs::input;
+use crate::libs::output;
+
+/// symbols for the values 62 and 63.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alphabet {
+    /// '+' and '/'.
+    Standard,
+    /// '-' and '_' of "base64url" (RFC 4648 section 5).
+    Url,
+}
 
 #[derive(Args)]
 pub struct Base64 {
     #[arg(short, long)]
     decode: bool,
+    /// when decoding, skip bytes which are not in the alphabet.
+    #[arg(short, long, requires = "decode")]
+    ignore_garbage: bool,
+    /// when decoding, reject input that is not canonical: missing or short
+    /// padding, non-zero unused bits, or data after padding.
+    #[arg(long, requires = "decode")]
+    strict: bool,
+
+    /// wrap encoded lines after COLS characters; 0 disables wrapping.
+    #[arg(short, long, value_name = "COLS", default_value_t = 76)]
+    wrap: usize,
+    /// MIME (RFC 2045) output for email bodies: 76 character lines ending
+    /// with CRLF. decoding always accepts CRLF.
+    #[arg(long, conflicts_with = "wrap")]
+    mime: bool,
 
+    /// use the URL and file name safe alphabet ('-' and '_' instead of '+' and '/').
+    #[arg(long)]
+    url: bool,
+    /// omit the trailing '=' padding when encoding.
+    #[arg(long)]
+    no_padding: bool,
+
+    /// File to encode (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    /// Use ./- for a file named -, and -- to end options before FILE starting with -.
     file: Option<path::PathBuf>,
+
+    /// read inherited file descriptor N instead of FILE.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(i32).range(0..), conflicts_with = "file")]
+    fd: Option<i32>,
+
+    /// write output to FILE instead of standard output.
+    /// the file is replaced atomically when all input is processed.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<path::PathBuf>,
+    /// octal permissions of the --out file (before umask).
+    #[arg(long, value_name = "MODE", value_parser = output::parse_mode, default_value = "666")]
+    mode: u32,
 }
 
 impl Base64 {
     pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
-        let f = self.file.unwrap_or(path::PathBuf::from("-"));
-        let mut input = input::Input::new(&f)?;
+        let mut input = match self.fd {
+            Some(fd) => input::Input::from_fd(fd)?,
+            None => {
+                let f = self.file.unwrap_or(path::PathBuf::from(input::STDIN_PATH));
+                input::Input::new(&f)?
+            }
+        };
 
-        let output = io::stdout().lock();
+        let out = self.out.unwrap_or(path::PathBuf::from(output::STDOUT_PATH));
+        let mut output = output::Output::with_mode(&out, self.mode)?;
 
+        let alphabet = match self.url {
+            true => Alphabet::Url,
+            false => Alphabet::Standard,
+        };
         if self.decode {
-            println!("base64 decode");
+            let mut decoder = decoder::Decoder::with_alphabet(alphabet, &mut output)
+                .ignore_garbage(self.ignore_garbage)
+                .strict(self.strict);
+            io::copy(&mut input, &mut decoder)?;
+            decoder.finish()?;
         } else {
-            let new_liner = new_liner::NewLiner::with_line_size(76, output);
-            let mut encoder = encoder::Encoder::new(new_liner);
+            let new_line = match self.mime {
+                true => new_liner::CRLF,
+                false => new_liner::LF,
+            };
+            let new_liner = new_liner::NewLiner::with_new_line(self.wrap, new_line, &mut output);
+            let mut encoder =
+                encoder::Encoder::with_alphabet(alphabet, !self.no_padding, new_liner);
 
             if let Err(err) = io::copy(&mut input, &mut encoder) {
                 eprintln!("{}", err);
@@ -35,8 +102,12 @@ impl Base64 {
             if let Err(err) = encoder.finish() {
                 eprintln!("{}", err);
             }
-            println!("");
+            drop(encoder);
+            if self.wrap > 0 {
+                output.write_all(new_line)?;
+            }
         }
+        output.commit()?;
         Ok(())
     }
 }
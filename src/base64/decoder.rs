@@ -0,0 +1,327 @@
+use std::io;
+
+use super::Alphabet;
+
+const INPUT_CHUNK_BYTE_SIZE: usize = 4;
+const OUTPUT_CHUNK_BYTE_SIZE: usize = 3;
+const PAD: u8 = b'=';
+
+/// value of base64 symbol or `None` if `c` is not in the alphabet.
+fn decode_symbol(c: u8, alphabet: Alphabet) -> Option<u8> {
+    match (c, alphabet) {
+        (b'A'..=b'Z', _) => Some(c - b'A'),
+        (b'a'..=b'z', _) => Some(c - b'a' + 26),
+        (b'0'..=b'9', _) => Some(c - b'0' + 52),
+        (b'+', Alphabet::Standard) | (b'-', Alphabet::Url) => Some(62),
+        (b'/', Alphabet::Standard) | (b'_', Alphabet::Url) => Some(63),
+        _ => None,
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// base64 decoder, which writes decoded data to the inner writer.
+/// line breaks in the input are skipped.
+pub struct Decoder<W: io::Write> {
+    buf: [u8; INPUT_CHUNK_BYTE_SIZE],
+    buf_seed: usize,
+    padding: usize,
+    decode_data: [u8; OUTPUT_CHUNK_BYTE_SIZE],
+    alphabet: Alphabet,
+    ignore_garbage: bool,
+    strict: bool,
+    /// a padded chunk was decoded, which ends the data.
+    ended: bool,
+    writer: Option<W>,
+}
+
+impl<W: io::Write> io::Write for Decoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.writer.is_none() {
+            panic!("Writer must be present");
+        }
+
+        for &c in buf {
+            self.push(c)?;
+            if self.buf_seed == INPUT_CHUNK_BYTE_SIZE {
+                self.flush_chunk()?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer
+            .as_mut()
+            .expect("Writer must be present")
+            .flush()
+    }
+}
+
+impl<W: io::Write> Drop for Decoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+impl<W: io::Write> Decoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_alphabet(Alphabet::Standard, writer)
+    }
+
+    /// decoder of symbols of `alphabet`.
+    pub fn with_alphabet(alphabet: Alphabet, writer: W) -> Self {
+        Decoder {
+            buf: [0; INPUT_CHUNK_BYTE_SIZE],
+            buf_seed: 0,
+            padding: 0,
+            decode_data: [0; OUTPUT_CHUNK_BYTE_SIZE],
+            alphabet,
+            ignore_garbage: false,
+            strict: false,
+            ended: false,
+            writer: Some(writer),
+        }
+    }
+
+    /// with `ignore` bytes which are not in the alphabet are skipped
+    /// instead of failing, like GNU base64 --ignore-garbage.
+    pub fn ignore_garbage(mut self, ignore: bool) -> Self {
+        self.ignore_garbage = ignore;
+        self
+    }
+
+    /// with `strict` the input must be canonical: the last chunk padded to
+    /// four symbols, unused bits of the last symbol zero, and no data
+    /// after a padded chunk.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// decode the rest of input. unpadded last chunk is accepted
+    /// unless strict.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+
+        if self.buf_seed == 1 {
+            self.writer = None;
+            return Err(invalid_data("truncated base64 input"));
+        }
+        if self.strict && self.buf_seed != 0 {
+            self.writer = None;
+            return Err(invalid_data(match self.padding {
+                0 => "missing base64 padding",
+                _ => "incorrect base64 padding length",
+            }));
+        }
+        if self.buf_seed != 0 {
+            self.padding += INPUT_CHUNK_BYTE_SIZE - self.buf_seed;
+            self.buf[self.buf_seed..].fill(0);
+            self.buf_seed = INPUT_CHUNK_BYTE_SIZE;
+            self.flush_chunk()?;
+        }
+
+        let mut writer = self.writer.take().unwrap();
+        writer.flush()
+    }
+
+    fn push(&mut self, c: u8) -> io::Result<()> {
+        match c {
+            b'\n' | b'\r' => Ok(()),
+            _ if self.ended && (c == PAD || decode_symbol(c, self.alphabet).is_some()) => {
+                Err(invalid_data("base64 data after padding"))
+            }
+            PAD => {
+                // padding may only fill the last two places of the chunk
+                if self.buf_seed < 2 {
+                    return Err(invalid_data("unexpected base64 padding"));
+                }
+                self.buf[self.buf_seed] = 0;
+                self.buf_seed += 1;
+                self.padding += 1;
+                Ok(())
+            }
+            _ => {
+                let x = match decode_symbol(c, self.alphabet) {
+                    Some(x) => x,
+                    None if self.ignore_garbage => return Ok(()),
+                    None => return Err(invalid_data("invalid base64 character")),
+                };
+                if self.padding != 0 {
+                    return Err(invalid_data("base64 data after padding"));
+                }
+                self.buf[self.buf_seed] = x;
+                self.buf_seed += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn flush_chunk(&mut self) -> io::Result<()> {
+        if self.strict && self.padding > 0 {
+            // bits of the last symbol beyond the decoded bytes
+            let last = self.buf[INPUT_CHUNK_BYTE_SIZE - 1 - self.padding];
+            let unused = (1 << (2 * self.padding)) - 1;
+            if last & unused != 0 {
+                self.writer = None;
+                return Err(invalid_data("non-zero trailing bits in base64 input"));
+            }
+            self.ended = true;
+        }
+        self.decode();
+        let len = OUTPUT_CHUNK_BYTE_SIZE - self.padding;
+        self.buf_seed = 0;
+        self.padding = 0;
+
+        let writer = self.writer.as_mut().unwrap();
+        writer.write_all(&self.decode_data[..len])
+    }
+
+    fn decode(&mut self) {
+        self.decode_data[0] = (self.buf[0] << 2) | (self.buf[1] >> 4);
+        self.decode_data[1] = (self.buf[1] << 4) | (self.buf[2] >> 2);
+        self.decode_data[2] = (self.buf[2] << 6) | self.buf[3];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::base64::Engine as _;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::io::Write;
+    use std::vec::Vec;
+
+    fn decode(data: &str) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out);
+        decoder.write_all(data.as_bytes())?;
+        decoder.finish()?;
+        drop(decoder);
+        Ok(out)
+    }
+
+    macro_rules! decoder {
+        ($name:ident,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = decode($data)
+                    .ok()
+                    .map(|out| String::from_utf8(out).unwrap());
+                let expected: Option<&str> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected.map(String::from), actual);
+            }
+        };
+    }
+
+    decoder!(empty, "", Some(""));
+    decoder!(a, "YQ==", Some("a"));
+    decoder!(partial_padding, "YQ=", Some("a"));
+    decoder!(aa, "YWE=", Some("aa"));
+    decoder!(aaa, "YWFh", Some("aaa"));
+    decoder!(aaaa, "YWFhYQ==", Some("aaaa"));
+    decoder!(hello, "aGVsbG8=", Some("hello"));
+    decoder!(unpadded, "aGVsbG8", Some("hello"));
+    decoder!(lines, "YW\nFh\r\nYQ==\n", Some("aaaa"));
+    decoder!(truncated, "YWFhY", None);
+    decoder!(invalid_char, "YW*h", None);
+    decoder!(early_padding, "Y===", None);
+    decoder!(data_after_padding, "YQ=a", None);
+    decoder!(url_char, "-_8", None);
+
+    fn decode_strict(data: &str) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out).strict(true);
+        decoder.write_all(data.as_bytes())?;
+        decoder.finish()?;
+        drop(decoder);
+        Ok(out)
+    }
+
+    macro_rules! strict {
+        ($name:ident,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = decode_strict($data).map_err(|err| err.to_string());
+                let expected: Result<&[u8], &str> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected.map(<[u8]>::to_vec).map_err(String::from), actual);
+            }
+        };
+    }
+
+    strict!(strict_padded, "YWFh\nYQ==\n", Ok(b"aaaa"));
+    strict!(strict_unpadded, "aGVsbG8", Err("missing base64 padding"));
+    strict!(
+        strict_short_padding,
+        "YQ=",
+        Err("incorrect base64 padding length")
+    );
+    strict!(
+        strict_trailing_bits,
+        "YR==",
+        Err("non-zero trailing bits in base64 input")
+    );
+    strict!(
+        strict_trailing_bits_one_pad,
+        "YWF=",
+        Err("non-zero trailing bits in base64 input")
+    );
+    strict!(
+        strict_embedded_padding,
+        "YQ==YQ==",
+        Err("base64 data after padding")
+    );
+
+    #[test]
+    fn ignore_garbage() {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out).ignore_garbage(true);
+        decoder.write_all(b"\t: YW Fh*YQ==\t\n").unwrap();
+        decoder.finish().unwrap();
+        drop(decoder);
+        assert_eq!(out, b"aaaa");
+    }
+
+    #[test]
+    fn url() {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::with_alphabet(Alphabet::Url, &mut out);
+        decoder.write_all(b"-_8").unwrap();
+        decoder.finish().unwrap();
+        drop(decoder);
+        assert_eq!(out, [0xFB, 0xFF]);
+    }
+
+    proptest! {
+        #[test]
+        fn reference(data in vec(any::<u8>(), 0..256), chunk in 1usize..16) {
+            let encoded = ::base64::engine::general_purpose::STANDARD.encode(&data);
+
+            let mut actual = Vec::new();
+            {
+                let mut decoder = Decoder::new(&mut actual);
+                for part in encoded.as_bytes().chunks(chunk) {
+                    decoder.write_all(part).unwrap();
+                }
+                decoder.finish().unwrap();
+            }
+
+            prop_assert_eq!(data, actual);
+        }
+    }
+}
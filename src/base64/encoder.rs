@@ -1,5 +1,7 @@
 use std::io;
 
+use super::Alphabet;
+
 const INPUT_CHUNK_BYTE_SIZE: usize = 3;
 const OUTPUT_CHUNK_BYTE_SIZE: usize = 4;
 const PADDING: [u8; INPUT_CHUNK_BYTE_SIZE] = [0x00, 0x00, 0x00];
@@ -9,11 +11,20 @@ const CODE_VEC: [u8; 64] = [
     b'g', b'h', b'i', b'j', b'k', b'l', b'm', b'n', b'o', b'p', b'q', b'r', b's', b't', b'u', b'v',
     b'w', b'x', b'y', b'z', b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'+', b'/',
 ];
+/// "base64url" alphabet of RFC 4648, safe in URLs and file names.
+const URL_CODE_VEC: [u8; 64] = [
+    b'A', b'B', b'C', b'D', b'E', b'F', b'G', b'H', b'I', b'J', b'K', b'L', b'M', b'N', b'O', b'P',
+    b'Q', b'R', b'S', b'T', b'U', b'V', b'W', b'X', b'Y', b'Z', b'a', b'b', b'c', b'd', b'e', b'f',
+    b'g', b'h', b'i', b'j', b'k', b'l', b'm', b'n', b'o', b'p', b'q', b'r', b's', b't', b'u', b'v',
+    b'w', b'x', b'y', b'z', b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9', b'-', b'_',
+];
 
 pub struct Encoder<W: io::Write> {
     buf: [u8; INPUT_CHUNK_BYTE_SIZE],
     buf_seed: usize,
     encode_data: [u8; OUTPUT_CHUNK_BYTE_SIZE],
+    code_vec: &'static [u8; 64],
+    pad: bool,
     writer: Option<W>,
 }
 
@@ -23,15 +34,13 @@ impl<W: io::Write> io::Write for Encoder<W> {
             panic!("Writer must be present");
         }
 
-        let consume_bytes = self.write_buf(&buf);
+        let consume_bytes = self.write_buf(buf);
         if self.is_buf_full() {
             self.encode();
             self.buf_seed = 0;
 
             let writer = self.writer.as_mut().unwrap();
-            if let Err(err) = writer.write_all(&self.encode_data) {
-                return Err(err);
-            }
+            writer.write_all(&self.encode_data)?;
         }
 
         Ok(consume_bytes)
@@ -53,10 +62,21 @@ impl<W: io::Write> Drop for Encoder<W> {
 
 impl<W: io::Write> Encoder<W> {
     pub fn new(writer: W) -> Self {
+        Self::with_alphabet(Alphabet::Standard, true, writer)
+    }
+
+    /// encoder with symbols of `alphabet`. without `pad` the last chunk
+    /// is not filled up with '='.
+    pub fn with_alphabet(alphabet: Alphabet, pad: bool, writer: W) -> Self {
         Encoder {
             buf: [0; INPUT_CHUNK_BYTE_SIZE],
             buf_seed: 0,
             encode_data: [0; OUTPUT_CHUNK_BYTE_SIZE],
+            code_vec: match alphabet {
+                Alphabet::Standard => &CODE_VEC,
+                Alphabet::Url => &URL_CODE_VEC,
+            },
+            pad,
             writer: Some(writer),
         }
     }
@@ -75,12 +95,12 @@ impl<W: io::Write> Encoder<W> {
 
             self.encode();
             self.buf_seed = 0;
-            for i in self.encode_data.len() - buf_free_size..self.encode_data.len() {
-                self.encode_data[i] = b'=';
-            }
-
-            if let Err(err) = writer.write_all(&self.encode_data) {
-                return Err(err);
+            let len = self.encode_data.len() - buf_free_size;
+            if self.pad {
+                self.encode_data[len..].fill(b'=');
+                writer.write_all(&self.encode_data)?;
+            } else {
+                writer.write_all(&self.encode_data[..len])?;
             }
         }
 
@@ -96,7 +116,7 @@ impl<W: io::Write> Encoder<W> {
             buf_free_size
         } else {
             let new_buf_seed = self.buf_seed + input.len();
-            self.buf[self.buf_seed..new_buf_seed].clone_from_slice(&input);
+            self.buf[self.buf_seed..new_buf_seed].clone_from_slice(input);
             self.buf_seed = new_buf_seed;
 
             input.len()
@@ -109,10 +129,10 @@ impl<W: io::Write> Encoder<W> {
         let idx_2 = ((self.buf[1] & 0b0000_1111) << 2) | ((self.buf[2] & 0b1100_0000) >> 6);
         let idx_3 = self.buf[2] & 0b0011_1111;
 
-        self.encode_data[0] = CODE_VEC[idx_0 as usize];
-        self.encode_data[1] = CODE_VEC[idx_1 as usize];
-        self.encode_data[2] = CODE_VEC[idx_2 as usize];
-        self.encode_data[3] = CODE_VEC[idx_3 as usize];
+        self.encode_data[0] = self.code_vec[idx_0 as usize];
+        self.encode_data[1] = self.code_vec[idx_1 as usize];
+        self.encode_data[2] = self.code_vec[idx_2 as usize];
+        self.encode_data[3] = self.code_vec[idx_3 as usize];
     }
 
     fn is_buf_full(&self) -> bool {
@@ -123,6 +143,9 @@ impl<W: io::Write> Encoder<W> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ::base64::Engine as _;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
     use std::io::Write;
     use std::vec::Vec;
 
@@ -154,4 +177,36 @@ mod tests {
     encoder!(aaa, "aaa", "YWFh");
     encoder!(aaaa, "aaaa", "YWFhYQ==");
     encoder!(hello, "hello", "aGVsbG8=");
+
+    proptest! {
+        #[test]
+        fn reference(data in vec(any::<u8>(), 0..256), chunk in 1usize..16) {
+            let mut out = Vec::new();
+            {
+                let mut encoder = Encoder::new(&mut out);
+                for part in data.chunks(chunk) {
+                    encoder.write_all(part).unwrap();
+                }
+            }
+
+            let actual = String::from_utf8(out).unwrap();
+            let expected = ::base64::engine::general_purpose::STANDARD.encode(&data);
+
+            prop_assert_eq!(expected, actual);
+        }
+
+        #[test]
+        fn reference_url(data in vec(any::<u8>(), 0..256), pad: bool) {
+            let mut out = Vec::new();
+            Encoder::with_alphabet(Alphabet::Url, pad, &mut out).write_all(&data).unwrap();
+
+            let actual = String::from_utf8(out).unwrap();
+            let expected = match pad {
+                true => ::base64::engine::general_purpose::URL_SAFE.encode(&data),
+                false => ::base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&data),
+            };
+
+            prop_assert_eq!(expected, actual);
+        }
+    }
 }
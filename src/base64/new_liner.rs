@@ -1,29 +1,35 @@
 use std::io;
 use std::marker;
 
-const NEW_LINE: [u8; 1] = [b'\n'];
+pub const LF: &[u8] = b"\n";
+/// line ending of MIME (RFC 2045) and email bodies.
+pub const CRLF: &[u8] = b"\r\n";
 
 pub struct NewLiner<W: io::Write + ?marker::Sized> {
     seed: usize,
     line_size: usize,
+    new_line: &'static [u8],
     writer: W,
 }
 
 impl<W: io::Write + ?marker::Sized> io::Write for NewLiner<W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.line_size == 0 {
+            return self.writer.write(buf);
+        }
         if self.seed == self.line_size {
-            self.writer.write_all(&NEW_LINE)?;
+            self.writer.write_all(self.new_line)?;
             self.seed = 0;
         }
 
         let space = self.line_size - self.seed;
         let buf = if buf.len() < space {
-            &buf
+            buf
         } else {
             &buf[..space]
         };
 
-        let writen = self.writer.write(&buf)?;
+        let writen = self.writer.write(buf)?;
         self.seed += writen;
         Ok(writen)
     }
@@ -34,10 +40,17 @@ impl<W: io::Write + ?marker::Sized> io::Write for NewLiner<W> {
 }
 
 impl<W: io::Write> NewLiner<W> {
+    /// line_size 0 writes everything on one line.
     pub fn with_line_size(line_size: usize, writer: W) -> Self {
+        Self::with_new_line(line_size, LF, writer)
+    }
+
+    /// lines of `line_size` bytes separated by `new_line`.
+    pub fn with_new_line(line_size: usize, new_line: &'static [u8], writer: W) -> Self {
         NewLiner {
             seed: 0,
             line_size,
+            new_line,
             writer,
         }
     }
@@ -77,4 +90,14 @@ mod tests {
     new_liner!(aaa1, 1, "aaa", "a\na\na");
     new_liner!(aaa2, 2, "aaa", "aa\na");
     new_liner!(aaa3, 3, "aaa", "aaa");
+    new_liner!(no_wrap, 0, "aaa", "aaa");
+
+    #[test]
+    fn crlf() {
+        let mut out = Vec::new();
+        NewLiner::with_new_line(2, CRLF, &mut out)
+            .write_all(b"aaaaa")
+            .unwrap();
+        assert_eq!(out, b"aa\r\naa\r\na");
+    }
 }
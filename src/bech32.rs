@@ -0,0 +1,102 @@
+use clap::{Args, Subcommand};
+use std::error;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use crate::libs::bech32::{self, Variant};
+use crate::libs::input;
+use crate::libs::output;
+
+#[derive(Args)]
+pub struct Bech32 {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// encode bytes of FILE with a human readable part
+    Encode(Encode),
+    /// verify the checksum of a string and write its bytes
+    Decode(Decode),
+}
+
+#[derive(Args)]
+struct Encode {
+    /// human readable part, like bc.
+    #[arg(long)]
+    hrp: String,
+
+    /// use the Bech32m checksum (BIP 350) instead of Bech32 (BIP 173).
+    #[arg(short, long)]
+    m: bool,
+
+    /// File to encode (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct Decode {
+    /// Bech32 or Bech32m string (optional; default is the first line of stdin).
+    string: Option<String>,
+
+    /// fail unless the human readable part is HRP.
+    #[arg(long)]
+    hrp: Option<String>,
+
+    /// write output to FILE instead of standard output.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<PathBuf>,
+}
+
+impl Bech32 {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        match self.command {
+            Commands::Encode(cmd) => cmd.exec(),
+            Commands::Decode(cmd) => cmd.exec(),
+        }
+    }
+}
+
+impl Encode {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let file = self.file.unwrap_or(PathBuf::from(input::STDIN_PATH));
+        let mut data = Vec::new();
+        input::Input::new(&file)?.read_to_end(&mut data)?;
+        let variant = match self.m {
+            true => Variant::Bech32m,
+            false => Variant::Bech32,
+        };
+        println!("{}", bech32::encode(&self.hrp, &data, variant)?);
+        Ok(())
+    }
+}
+
+impl Decode {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let s = match self.string {
+            Some(s) => s,
+            None => {
+                let mut s = String::new();
+                input::Input::new(&PathBuf::from(input::STDIN_PATH))?.read_to_string(&mut s)?;
+                s.lines().next().unwrap_or_default().trim().to_string()
+            }
+        };
+        let (hrp, data, _) = bech32::decode(&s).map_err(|err| format!("{}: {}", s, err))?;
+        if let Some(expected) = self.hrp {
+            if !expected.eq_ignore_ascii_case(&hrp) {
+                return Err(
+                    format!("{}: human readable part {}, expected {}", s, hrp, expected).into(),
+                );
+            }
+        }
+
+        let out = self.out.unwrap_or(PathBuf::from(output::STDOUT_PATH));
+        let mut out = output::Output::new(&out)?;
+        out.write_all(&data)?;
+        out.flush()?;
+        out.commit()?;
+        Ok(())
+    }
+}
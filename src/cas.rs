@@ -0,0 +1,93 @@
+use clap::{Args, Subcommand};
+use std::error;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::libs::cas;
+use crate::libs::input;
+use crate::libs::output;
+
+#[derive(Args)]
+pub struct Cas {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// store files and print their digests
+    Put(Put),
+    /// write the blob of a digest, verifying its content
+    Get(Get),
+}
+
+#[derive(Args)]
+struct Put {
+    /// files to store (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    files: Option<Vec<PathBuf>>,
+
+    /// store directory; created when missing.
+    #[arg(long, value_name = "DIR")]
+    store: PathBuf,
+}
+
+#[derive(Args)]
+struct Get {
+    /// digest of the blob, as sha256:HEX or HEX.
+    #[arg(value_parser = cas::parse_digest)]
+    digest: String,
+
+    /// store directory.
+    #[arg(long, value_name = "DIR")]
+    store: PathBuf,
+
+    /// write output to FILE instead of standard output.
+    /// the file is replaced only when the blob is verified.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<PathBuf>,
+}
+
+impl Cas {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        match self.command {
+            Commands::Put(cmd) => cmd.exec(),
+            Commands::Get(cmd) => cmd.exec(),
+        }
+    }
+}
+
+impl Put {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let files = self.files.unwrap_or(vec![PathBuf::from(input::STDIN_PATH)]);
+        let store = cas::Store::new(&self.store);
+        let mut failed = 0;
+        for file in files.iter() {
+            match input::Input::new(file).and_then(|r| store.put(r)) {
+                Ok(digest) => println!("{}:{}  {}", cas::ALGORITHM, digest, file.display()),
+                Err(err) => {
+                    eprintln!("put {:?}: {}", file, err);
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("WARNING: {} FAILS", failed).into());
+        }
+        Ok(())
+    }
+}
+
+impl Get {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let out = self.out.unwrap_or(PathBuf::from(output::STDOUT_PATH));
+        let mut out = output::Output::new(&out)?;
+        cas::Store::new(&self.store)
+            .get(&self.digest, &mut out)
+            .map_err(|err| format!("get {}:{}: {}", cas::ALGORITHM, self.digest, err))?;
+        out.flush()?;
+        out.commit()?;
+        Ok(())
+    }
+}
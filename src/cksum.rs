@@ -0,0 +1,74 @@
+use clap::Args;
+use std::error;
+use std::io;
+use std::path::PathBuf;
+
+use crate::libs::checksum::{Adler32, BsdSum, PosixCrc, SysvSum};
+use crate::libs::input;
+
+#[derive(Args)]
+pub struct Cksum {
+    /// Files to checksum (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    files: Option<Vec<PathBuf>>,
+
+    /// use BSD `sum` algorithm (like `sum -r`), with 1K blocks.
+    #[arg(short = 'r', long, conflicts_with_all = ["sysv", "adler32"])]
+    bsd: bool,
+    /// use System V `sum` algorithm (like `sum -s`), with 512 byte blocks.
+    #[arg(short, long, conflicts_with = "adler32")]
+    sysv: bool,
+    /// use zlib Adler-32, printed in hex, with length in bytes.
+    #[arg(long)]
+    adler32: bool,
+}
+
+impl Cksum {
+    pub fn exec(mut self) -> Result<(), Box<dyn error::Error>> {
+        // like coreutils, names are printed only for FILE arguments.
+        let (files, print_name) = match self.files.take() {
+            Some(files) => (files, true),
+            None => (vec![PathBuf::from(input::STDIN_PATH)], false),
+        };
+
+        let mut failed = 0;
+        for file in &files {
+            match self.checksum(file) {
+                Ok(line) if print_name => println!("{} {}", line, file.display()),
+                Ok(line) => println!("{}", line),
+                Err(err) => {
+                    eprintln!("cksum {:?}: {}", file, err);
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            return Err(format!("{} files failed", failed).into());
+        }
+        Ok(())
+    }
+
+    fn checksum(&self, file: &PathBuf) -> io::Result<String> {
+        let mut r = input::Input::new(file)?;
+        if self.bsd {
+            let mut sum = BsdSum::new();
+            io::copy(&mut r, &mut sum)?;
+            let (sum, blocks) = sum.finish();
+            Ok(format!("{:05} {:5}", sum, blocks))
+        } else if self.adler32 {
+            let mut sum = Adler32::new();
+            let len = io::copy(&mut r, &mut sum)?;
+            Ok(format!("{:08x} {}", sum.finish(), len))
+        } else if self.sysv {
+            let mut sum = SysvSum::new();
+            io::copy(&mut r, &mut sum)?;
+            let (sum, blocks) = sum.finish();
+            Ok(format!("{} {}", sum, blocks))
+        } else {
+            let mut crc = PosixCrc::new();
+            io::copy(&mut r, &mut crc)?;
+            let (crc, len) = crc.finish();
+            Ok(format!("{} {}", crc, len))
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use clap::{ArgAction, Args};
+use std::env;
+use std::error;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process;
+
+use crate::libs::secret;
+
+/// exit status when the secrets could not be read, as `cmp` does.
+const TROUBLE_EXIT_CODE: i32 = 2;
+
+#[derive(Args)]
+pub struct CmpSecret {
+    /// read a secret from FILE (repeatable).
+    #[arg(long, value_name = "FILE")]
+    file: Vec<PathBuf>,
+    /// read a secret from environment variable VAR (repeatable).
+    #[arg(long, value_name = "VAR")]
+    env: Vec<String>,
+    /// read a secret from standard input.
+    #[arg(long)]
+    stdin: bool,
+    /// read a secret from the terminal without echo; give twice to read both.
+    #[arg(long, action = ArgAction::Count)]
+    prompt: u8,
+    /// keep a trailing line break of secrets read from files and standard
+    /// input instead of removing one.
+    #[arg(long)]
+    keep_newline: bool,
+}
+
+impl CmpSecret {
+    /// compares exactly two secrets in constant time. exits 0 when they are
+    /// equal, 1 when they differ and 2 when they could not be read.
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let secrets = match self.read() {
+            Ok(secrets) => secrets,
+            Err(err) => {
+                eprintln!("cmp-secret: {}", err);
+                process::exit(TROUBLE_EXIT_CODE);
+            }
+        };
+        if !secret::eq(&secrets[0], &secrets[1]) {
+            return Err("secrets differ".into());
+        }
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Vec<Vec<u8>>, Box<dyn error::Error>> {
+        let count =
+            self.file.len() + self.env.len() + usize::from(self.stdin) + usize::from(self.prompt);
+        if count != 2 {
+            return Err(format!("expected 2 secrets, got {}", count).into());
+        }
+
+        let trim = |s: Vec<u8>| match self.keep_newline {
+            true => s,
+            false => secret::trim_newline(s),
+        };
+        let mut secrets = Vec::new();
+        for f in self.file.iter() {
+            secrets.push(trim(
+                fs::read(f).map_err(|err| format!("{:?}: {}", f, err))?,
+            ));
+        }
+        for var in self.env.iter() {
+            let value = env::var_os(var).ok_or(format!("{}: not set", var))?;
+            secrets.push(value.into_encoded_bytes());
+        }
+        if self.stdin {
+            let mut s = Vec::new();
+            io::stdin().lock().read_to_end(&mut s)?;
+            secrets.push(trim(s));
+        }
+        for i in 0..self.prompt {
+            let msg = if i == 0 { "Secret: " } else { "Again: " };
+            secrets.push(secret::prompt(msg)?);
+        }
+        Ok(secrets)
+    }
+}
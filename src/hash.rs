@@ -1,14 +1,36 @@
-mod check;
+pub(crate) mod check;
 mod digest;
+mod template;
 
 use clap::Args;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error;
 use std::fmt;
 use std::io::BufRead;
-use std::{io, path::PathBuf};
+use std::io::{Read, Seek, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fs, io, path::PathBuf};
 
+use crate::libs::audit;
+use crate::libs::cancel;
+use crate::libs::hash;
 pub use crate::libs::hash::Func;
 use crate::libs::input;
+use crate::libs::log;
+use crate::libs::metrics;
+use crate::libs::notify;
+use crate::libs::output;
+use crate::libs::pool;
+use crate::libs::report;
+use crate::libs::resume;
+use crate::libs::size;
+use crate::libs::tar;
+use crate::libs::warn;
+use crate::libs::watch;
+use crate::libs::zip;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -16,79 +38,580 @@ type Result<T> = std::result::Result<T, Error>;
 pub struct Hash {
     /// Files to digest (optional; default is stdin).
     /// With no FILE, or when FILE is -, read standard input.
+    /// Use ./- for a file named -, and -- to end options before FILEs starting with -.
     files: Option<Vec<PathBuf>>,
 
     /// create a BSD-style checksum if true.
     /// else create GNU style checksum file.
     #[arg(short, long)]
     tag: bool,
+    /// write only the raw digest bytes of each input, for piping into
+    /// another command like `ssl base64`.
+    #[arg(long, conflicts_with_all = ["tag", "check", "randomart", "qr"])]
+    binary_out: bool,
+    /// write every line in this format instead, like `{digest}  {path}  {size}`.
+    /// fields: algorithm, digest, digest_base64, path, size (bytes hashed)
+    /// and mtime (`-` when not a regular file). `{{` and `}}` are braces.
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["tag", "check", "binary_out", "salt", "metadata"])]
+    template: Option<template::Template>,
+    /// write a header row and one row per file (algorithm, digest, path,
+    /// size) or, with --check, per line (manifest, line, path, status,
+    /// error) as csv or tsv, for spreadsheets.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        conflicts_with_all = ["tag", "binary_out", "template", "salt", "metadata", "randomart", "qr", "watch"]
+    )]
+    format: Option<report::Format>,
+    /// digest the digest again, like SHA256d (hash of hash) of Bitcoin.
+    #[arg(long)]
+    double: bool,
+    /// digest with the CHAIN of functions instead, like 'sha256|md5':
+    /// the first digests the input, each next one the digest before it.
+    #[arg(long, value_name = "CHAIN", conflicts_with_all = ["check", "double"])]
+    pipe: Option<hash::Chain>,
+    /// prefix every line's digest with a fresh random salt (as hex and `:`),
+    /// hashed before the data, so known files can not be swapped in by
+    /// precomputed digests. --check verifies such lines.
+    #[arg(long, conflicts_with_all = ["check", "bits", "pipe", "binary_out"])]
+    salt: bool,
+    /// start the line of every regular FILE with its size and modification
+    /// time, like `[size=6 mtime=1700000000.000000000] `.
+    #[arg(long, conflicts_with_all = ["check", "binary_out", "tar", "zip", "concat", "fd"])]
+    metadata: bool,
+    /// with --check, compare the recorded size and modification time of
+    /// files before hashing them; lines without them are only hashed.
+    #[arg(long, requires = "check")]
+    verify_metadata: bool,
+    /// with --check, hash files which failed up to N more times after all
+    /// lines are checked, waiting 1s, 2s, 4s... before each round, and
+    /// tell corrupt files from ones which can not be read reliably.
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "check")]
+    retries: u32,
+    /// when reading a file fails, report the kind of error, how many bytes
+    /// were hashed before it and their digest, to help salvage data from
+    /// failing disks.
+    #[arg(long, conflicts_with = "bits")]
+    partial_on_error: bool,
+    /// digest each FILE as a Merkle tree (RFC 6962) of SIZE chunks, like 1M,
+    /// hashing -j chunks at once, so one huge file uses several cores.
+    /// the digest differs from the plain one; --check needs the same SIZE.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_chunk_size,
+        conflicts_with_all = ["bits", "detect_collisions", "salt", "partial_on_error"]
+    )]
+    merkle: Option<usize>,
     /// read checksums from the FILEs and check them.
     #[arg(short, long)]
     check: bool,
+    /// with --check, record verified lines in FILE and skip lines already
+    /// recorded there, so an interrupted run continues where it stopped.
+    /// FILE is removed once every line has been verified.
+    #[arg(long, value_name = "FILE", requires = "check")]
+    resume_file: Option<PathBuf>,
+    /// with --check, keep running after the check and verify files again
+    /// whenever they change, until interrupted.
+    #[arg(long, requires = "check", conflicts_with_all = ["resume_file", "out"])]
+    watch: bool,
+    /// with --watch, serve verification counters for Prometheus
+    /// on http://ADDR/metrics, like 127.0.0.1:9101.
+    #[arg(long, value_name = "ADDR", requires = "watch")]
+    metrics: Option<SocketAddr>,
+    /// with --check, run CMD for every failed line; {path}, {expected},
+    /// {actual}, {error} and {source} (the checksum file) in its arguments
+    /// are replaced. CMD is split on spaces outside quotes and run without a shell.
+    #[arg(long, value_name = "CMD", requires = "check")]
+    on_fail: Option<notify::Template>,
+    /// with --check, POST every failure as JSON to URL (http:// only).
+    #[arg(long, value_name = "URL", requires = "check")]
+    webhook: Option<String>,
+    /// with --check, append a record of every verified line to FILE.
+    /// each record holds the digest of the previous one, so the log is
+    /// tamper-evident (see `ssl audit-verify`).
+    #[arg(long, value_name = "FILE", requires = "check")]
+    audit_log: Option<PathBuf>,
+    /// with --check, where to report failed checks: stderr, syslog or journald.
+    #[arg(
+        long,
+        value_name = "TARGET",
+        requires = "check",
+        default_value = "stderr"
+    )]
+    log_target: log::Target,
+    /// hash only N bits of the input (for messages which are not a whole number of bytes).
+    /// the input must be exactly as long as needed to hold N bits,
+    /// the last bits are taken from the high-order end of the last byte.
+    #[arg(long, value_name = "N", conflicts_with = "check")]
+    bits: Option<u64>,
+    /// fail on inputs which contain a block of a known collision attack (MD5 only).
+    #[arg(long, conflicts_with = "bits")]
+    detect_collisions: bool,
+    /// read files in text mode: CRLF line ends are hashed as LF, so text
+    /// checked out with Windows line ends has the same digest as on unix.
+    #[arg(long, conflicts_with_all = ["bits", "tar", "zip", "fd"])]
+    text: bool,
+    /// print OpenSSH-style randomart of each digest after its line.
+    #[arg(long, conflicts_with = "check")]
+    randomart: bool,
+    /// FILEs are tar archives: digest every regular file member
+    /// without extracting, named by its path in the archive.
+    #[arg(long, conflicts_with = "check")]
+    tar: bool,
+    /// FILEs are zip archives: digest every stored or deflated member,
+    /// named by its path in the archive, and verify its stored CRC-32.
+    #[arg(long, conflicts_with_all = ["check", "tar"])]
+    zip: bool,
+    /// digest all FILEs as one continuous stream, in argument order,
+    /// and print a single line named -.
+    #[arg(long, conflicts_with_all = ["check", "tar", "zip"])]
+    concat: bool,
+    /// read inherited file descriptor N instead of FILEs (repeatable),
+    /// named /dev/fd/N in the output.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(i32).range(0..),
+        conflicts_with_all = ["files", "check", "tar", "zip", "concat"]
+    )]
+    fd: Vec<i32>,
+    /// treat standard input as empty instead of reading it, so that
+    /// no FILE (or -) hashes the empty input explicitly.
+    #[arg(long, conflicts_with = "check")]
+    stdin_null: bool,
+    /// digest up to N FILEs at once; 0 means one per cpu.
+    /// lines are still printed in FILE order.
+    #[arg(short, long, value_name = "N", default_value_t = 1)]
+    jobs: usize,
+    /// pin the -j workers to these cpus in turn, like 0-7 or 0,2,4; with
+    /// -j 0 one worker per cpu listed. linux only, ignored elsewhere.
+    #[arg(long, value_name = "CPUS", conflicts_with = "check")]
+    cpu_list: Option<pool::CpuList>,
+    /// print hex digest as a terminal QR code after its line.
+    #[arg(long, conflicts_with = "check")]
+    qr: bool,
+    /// write output to FILE instead of standard output.
+    /// the file is replaced atomically when all input is processed.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<PathBuf>,
+    /// octal permissions of the --out file (before umask).
+    #[arg(long, value_name = "MODE", value_parser = output::parse_mode, default_value = "666")]
+    mode: u32,
+}
+
+fn parse_chunk_size(s: &str) -> std::result::Result<usize, String> {
+    match size::parse(s)? {
+        0 => Err("chunk size must not be zero".into()),
+        size => usize::try_from(size).map_err(|_| format!("chunk size too large: {}", s)),
+    }
 }
 
 impl Hash {
+    /// exec with `algo` applied twice, as by --double.
+    pub fn exec_double(mut self, algo: Func) -> Result<()> {
+        self.double = true;
+        self.exec(algo)
+    }
+
     pub fn exec(self, algo: Func) -> Result<()> {
-        let files = self.files.unwrap_or(vec![PathBuf::from("-")]);
-        let style = if self.tag {
-            digest::Style::BSD
-        } else {
-            digest::Style::GNU
+        let (algo, then) = match self.pipe {
+            Some(hash::Chain(ref chain)) => (chain[0], chain[1..].to_vec()),
+            None => (algo, Vec::new()),
+        };
+        for f in [algo].iter().chain(then.iter()) {
+            hash::allowed(*f).map_err(Error::Refused)?;
+            if !self.check {
+                warn::generating(*f);
+            }
+        }
+        let files = self.files.unwrap_or(vec![PathBuf::from(input::STDIN_PATH)]);
+        let out = self.out.unwrap_or(PathBuf::from(output::STDOUT_PATH));
+        let mut out = output::Output::with_mode(&out, self.mode)?;
+        let cpus = self.cpu_list.map(|list| list.0).unwrap_or_default();
+        let jobs = match self.jobs {
+            0 if !cpus.is_empty() => cpus.len(),
+            0 => pool::default_jobs(),
+            jobs => jobs,
+        };
+        let opts = digest::Options {
+            style: if let Some(format) = self.format {
+                digest::Style::Report(format)
+            } else if let Some(template) = self.template {
+                digest::Style::Template(template)
+            } else if self.binary_out {
+                digest::Style::Binary
+            } else if self.tag {
+                digest::Style::Bsd
+            } else {
+                digest::Style::Gnu
+            },
+            bits: self.bits,
+            double: self.double,
+            then,
+            detect_collisions: self.detect_collisions,
+            randomart: self.randomart,
+            qr: self.qr,
+            stdin_null: self.stdin_null,
+            text: self.text,
+            salt: self.salt,
+            metadata: self.metadata,
+            partial_on_error: self.partial_on_error,
+            merkle: self.merkle,
+            jobs,
+        };
+        let check_opts = check::Options {
+            detect_collisions: self.detect_collisions,
+            text: self.text,
+            double: self.double,
+            metadata: self.verify_metadata,
+            partial_on_error: self.partial_on_error,
+            merkle: self.merkle,
+            jobs,
+        };
+
+        let mut progress = match self.resume_file {
+            Some(ref path) => Some(Progress::load(path)?),
+            None => None,
+        };
+        let audit_log = match self.audit_log {
+            Some(ref path) => {
+                Some(audit::Log::open(path).map_err(|err| Error::AuditLog(path.into(), err))?)
+            }
+            None => None,
+        };
+        let registry = match self.metrics {
+            Some(addr) => {
+                let registry = Arc::new(metrics::Metrics::new());
+                metrics::serve(addr, registry.clone()).map_err(Error::Metrics)?;
+                Some(registry)
+            }
+            None => None,
+        };
+        let mut report = Report {
+            format: self.format,
+            audit_log,
+            logger: log::open(self.log_target).map_err(Error::LogTarget)?,
+            notifier: notify::Notifier {
+                on_fail: self.on_fail,
+                webhook: self.webhook,
+            },
+            metrics: registry,
         };
+        if let Some(format) = self.format {
+            let columns = match self.check {
+                true => &CHECK_COLUMNS[..],
+                false => &digest::COLUMNS[..],
+            };
+            format.row(&mut out, columns)?;
+        }
+        let token = cancel::Token::on_interrupt();
+        let res = match self.check {
+            true if self.watch => watch(&mut out, &files, check_opts, &mut report, &token),
+            true => check(
+                &mut out,
+                files,
+                check_opts,
+                self.retries,
+                progress.as_mut(),
+                &mut report,
+                &token,
+            )
+            .map_err(Error::from),
+            _ if !self.fd.is_empty() => Ok(fds(&mut out, &self.fd, algo, &opts)),
+            _ if self.tar => Ok(tar(&mut out, files, algo, &opts)),
+            _ if self.zip => Ok(zip(&mut out, files, algo, &opts)),
+            _ if self.concat => Ok(concat(&mut out, files, algo, &opts)),
+            _ => {
+                // Merkle mode splits every file into chunks on the workers.
+                let jobs = match opts.merkle {
+                    Some(_) => 1,
+                    None => jobs,
+                };
+                digest(&mut out, files, algo, &opts, jobs, &cpus, &token).map_err(Error::from)
+            }
+        };
+        // lines of completed entries are kept on interrupt.
+        out.commit()?;
 
-        match self.check {
-            true => check(files),
-            _ => digest(files, algo, style),
+        match res? {
+            0 => Ok(()),
+            failed => Err(Error::Failed(failed)),
         }
     }
 }
 
 #[derive(Debug)]
-pub struct Error {
-    failed: usize,
+pub enum Error {
+    Failed(usize),
+    Interrupted(cancel::Interrupted),
+    /// hash function refused, like MD5 in FIPS mode.
+    Refused(io::Error),
+    Output(io::Error),
+    Watch(io::Error),
+    AuditLog(PathBuf, io::Error),
+    LogTarget(io::Error),
+    Metrics(io::Error),
+    ResumeFile(PathBuf, io::Error),
+    Resume(PathBuf, resume::Error),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "WARNING: {} FAILS", self.failed)
+        match self {
+            Error::Failed(failed) => write!(f, "WARNING: {} FAILS", failed),
+            Error::Interrupted(summary) => write!(f, "{}", summary),
+            Error::Refused(err) => write!(f, "{}", err),
+            Error::Output(err) => write!(f, "output: {}", err),
+            Error::Watch(err) => write!(f, "watch: {}", err),
+            Error::AuditLog(path, err) => write!(f, "audit log {:?}: {}", path, err),
+            Error::LogTarget(err) => write!(f, "log target: {}", err),
+            Error::Metrics(err) => write!(f, "metrics: {}", err),
+            Error::ResumeFile(path, err) => write!(f, "{:?}: {}", path, err),
+            Error::Resume(path, err) => write!(f, "{:?}: {}", path, err),
+        }
     }
 }
 
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        None
+        match *self {
+            Error::Failed(_) => None,
+            Error::Interrupted(ref e) => Some(e),
+            Error::Refused(ref e) => Some(e),
+            Error::Output(ref e) => Some(e),
+            Error::Watch(ref e) => Some(e),
+            Error::AuditLog(_, ref e) => Some(e),
+            Error::LogTarget(ref e) => Some(e),
+            Error::Metrics(ref e) => Some(e),
+            Error::ResumeFile(_, ref e) => Some(e),
+            Error::Resume(_, ref e) => Some(e),
+        }
+    }
+}
+
+impl From<cancel::Interrupted> for Error {
+    fn from(summary: cancel::Interrupted) -> Error {
+        Error::Interrupted(summary)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Output(err)
+    }
+}
+
+/// how often the --resume-file is rewritten during a check.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// verified lines of checksum files, kept in the --resume-file.
+struct Progress {
+    path: PathBuf,
+    state: resume::State,
+    saved: Instant,
+}
+
+impl Progress {
+    /// reads the state of a previous run; a missing file starts from scratch.
+    fn load(path: &Path) -> Result<Progress> {
+        let state = match fs::read_to_string(path) {
+            Ok(text) => {
+                resume::State::parse(&text).map_err(|err| Error::Resume(path.into(), err))?
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => resume::State::new(),
+            Err(err) => return Err(Error::ResumeFile(path.into(), err)),
+        };
+        Ok(Progress {
+            path: path.into(),
+            state,
+            saved: Instant::now(),
+        })
+    }
+
+    fn save(&mut self) {
+        let res = output::AtomicFile::new(&self.path).and_then(|mut f| {
+            writeln!(f, "{}", self.state)?;
+            f.commit()
+        });
+        if let Err(err) = res {
+            eprintln!("{:?}: {}", self.path, err);
+        }
+        self.saved = Instant::now();
+    }
+
+    fn finish(&mut self, failed: usize) {
+        if failed > 0 {
+            // failed lines are checked again by the next run.
+            self.save();
+        } else if let Err(err) = fs::remove_file(&self.path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                eprintln!("{:?}: {}", self.path, err);
+            }
+        }
+    }
+}
+
+/// destinations of check results besides the output:
+/// audit log, log target, failure notifications and metrics.
+struct Report {
+    /// results are written to the output as rows of this format.
+    format: Option<report::Format>,
+    audit_log: Option<audit::Log>,
+    logger: Box<dyn log::Backend>,
+    notifier: notify::Notifier,
+    metrics: Option<Arc<metrics::Metrics>>,
+}
+
+impl Report {
+    fn log(&mut self, level: log::Level, msg: &str) {
+        self.logger.log(level, msg);
+    }
+
+    /// records result `res` of checking line `number` of `manifest`, which took `elapsed`.
+    fn checked(
+        &mut self,
+        manifest: &Path,
+        number: u64,
+        line: &str,
+        res: &std::result::Result<u64, check::Error>,
+        elapsed: Duration,
+    ) {
+        let parsed = check::parse(line).ok();
+        let file = parsed.as_ref().map(|entry| entry.path.to_string_lossy());
+        let manifest = manifest.to_string_lossy();
+        if let Some(ref mut log) = self.audit_log {
+            let record = audit::Record {
+                manifest: &manifest,
+                line: number,
+                file: file.as_deref(),
+                digest: parsed.as_ref().map(|entry| &entry.digest),
+                error: res.as_ref().err().map(|err| err.to_string()),
+            };
+            if let Err(err) = log.append(&record) {
+                eprintln!("audit log: {}", err);
+            }
+        }
+        if let Some(ref m) = self.metrics {
+            m.record(res.is_ok(), *res.as_ref().unwrap_or(&0), elapsed);
+        }
+        if let Err(ref err) = res {
+            let failure = notify::Failure {
+                source: manifest.into_owned(),
+                path: file.map(|f| f.into_owned()).unwrap_or_default(),
+                expected: parsed.map(|entry| entry.digest.to_string()),
+                actual: match err {
+                    check::Error::DigestIncorrect(actual) => Some(actual.to_string()),
+                    _ => None,
+                },
+                error: err.to_string(),
+            };
+            self.notifier.notify(&[failure], self.logger.as_mut());
+        }
     }
 }
 
 /// read and check checksum file(s).
 /// compare for files listed in checksum file expected and actual computed hash of the file
 /// (among the list).
-fn check(files: Vec<PathBuf>) -> Result<()> {
-    let mut failed: usize = 0;
-    for file in files.iter() {
-        let r = match input::Input::new(&file) {
+/// lines recorded in `progress` are skipped and newly verified lines are added to it.
+/// files which fail to hash or mismatch are hashed again up to `retries` times
+/// at the end, and reported only once they pass or run out of retries.
+/// results of checked lines go to `report`.
+/// returns number of failed checks.
+fn check<W: Write>(
+    w: &mut W,
+    files: Vec<PathBuf>,
+    check_opts: check::Options,
+    retries: u32,
+    mut progress: Option<&mut Progress>,
+    report: &mut Report,
+    token: &cancel::Token,
+) -> std::result::Result<usize, cancel::Interrupted> {
+    let (mut completed, mut failed): (usize, usize) = (0, 0);
+    let mut pending = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let r = match input::Input::new(file) {
             Ok(input) => input,
             Err(err) => {
-                eprintln!("{}", err);
+                report.log(log::Level::Error, &err.to_string());
                 continue;
             }
         };
+        let manifest = file.to_string_lossy();
+        if let Some(ref p) = progress {
+            match p.state.verified_count(&manifest) {
+                0 => (),
+                n => eprintln!("{:?}: skipping {} lines verified before", file, n),
+            }
+        }
 
-        let buf_r = io::BufReader::new(r);
-        for line in buf_r.lines() {
+        let mut lines = io::BufReader::new(r).lines();
+        let mut number = 0;
+        while let Some(line) = lines.next() {
+            number += 1;
+            if let Some(ref mut p) = progress {
+                if p.state.is_verified(&manifest, number) {
+                    continue;
+                }
+                if p.saved.elapsed() >= SAVE_INTERVAL {
+                    p.save();
+                }
+            }
+            if token.is_cancelled() {
+                if let Some(p) = progress {
+                    p.save();
+                }
+                // lines of checksum files are cheap to count.
+                let remaining = 1
+                    + lines.count()
+                    + files[i + 1..]
+                        .iter()
+                        .filter_map(|f| input::Input::new(f).ok())
+                        .map(|r| io::BufReader::new(r).lines().count())
+                        .sum::<usize>();
+                return Err(cancel::Interrupted {
+                    completed,
+                    failed,
+                    remaining,
+                });
+            }
             let line = match line {
                 Ok(line) => line,
                 Err(err) => {
-                    eprintln!("read line: {}", err);
+                    report.log(log::Level::Error, &format!("read line: {}", err));
                     failed += 1;
                     continue;
                 }
             };
-            match check::line(&line) {
-                // TODO: not file path in line.
-                Ok(_) => println!("{:?} OK", file),
+            let start = Instant::now();
+            let res = check::line(&line, &check_opts);
+            if retries > 0 && Retry::retryable(&res) {
+                pending.push(Retry::new(file, number, line, res, start.elapsed()));
+                continue;
+            }
+            report.checked(file, number, &line, &res, start.elapsed());
+            let written = write_checked(w, report.format, file, number, &line, &res);
+            match res {
+                Ok(_) => {
+                    if let Err(err) = written {
+                        eprintln!("write: {}", err);
+                        failed += 1;
+                        continue;
+                    }
+                    completed += 1;
+                    if let Some(ref mut p) = progress {
+                        p.state.mark_verified(&manifest, number);
+                    }
+                }
                 Err(err) => {
-                    eprintln!("check_line: file {:?}, line {:?}: {}", file,line, err);
+                    report.log(
+                        log::Level::Error,
+                        &format!("check_line: file {:?}, line {:?}: {}", file, line, err),
+                    );
+                    if let Err(err) = written {
+                        eprintln!("write: {}", err);
+                    }
                     failed += 1;
                     continue;
                 }
@@ -96,30 +619,537 @@ fn check(files: Vec<PathBuf>) -> Result<()> {
         }
     }
 
-    if failed > 0 {
-        Err(Error { failed })
-    } else {
-        Ok(())
+    let mut recovered = 0;
+    let mut backoff = RETRY_BACKOFF;
+    for _ in 0..retries {
+        if pending.is_empty() || token.is_cancelled() {
+            break;
+        }
+        token.sleep(backoff);
+        backoff *= 2;
+        let mut left = Vec::new();
+        for mut retry in pending {
+            if token.is_cancelled() {
+                left.push(retry);
+                continue;
+            }
+            let start = Instant::now();
+            retry.attempt(check::line(&retry.line, &check_opts), start.elapsed());
+            if retry.res.is_err() {
+                left.push(retry);
+                continue;
+            }
+            report.checked(
+                &retry.file,
+                retry.number,
+                &retry.line,
+                &retry.res,
+                retry.elapsed,
+            );
+            let written = write_checked(
+                w,
+                report.format,
+                &retry.file,
+                retry.number,
+                &retry.line,
+                &retry.res,
+            );
+            if let Err(err) = written {
+                eprintln!("write: {}", err);
+                failed += 1;
+                continue;
+            }
+            completed += 1;
+            recovered += 1;
+            if let Some(ref mut p) = progress {
+                p.state
+                    .mark_verified(&retry.file.to_string_lossy(), retry.number);
+            }
+        }
+        pending = left;
+    }
+
+    let mut kinds = BTreeMap::new();
+    for retry in &pending {
+        report.checked(
+            &retry.file,
+            retry.number,
+            &retry.line,
+            &retry.res,
+            retry.elapsed,
+        );
+        let written = write_checked(
+            w,
+            report.format,
+            &retry.file,
+            retry.number,
+            &retry.line,
+            &retry.res,
+        );
+        if let Err(err) = written {
+            eprintln!("write: {}", err);
+        }
+        if let Err(ref err) = retry.res {
+            *kinds.entry(retry.kind()).or_insert(0) += 1;
+            report.log(
+                log::Level::Error,
+                &format!(
+                    "check_line: file {:?}, line {:?}: {} ({} after {} attempts)",
+                    retry.file,
+                    retry.line,
+                    err,
+                    retry.kind(),
+                    retry.attempts
+                ),
+            );
+        }
+    }
+    failed += pending.len();
+    if retries > 0 && recovered + pending.len() > 0 {
+        report.log(
+            log::Level::Warning,
+            &format!(
+                "retries: {} recovered, {} corrupt, {} unreadable, {} unstable",
+                recovered,
+                kinds.get("corrupt").unwrap_or(&0),
+                kinds.get("unreadable").unwrap_or(&0),
+                kinds.get("unstable").unwrap_or(&0)
+            ),
+        );
+    }
+
+    if let Some(p) = progress {
+        p.finish(failed);
+    }
+    if token.is_cancelled() && !pending.is_empty() {
+        return Err(cancel::Interrupted {
+            completed,
+            failed,
+            remaining: 0,
+        });
+    }
+    Ok(failed)
+}
+
+/// columns of the rows of --check --format.
+const CHECK_COLUMNS: [&str; 5] = ["manifest", "line", "path", "status", "error"];
+
+/// writes the result of checking `line` of `manifest`: a row of `format`
+/// for every line, or else `"MANIFEST" OK` for lines which passed.
+fn write_checked<W: Write>(
+    w: &mut W,
+    format: Option<report::Format>,
+    manifest: &Path,
+    number: u64,
+    line: &str,
+    res: &std::result::Result<u64, check::Error>,
+) -> io::Result<()> {
+    let Some(format) = format else {
+        // TODO: not file path in line.
+        return match res {
+            Ok(_) => writeln!(w, "{:?} OK", manifest),
+            Err(_) => Ok(()),
+        };
+    };
+    let path = check::parse(line)
+        .map(|entry| entry.path.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (status, error) = match res {
+        Ok(_) => ("OK", String::new()),
+        Err(err) => ("FAILED", err.to_string()),
+    };
+    let fields = [
+        &*manifest.to_string_lossy(),
+        &number.to_string(),
+        &path,
+        status,
+        &error,
+    ];
+    format.row(w, &fields)
+}
+
+/// wait before the first round of retries, doubled for every next round.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// line which failed and waits to be checked again.
+struct Retry {
+    file: PathBuf,
+    number: u64,
+    line: String,
+    res: std::result::Result<u64, check::Error>,
+    elapsed: Duration,
+    attempts: u32,
+    /// actual digest of the first attempt while every attempt gave the same.
+    same_digest: Option<hash::Digest>,
+}
+
+impl Retry {
+    /// whether a failed check may pass when the file is read again.
+    fn retryable(res: &std::result::Result<u64, check::Error>) -> bool {
+        matches!(
+            res,
+            Err(check::Error::DigestIncorrect(_))
+                | Err(check::Error::Digest(_))
+                | Err(check::Error::Partial(_))
+        )
+    }
+
+    fn new(
+        file: &Path,
+        number: u64,
+        line: String,
+        res: std::result::Result<u64, check::Error>,
+        elapsed: Duration,
+    ) -> Retry {
+        let same_digest = match res {
+            Err(check::Error::DigestIncorrect(ref actual)) => Some(actual.clone()),
+            _ => None,
+        };
+        Retry {
+            file: file.into(),
+            number,
+            line,
+            res,
+            elapsed,
+            attempts: 1,
+            same_digest,
+        }
+    }
+
+    fn attempt(&mut self, res: std::result::Result<u64, check::Error>, elapsed: Duration) {
+        self.same_digest = match (&res, self.same_digest.take()) {
+            (Err(check::Error::DigestIncorrect(actual)), Some(first)) if *actual == first => {
+                Some(first)
+            }
+            _ => None,
+        };
+        self.res = res;
+        self.elapsed = elapsed;
+        self.attempts += 1;
+    }
+
+    /// why the line still fails: "corrupt" when every read gave the same
+    /// wrong digest, so the data itself differs, "unreadable" when the last
+    /// read failed, and "unstable" when reads gave different digests.
+    fn kind(&self) -> &'static str {
+        match (&self.same_digest, &self.res) {
+            (Some(_), _) => "corrupt",
+            (None, Err(check::Error::Digest(_)) | Err(check::Error::Partial(_))) => "unreadable",
+            (None, _) => "unstable",
+        }
+    }
+}
+
+/// how long to wait for more changes before verifying changed files.
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+/// how often a watch checks for interrupt.
+const WAKEUP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// check checksum file(s) like `check`, then verify files again whenever
+/// they change, until interrupted.
+/// files are watched before the first check, so no change is missed.
+/// every verification goes to `report`, which also logs recovered files.
+/// returns number of files failing when interrupted.
+fn watch<W: Write>(
+    w: &mut W,
+    files: &[PathBuf],
+    check_opts: check::Options,
+    report: &mut Report,
+    token: &cancel::Token,
+) -> Result<usize> {
+    let mut watcher = watch::Watcher::new().map_err(Error::Watch)?;
+    let mut lines = BTreeMap::new();
+    let mut unchecked = 0;
+    for file in files {
+        let r = match input::Input::new(file) {
+            Ok(input) => input,
+            Err(err) => {
+                report.log(log::Level::Error, &err.to_string());
+                continue;
+            }
+        };
+        for (i, line) in io::BufReader::new(r).lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    report.log(log::Level::Error, &format!("read line: {}", err));
+                    unchecked += 1;
+                    continue;
+                }
+            };
+            match check::parse(&line) {
+                Ok(check::Entry { path, .. }) => {
+                    // such files are still checked once.
+                    if let Err(err) = watcher.add(&path) {
+                        report.log(log::Level::Warning, &format!("watch {:?}: {}", path, err));
+                    }
+                    lines.insert(path, (file, i as u64 + 1, line));
+                }
+                Err(err) => {
+                    report.log(
+                        log::Level::Error,
+                        &format!("check_line: file {:?}, line {:?}: {}", file, line, err),
+                    );
+                    unchecked += 1;
+                }
+            }
+        }
+    }
+
+    let mut failing = BTreeSet::new();
+    let mut verify = |w: &mut W, path: &PathBuf| {
+        let (manifest, number, ref line) = lines[path];
+        let start = Instant::now();
+        let res = check::line(line, &check_opts);
+        report.checked(manifest, number, line, &res, start.elapsed());
+        match res {
+            Ok(_) => {
+                if failing.remove(path) {
+                    report.log(log::Level::Info, &format!("file {:?}: OK again", path));
+                }
+                if let Err(err) = writeln!(w, "{:?} OK", path) {
+                    eprintln!("write: {}", err);
+                }
+            }
+            Err(err) => {
+                failing.insert(path.clone());
+                report.log(
+                    log::Level::Error,
+                    &format!("check_line: file {:?}: {}", path, err),
+                );
+            }
+        }
+        if let Some(ref m) = report.metrics {
+            m.set_failing(failing.len() as u64);
+        }
+    };
+
+    let mut remaining = lines.len();
+    for path in lines.keys() {
+        if token.is_cancelled() {
+            return Err(Error::Interrupted(cancel::Interrupted {
+                completed: lines.len() - remaining - failing.len(),
+                failed: failing.len() + unchecked,
+                remaining,
+            }));
+        }
+        verify(w, path);
+        remaining -= 1;
+    }
+
+    while !token.is_cancelled() {
+        let mut changed = watcher.wait(WAKEUP_INTERVAL).map_err(Error::Watch)?;
+        if changed.is_empty() {
+            continue;
+        }
+        loop {
+            let more = watcher.wait(SETTLE_TIME).map_err(Error::Watch)?;
+            if more.is_empty() {
+                break;
+            }
+            changed.extend(more);
+        }
+        changed.sort();
+        changed.dedup();
+        for path in &changed {
+            verify(w, path);
+        }
+    }
+
+    Ok(failing.len() + unchecked)
+}
+
+/// create checksum file, digesting up to `jobs` files at once.
+/// files not started when `token` is cancelled are skipped.
+/// returns number of failed files.
+fn digest<W: Write>(
+    w: &mut W,
+    files: Vec<PathBuf>,
+    algo: Func,
+    opts: &digest::Options,
+    jobs: usize,
+    cpus: &[usize],
+    token: &cancel::Token,
+) -> std::result::Result<usize, cancel::Interrupted> {
+    let (mut completed, mut failed, mut remaining): (usize, usize, usize) = (0, 0, 0);
+    pool::ordered_map_on(
+        jobs,
+        cpus,
+        &files,
+        |file| {
+            if token.is_cancelled() {
+                return None;
+            }
+            // a line may be written before an error, as on detected collisions.
+            let mut line = Vec::new();
+            let res = digest::writeln(&mut line, file, algo, opts)
+                .map_err(|err| format!("digest {:?}: {}", file, err));
+            Some((line, res))
+        },
+        |res| {
+            let (line, res) = match res {
+                Some(res) => res,
+                None => {
+                    remaining += 1;
+                    return;
+                }
+            };
+            if let Err(err) = w.write_all(&line) {
+                eprintln!("write: {}", err);
+                failed += 1;
+            } else if let Err(err) = res {
+                eprintln!("{}", err);
+                failed += 1;
+            } else {
+                completed += 1;
+            }
+        },
+    );
+
+    if remaining > 0 {
+        return Err(cancel::Interrupted {
+            completed,
+            failed,
+            remaining,
+        });
+    }
+    Ok(failed)
+}
+
+/// create checksum file of inherited file descriptors.
+/// returns number of failed descriptors.
+fn fds<W: Write>(w: &mut W, fds: &[i32], algo: Func, opts: &digest::Options) -> usize {
+    let mut failed: usize = 0;
+    for &fd in fds {
+        let name = input::Input::fd_name(fd);
+        let res = input::Input::from_fd(fd)
+            .map_err(|err| err.into())
+            .and_then(|r| digest::writeln_reader(w, r, &name, algo, opts));
+        if let Err(err) = res {
+            eprintln!("digest {}: {}", name, err);
+            failed += 1;
+        }
+    }
+
+    failed
+}
+
+/// create checksum line of the concatenation of files.
+/// returns 1 if any file failed, else 0.
+fn concat<W: Write>(w: &mut W, files: Vec<PathBuf>, algo: Func, opts: &digest::Options) -> usize {
+    let r = input::Concat::new(files, opts.stdin_null);
+    let res = match opts.text {
+        true => digest::writeln_reader(w, input::Text::new(r), input::STDIN_PATH, algo, opts),
+        false => digest::writeln_reader(w, r, input::STDIN_PATH, algo, opts),
+    };
+    match res {
+        Ok(_) => 0,
+        Err(err) => {
+            eprintln!("digest: {}", err);
+            1
+        }
     }
 }
 
-/// create checksum file.
-fn digest(files: Vec<PathBuf>, algo: Func, style: digest::Style) -> Result<()> {
+/// create checksum file of members of tar archives.
+/// returns number of failed members and archives.
+fn tar<W: Write>(w: &mut W, files: Vec<PathBuf>, algo: Func, opts: &digest::Options) -> usize {
     let mut failed: usize = 0;
     for file in files.iter() {
-        match digest::println(&file, algo, style) {
-            Ok(_) => (),
+        let mut archive = match input::Input::with_stdin_null(file, opts.stdin_null) {
+            Ok(input) => tar::Reader::new(io::BufReader::new(input)),
             Err(err) => {
-                eprintln!("digest {:?}: {}", file, err);
+                eprintln!("{:?}: {}", file, err);
                 failed += 1;
                 continue;
             }
         };
+        loop {
+            let header = match archive.next_entry() {
+                Ok(Some(header)) => header,
+                Ok(None) => break,
+                Err(err) => {
+                    eprintln!("tar {:?}: {}", file, err);
+                    failed += 1;
+                    break;
+                }
+            };
+            if !header.is_file() {
+                continue;
+            }
+            if let Err(err) = digest::writeln_reader(w, &mut archive, &header.path, algo, opts) {
+                eprintln!("digest {:?} in {:?}: {}", header.path, file, err);
+                failed += 1;
+            }
+        }
     }
 
-    if failed > 0 {
-        Err(Error { failed })
-    } else {
-        Ok(())
+    failed
+}
+
+/// create checksum file of members of zip archives.
+/// archives which can not seek, as standard input or FIFOs, are buffered in memory.
+/// returns number of failed members and archives.
+fn zip<W: Write>(w: &mut W, files: Vec<PathBuf>, algo: Func, opts: &digest::Options) -> usize {
+    let mut failed: usize = 0;
+    for file in files.iter() {
+        let archive = match input::Input::with_stdin_null(file, opts.stdin_null) {
+            Ok(input::Input::File(f)) if f.metadata().is_ok_and(|m| m.is_file()) => {
+                Ok(zip_members(w, input::Input::File(f), file, algo, opts))
+            }
+            Ok(mut input) => {
+                let mut buf = Vec::new();
+                input
+                    .read_to_end(&mut buf)
+                    .map(|_| zip_members(w, io::Cursor::new(buf), file, algo, opts))
+            }
+            Err(err) => Err(err),
+        };
+        match archive {
+            Ok(Ok(n)) => failed += n,
+            Ok(Err(err)) => {
+                eprintln!("zip {:?}: {}", file, err);
+                failed += 1;
+            }
+            Err(err) => {
+                eprintln!("{:?}: {}", file, err);
+                failed += 1;
+            }
+        }
     }
+
+    failed
+}
+
+/// digest members of one zip archive, skipping directories. members
+/// whose CRC-32 or size does not match fail without a line.
+/// returns number of failed members.
+fn zip_members<W: Write, R: Read + Seek>(
+    w: &mut W,
+    mut r: R,
+    file: &PathBuf,
+    algo: Func,
+    opts: &digest::Options,
+) -> io::Result<usize> {
+    let mut failed: usize = 0;
+    for entry in zip::central_directory(&mut r)? {
+        if entry.is_dir() {
+            continue;
+        }
+        let data = match zip::read_entry(&mut r, &entry) {
+            Ok(data) => data,
+            Err(err) => {
+                eprintln!("zip {:?} in {:?}: {}", entry.name, file, err);
+                failed += 1;
+                continue;
+            }
+        };
+        // the CRC-32 is checked when the data is read to its end.
+        if let Err(err) = digest::writeln_reader(w, data, &entry.name, algo, opts) {
+            eprintln!("zip {:?} in {:?}: {}", entry.name, file, err);
+            failed += 1;
+        }
+    }
+
+    Ok(failed)
 }
@@ -2,27 +2,54 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use std::error;
 use std::fmt;
-use std::io;
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use crate::libs::hash;
 use crate::libs::hash::md5;
 use crate::libs::hash::sha256;
+use crate::libs::hex;
 use crate::libs::input;
+use crate::libs::merkle;
+
+use super::digest::COLLISION_DETECTED;
 
 #[derive(Debug)]
 pub enum Error {
-    DigestIncorrect,
+    /// the actual digest.
+    DigestIncorrect(hash::Digest),
+    /// the recorded and the actual size.
+    SizeIncorrect(u64, u64),
+    /// the recorded and the actual modification time.
+    MtimeIncorrect(Metadata, Metadata),
+    CollisionDetected,
     ParseChecksumLine(ParseChecksumLineError),
     Digest(io::Error),
+    /// read error with the digest of the data before it.
+    Partial(hash::PartialError),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::DigestIncorrect => write!(f, "digest incorrect"),
+            Error::DigestIncorrect(_) => write!(f, "digest incorrect"),
+            Error::SizeIncorrect(expected, actual) => write!(
+                f,
+                "size incorrect: expected {}, actual {}",
+                expected, actual
+            ),
+            Error::MtimeIncorrect(expected, actual) => write!(
+                f,
+                "modification time incorrect: expected {}, actual {}",
+                expected.mtime_string(),
+                actual.mtime_string()
+            ),
+            Error::CollisionDetected => write!(f, "{}", COLLISION_DETECTED),
             Error::ParseChecksumLine(err) => write!(f, "parse checksumline: {}", err),
             Error::Digest(err) => write!(f, "digest: {}", err),
+            Error::Partial(err) => write!(f, "digest: {}", err),
         }
     }
 }
@@ -30,9 +57,13 @@ impl fmt::Display for Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
-            Error::DigestIncorrect => None,
+            Error::DigestIncorrect(_) => None,
+            Error::SizeIncorrect(..) => None,
+            Error::MtimeIncorrect(..) => None,
+            Error::CollisionDetected => None,
             Error::ParseChecksumLine(ref e) => Some(e),
             Error::Digest(ref e) => Some(e),
+            Error::Partial(ref e) => Some(e),
         }
     }
 }
@@ -49,28 +80,156 @@ impl From<io::Error> for Error {
     }
 }
 
+/// reader which counts the bytes read through it.
+pub(super) struct Counter<R> {
+    pub(super) inner: R,
+    pub(super) count: u64,
+}
+
+impl<R: Read> Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Options {
+    /// fail on inputs which contain a block of a known collision attack (MD5 only).
+    pub detect_collisions: bool,
+    /// hash files with CRLF line ends turned into LF.
+    pub text: bool,
+    /// expected digests are digests of the digest of the file.
+    pub double: bool,
+    /// compare recorded size and modification time before hashing.
+    pub metadata: bool,
+    /// report bytes hashed and their digest when a read fails.
+    pub partial_on_error: bool,
+    /// expected digests are Merkle tree hashes of chunks of this size.
+    pub merkle: Option<usize>,
+    /// chunks hashed at once in Merkle mode.
+    pub jobs: usize,
+}
+
+/// size and modification time of a file, recorded in a checksum line as
+/// `[size=N mtime=SECONDS.NANOSECONDS] ` before the usual line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metadata {
+    pub size: u64,
+    /// since the unix epoch.
+    pub mtime: Duration,
+}
+
+impl Metadata {
+    pub fn of(path: &Path) -> io::Result<Metadata> {
+        let meta = fs::metadata(path)?;
+        let mtime = meta
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "modified before the unix epoch")
+            })?;
+        Ok(Metadata {
+            size: meta.len(),
+            mtime,
+        })
+    }
+
+    pub fn mtime_string(&self) -> String {
+        format!("{}.{:09}", self.mtime.as_secs(), self.mtime.subsec_nanos())
+    }
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[size={} mtime={}] ", self.size, self.mtime_string())
+    }
+}
+
+/// file checked by a line of a checksum file.
+pub struct Entry {
+    pub path: PathBuf,
+    pub digest: hash::Digest,
+    /// bytes hashed before the file data; empty for lines without salt.
+    pub salt: Vec<u8>,
+    pub metadata: Option<Metadata>,
+    /// digest is of the digest of the file, as by a BSD line labelled `SHA256D`.
+    pub double: bool,
+}
+
 /// check line in checksum file
-pub fn line(line: &str) -> Result<(), Error> {
-    let (path, expected_digest) = parse_checksum_line(line)?;
-    let r = input::Input::new(&path)?;
+/// returns number of bytes hashed.
+pub fn line(line: &str, opts: &Options) -> Result<u64, Error> {
+    verify(&parse_checksum_line(line)?, opts)
+}
+
+/// check file of `entry` against its digest.
+/// returns number of bytes hashed.
+pub fn verify(entry: &Entry, opts: &Options) -> Result<u64, Error> {
+    if let (true, Some(expected)) = (opts.metadata, entry.metadata) {
+        let actual = Metadata::of(&entry.path)?;
+        if expected.size != actual.size {
+            return Err(Error::SizeIncorrect(expected.size, actual.size));
+        }
+        if expected.mtime != actual.mtime {
+            return Err(Error::MtimeIncorrect(expected, actual));
+        }
+    }
+    let r = input::Input::new(&entry.path)?;
+    let mut counter = Counter {
+        inner: match opts.text {
+            true => Box::new(input::Text::new(r)) as Box<dyn Read>,
+            false => Box::new(r),
+        },
+        count: 0,
+    };
+    let r = entry.salt.as_slice().chain(&mut counter);
 
-    let actual_digest = match expected_digest {
-        hash::Digest::MD5(_) => hash::digest(r, hash::Func::MD5)?,
-        hash::Digest::SHA256(_) => hash::digest(r, hash::Func::SHA256)?,
+    let (actual_digest, collision) = match entry.digest {
+        hash::Digest::MD5(_) if opts.detect_collisions => {
+            let (digest, collision) = hash::md5_detect_collisions(r)?;
+            (hash::Digest::MD5(digest), collision)
+        }
+        _ if opts.merkle.is_some() => {
+            let chunk_size = opts.merkle.unwrap_or_default();
+            let f = entry.digest.func();
+            (merkle::digest(r, f, chunk_size, opts.jobs)?, false)
+        }
+        _ if opts.partial_on_error => (
+            hash::digest_partial(r, entry.digest.func()).map_err(Error::Partial)?,
+            false,
+        ),
+        hash::Digest::MD5(_) => (hash::digest(r, hash::Func::MD5)?, false),
+        hash::Digest::SHA256(_) => (hash::digest(r, hash::Func::SHA256)?, false),
+    };
+    let actual_digest = match opts.double || entry.double {
+        true => hash::digest(actual_digest.as_bytes(), actual_digest.func())?,
+        false => actual_digest,
     };
 
-    if expected_digest != actual_digest {
-        Err(Error::DigestIncorrect)
+    if entry.digest != actual_digest {
+        Err(Error::DigestIncorrect(actual_digest))
+    } else if collision {
+        Err(Error::CollisionDetected)
     } else {
-        Ok(())
+        Ok(counter.count)
     }
 }
 
+/// path, expected digest and salt of the file checked by line in checksum file
+pub fn parse(line: &str) -> Result<Entry, ParseChecksumLineError> {
+    parse_checksum_line(line)
+}
+
 #[derive(Debug)]
 pub enum ParseChecksumLineError {
     UnrecognizeLine,
     CapturePath,
     CaptureDigest,
+    InvalidEscape,
+    ParseMetadata,
+    ParseSalt(hex::DecodeError),
     ParseDigest(ParseDigestError),
 }
 
@@ -80,6 +239,9 @@ impl fmt::Display for ParseChecksumLineError {
             ParseChecksumLineError::UnrecognizeLine => write!(f, "line is unrecognize"),
             ParseChecksumLineError::CapturePath => write!(f, "fail to capture path"),
             ParseChecksumLineError::CaptureDigest => write!(f, "fail to capture digest"),
+            ParseChecksumLineError::InvalidEscape => write!(f, "invalid escape in path"),
+            ParseChecksumLineError::ParseMetadata => write!(f, "metadata out of range"),
+            ParseChecksumLineError::ParseSalt(err) => write!(f, "parse salt: {}", err),
             ParseChecksumLineError::ParseDigest(err) => write!(f, "parse digest: {}", err),
         }
     }
@@ -91,6 +253,9 @@ impl error::Error for ParseChecksumLineError {
             ParseChecksumLineError::UnrecognizeLine => None,
             ParseChecksumLineError::CapturePath => None,
             ParseChecksumLineError::CaptureDigest => None,
+            ParseChecksumLineError::InvalidEscape => None,
+            ParseChecksumLineError::ParseMetadata => None,
+            ParseChecksumLineError::ParseSalt(ref e) => Some(e),
             ParseChecksumLineError::ParseDigest(ref e) => Some(e),
         }
     }
@@ -102,75 +267,141 @@ impl From<ParseDigestError> for ParseChecksumLineError {
     }
 }
 
-fn parse_checksum_line(line: &str) -> Result<(PathBuf, hash::Digest), ParseChecksumLineError> {
+/// GNU style lines may mark the file as read in binary mode by `*`
+/// before its name, as written by Windows builds of sha256sum.
+/// lines starting with `\` have an escaped name.
+/// salted lines have the hex salt and `:` before the digest.
+/// lines may start with the file's `Metadata`.
+/// BSD lines labelled like `SHA256D` hold the digest of the digest.
+fn parse_checksum_line(line: &str) -> Result<Entry, ParseChecksumLineError> {
+    lazy_static! {
+        static ref METADATA_RE: Regex =
+            Regex::new(r"^\[size=([0-9]+) mtime=([0-9]+)\.([0-9]{9})\] ")
+                .expect("metadata regex must be valid");
+    }
+    let (line, metadata) = match METADATA_RE.captures(line) {
+        Some(caps) => {
+            let number = |i: usize| {
+                caps[i]
+                    .parse::<u64>()
+                    .map_err(|_| ParseChecksumLineError::ParseMetadata)
+            };
+            let metadata = Metadata {
+                size: number(1)?,
+                mtime: Duration::new(number(2)?, number(3)? as u32),
+            };
+            (&line[caps[0].len()..], Some(metadata))
+        }
+        None => (line, None),
+    };
+    let (line, escaped) = match line.strip_prefix('\\') {
+        Some(line) => (line, true),
+        None => (line, false),
+    };
     lazy_static! {
         static ref SHA256_GNU_STYLE_RE: Regex =
-            Regex::new(r"^([[:alpha:]|0-9]{64})[[:space:]]+(.+)$")
+            Regex::new(r"^(?:([[:xdigit:]]+):)?([[:alpha:]|0-9]{64})(?: \*|[[:space:]]+)(.+)$")
                 .expect("sha256 gnu regex must be valid");
     }
     lazy_static! {
-        static ref SHA256_BSD_STYLE_RE: Regex =
-            Regex::new(r"^SHA256 \((.+)\)[[:space:]]*={1}[[:space:]]*([[:alpha:]|0-9]{64})$")
-                .expect("sha256 bsd regex must be valid");
+        static ref SHA256_BSD_STYLE_RE: Regex = Regex::new(
+            r"^SHA256(D?) \((.+)\)[[:space:]]*={1}[[:space:]]*(?:([[:xdigit:]]+):)?([[:alpha:]|0-9]{64})$"
+        )
+        .expect("sha256 bsd regex must be valid");
     }
     lazy_static! {
-        static ref MD5_GNU_STYLE_RE: Regex = Regex::new(r"^([[:alpha:]|0-9]{32})[[:space:]]+(.+)$")
-            .expect("md5 gnu regex must be valid");
+        static ref MD5_GNU_STYLE_RE: Regex =
+            Regex::new(r"^(?:([[:xdigit:]]+):)?([[:alpha:]|0-9]{32})(?: \*|[[:space:]]+)(.+)$")
+                .expect("md5 gnu regex must be valid");
     }
     lazy_static! {
-        static ref MD5_BSD_STYLE_RE: Regex =
-            Regex::new(r"^MD5 \((.+)\)[[:space:]]*={1}[[:space:]]*([[:alpha:]|0-9]{32})$")
-                .expect("md5 bsd regex must be valid");
-    }
-
-    let (path, expected_digest, hf) = if let Some(caps) = SHA256_GNU_STYLE_RE.captures(line) {
-        let path = caps
-            .get(2)
-            .ok_or(ParseChecksumLineError::CapturePath)?
-            .as_str();
-        let expected_digest = caps
-            .get(1)
-            .ok_or(ParseChecksumLineError::CaptureDigest)?
-            .as_str();
-        (path, expected_digest, hash::Func::SHA256)
+        static ref MD5_BSD_STYLE_RE: Regex = Regex::new(
+            r"^MD5(D?) \((.+)\)[[:space:]]*={1}[[:space:]]*(?:([[:xdigit:]]+):)?([[:alpha:]|0-9]{32})$"
+        )
+        .expect("md5 bsd regex must be valid");
+    }
+
+    // capture groups of path, salt and digest. BSD lines capture the `D`
+    // of a double digest first.
+    const GNU: (usize, usize, usize) = (3, 1, 2);
+    const BSD: (usize, usize, usize) = (2, 3, 4);
+    let (caps, (path, salt, digest), hf) = if let Some(caps) = SHA256_GNU_STYLE_RE.captures(line) {
+        (caps, GNU, hash::Func::SHA256)
     } else if let Some(caps) = SHA256_BSD_STYLE_RE.captures(line) {
-        let path = caps
-            .get(1)
-            .ok_or(ParseChecksumLineError::CapturePath)?
-            .as_str();
-        let expected_digest = caps
-            .get(2)
-            .ok_or(ParseChecksumLineError::CaptureDigest)?
-            .as_str();
-        (path, expected_digest, hash::Func::SHA256)
+        (caps, BSD, hash::Func::SHA256)
     } else if let Some(caps) = MD5_GNU_STYLE_RE.captures(line) {
-        let path = caps
-            .get(2)
-            .ok_or(ParseChecksumLineError::CapturePath)?
-            .as_str();
-        let expected_digest = caps
-            .get(1)
-            .ok_or(ParseChecksumLineError::CaptureDigest)?
-            .as_str();
-        (path, expected_digest, hash::Func::SHA256)
-    } else if let Some(caps) = MD5_GNU_STYLE_RE.captures(line) {
-        let path = caps
-            .get(1)
-            .ok_or(ParseChecksumLineError::CapturePath)?
-            .as_str();
-        let expected_digest = caps
-            .get(2)
-            .ok_or(ParseChecksumLineError::CaptureDigest)?
-            .as_str();
-        (path, expected_digest, hash::Func::SHA256)
+        (caps, GNU, hash::Func::MD5)
+    } else if let Some(caps) = MD5_BSD_STYLE_RE.captures(line) {
+        (caps, BSD, hash::Func::MD5)
     } else {
         return Err(ParseChecksumLineError::UnrecognizeLine);
     };
+    let double = (path, salt, digest) == BSD && &caps[1] == "D";
+    let path = caps
+        .get(path)
+        .ok_or(ParseChecksumLineError::CapturePath)?
+        .as_str();
+    let expected_digest = caps
+        .get(digest)
+        .ok_or(ParseChecksumLineError::CaptureDigest)?
+        .as_str();
+    let salt = match caps.get(salt) {
+        Some(salt) => hex::decode(salt.as_str()).map_err(ParseChecksumLineError::ParseSalt)?,
+        None => Vec::new(),
+    };
+
+    let path = match escaped {
+        true => unescape(path).ok_or(ParseChecksumLineError::InvalidEscape)?,
+        false => PathBuf::from(path),
+    };
+    let digest = parse_digest(expected_digest, hf)?;
+
+    Ok(Entry {
+        path,
+        digest,
+        salt,
+        metadata,
+        double,
+    })
+}
+
+/// name with the escapes written by GNU coreutils undone, including
+/// `\xHH` for bytes which are not UTF-8.
+fn unescape(name: &str) -> Option<PathBuf> {
+    let mut unescaped = Vec::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        match chars.next()? {
+            '\\' => unescaped.push(b'\\'),
+            'n' => unescaped.push(b'\n'),
+            'r' => unescaped.push(b'\r'),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+                unescaped.push(u8::from_str_radix(&hex, 16).ok()?);
+            }
+            _ => return None,
+        }
+    }
+    path_from_bytes(unescaped)
+}
 
-    let path = PathBuf::from(path);
-    let expected_digest = parse_digest(expected_digest, hf)?;
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(std::ffi::OsString::from_vec(bytes).into())
+}
 
-    Ok((path, expected_digest))
+/// names which are not UTF-8 can not be opened.
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    String::from_utf8(bytes).ok().map(PathBuf::from)
 }
 
 #[derive(Debug)]
@@ -219,8 +450,7 @@ fn parse_digest_md5(s: &str) -> Result<md5::Digest, ParseDigestError> {
         return Err(ParseDigestError::InvalidStrLen {
             expected: md5::DIGEST_STR_LEN,
             actual: s.len(),
-        }
-        .into());
+        });
     }
 
     let mut digest = [0u8; md5::DIGEST_BYTE_SIZE];
@@ -1,28 +1,230 @@
+use std::borrow::Cow;
 use std::error;
+use std::io::{self, Read};
 use std::path;
 
+use crate::libs::art;
 use crate::libs::hash;
+use crate::libs::hex;
 use crate::libs::input;
+use crate::libs::merkle;
+use crate::libs::qr;
+use crate::libs::report;
+use crate::libs::rng;
+
+use super::check;
+use super::template;
 
 type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
-#[derive(Clone, Copy)]
+/// length of the random salt of --salt lines.
+const SALT_BYTE_SIZE: usize = 16;
+
+/// columns of the rows of --format.
+pub const COLUMNS: [&str; 4] = ["algorithm", "digest", "path", "size"];
+
+pub const COLLISION_DETECTED: &str = "input contains a block of a known collision attack";
+
+#[derive(Clone)]
 pub enum Style {
-    BSD,
-    GNU,
+    Bsd,
+    Gnu,
+    /// raw digest bytes without name or line end, for piping.
+    Binary,
+    /// line formatted by the user, as by --template.
+    Template(template::Template),
+    /// row of `COLUMNS` in a spreadsheet format; the name is not escaped.
+    Report(report::Format),
+}
+
+#[derive(Clone)]
+pub struct Options {
+    pub style: Style,
+    pub bits: Option<u64>,
+    /// digest the digest again, like SHA256d.
+    pub double: bool,
+    /// functions applied to the digest one after another, as by --pipe.
+    pub then: Vec<hash::Func>,
+    pub detect_collisions: bool,
+    pub randomart: bool,
+    pub qr: bool,
+    /// standard input is empty instead of read.
+    pub stdin_null: bool,
+    /// CRLF line ends are hashed as LF.
+    pub text: bool,
+    /// hash a fresh random salt before the data of every line.
+    pub salt: bool,
+    /// record size and modification time of files before their lines.
+    pub metadata: bool,
+    /// report bytes hashed and their digest when a read fails.
+    pub partial_on_error: bool,
+    /// Merkle tree hash of chunks of this size, see `merkle::digest`.
+    pub merkle: Option<usize>,
+    /// chunks hashed at once in Merkle mode.
+    pub jobs: usize,
 }
 
-pub fn println(f: &path::PathBuf, hf: hash::Func, style: Style) -> Result<()> {
-    let r = input::Input::new(&f)?;
-    let digest = hash::digest(r, hf)?;
+pub fn writeln<W: io::Write>(
+    w: &mut W,
+    f: &path::PathBuf,
+    hf: hash::Func,
+    opts: &Options,
+) -> Result<()> {
+    let r = input::Input::with_stdin_null(f, opts.stdin_null)?;
+    // taken before reading, so changes while hashing are caught by --check.
+    let metadata = match r {
+        input::Input::File(_) if opts.metadata || matches!(opts.style, Style::Template(_)) => {
+            Some(check::Metadata::of(f)?)
+        }
+        _ => None,
+    };
+    let name = name_bytes(f);
+    match opts.text {
+        true => write_line(w, input::Text::new(r), &name, metadata, hf, opts),
+        false => write_line(w, r, &name, metadata, hf, opts),
+    }
+}
+
+/// write checksum line of data of `r` named `name`.
+pub fn writeln_reader<W: io::Write, R: io::Read>(
+    w: &mut W,
+    r: R,
+    name: &str,
+    hf: hash::Func,
+    opts: &Options,
+) -> Result<()> {
+    write_line(w, r, name.as_bytes(), None, hf, opts)
+}
+
+fn write_line<W: io::Write, R: io::Read>(
+    w: &mut W,
+    r: R,
+    name: &[u8],
+    metadata: Option<check::Metadata>,
+    hf: hash::Func,
+    opts: &Options,
+) -> Result<()> {
+    let salt = match opts.salt {
+        true => {
+            let mut salt = vec![0u8; SALT_BYTE_SIZE];
+            rng::Rng::from_os()?.read_exact(&mut salt)?;
+            salt
+        }
+        false => Vec::new(),
+    };
+    let mut counter = check::Counter { inner: r, count: 0 };
+    let r = salt.as_slice().chain(&mut counter);
+
+    let (digest, collision) = match (opts.bits, hf) {
+        (Some(bits), _) => (hash::digest_bits(r, hf, bits)?, false),
+        (None, hash::Func::MD5) if opts.detect_collisions => {
+            let (digest, collision) = hash::md5_detect_collisions(r)?;
+            (hash::Digest::MD5(digest), collision)
+        }
+        (None, _) if opts.merkle.is_some() => {
+            let chunk_size = opts.merkle.unwrap_or_default();
+            (merkle::digest(r, hf, chunk_size, opts.jobs)?, false)
+        }
+        (None, _) if opts.partial_on_error => (hash::digest_partial(r, hf)?, false),
+        (None, _) => (hash::digest(r, hf)?, false),
+    };
+    let digest = match opts.double {
+        true => hash::digest(digest.as_bytes(), hf)?,
+        false => digest,
+    };
+    let digest = opts
+        .then
+        .iter()
+        .try_fold(digest, |digest, &f| hash::digest(digest.as_bytes(), f))?;
+    let label = match (opts.then.is_empty(), opts.double) {
+        // like SHA256D, so --check of BSD lines knows to digest twice.
+        (true, true) => format!("{}D", hf),
+        (true, false) => hf.to_string(),
+        (false, _) => hash::Chain([&[hf], opts.then.as_slice()].concat()).to_string(),
+    };
 
-    // TODO: handle unwrap
-    let name = f.to_str().unwrap();
+    let shown = match salt.is_empty() {
+        true => digest.to_string(),
+        false => format!("{}:{}", hex::encode(&salt), digest),
+    };
 
-    match style {
-        Style::BSD => println!("{} ({}) = {}", hf, name, digest),
-        Style::GNU => println!("{}  {}", digest, name),
+    let raw = String::from_utf8_lossy(name);
+    let (escaped, name) = escape(name);
+    let prefix = match metadata {
+        Some(ref metadata) if opts.metadata => format!("{}{}", metadata, escaped),
+        _ => escaped.to_string(),
+    };
+    match opts.style {
+        Style::Bsd => writeln!(w, "{}{} ({}) = {}", prefix, label, name, shown)?,
+        Style::Gnu => writeln!(w, "{}{}  {}", prefix, shown, name)?,
+        Style::Binary => w.write_all(digest.as_bytes())?,
+        Style::Template(ref t) => {
+            let fields = template::Fields {
+                algorithm: &label,
+                digest: &digest,
+                path: &name,
+                size: counter.count,
+                metadata,
+            };
+            t.write(w, &fields)?
+        }
+        Style::Report(format) => {
+            let size = counter.count.to_string();
+            format.row(w, &[&label, &digest.to_string(), &raw, &size])?
+        }
+    }
+    if opts.randomart {
+        write!(w, "{}", art::randomart("", &label, digest.as_bytes()))?;
+    }
+    if opts.qr {
+        let code = qr::QrCode::encode(digest.to_string().as_bytes())?;
+        write!(w, "{}", code.to_terminal())?;
     }
 
+    if collision {
+        return Err(COLLISION_DETECTED.into());
+    }
     Ok(())
 }
+
+/// name as written by GNU coreutils: a name with `\`, LF or CR has them
+/// escaped and its line starts with `\`, so it stays on one line. bytes
+/// which are not UTF-8 are escaped as `\xHH` in the same way.
+fn escape(name: &[u8]) -> (&'static str, Cow<'_, str>) {
+    if let Ok(name) = std::str::from_utf8(name) {
+        if !name.contains(['\\', '\n', '\r']) {
+            return ("", Cow::Borrowed(name));
+        }
+    }
+    let mut escaped = String::with_capacity(name.len() + 2);
+    for chunk in name.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                c => escaped.push(c),
+            }
+        }
+        for b in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", b));
+        }
+    }
+    ("\\", Cow::Owned(escaped))
+}
+
+/// bytes of the name of `f`, which need not be UTF-8.
+#[cfg(unix)]
+fn name_bytes(f: &path::Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(f.as_os_str().as_bytes())
+}
+
+/// bytes of the name of `f`, with unpaired surrogates replaced.
+#[cfg(not(unix))]
+fn name_bytes(f: &path::Path) -> Cow<'_, [u8]> {
+    match f.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
@@ -0,0 +1,225 @@
+//! checksum lines in a format given by the user, like
+//! `{digest}  {path}  {size}`, for reports without post-processing.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str;
+
+use crate::base64::encoder;
+use crate::libs::hash;
+
+use super::check;
+
+/// fields which can be placed in a template.
+const FIELDS: [&str; 6] = [
+    "algorithm",
+    "digest",
+    "digest_base64",
+    "path",
+    "size",
+    "mtime",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(&'static str),
+}
+
+/// text with `{field}` placeholders; `{{` and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<Part>);
+
+/// values of the fields of one line.
+pub struct Fields<'a> {
+    pub algorithm: &'a str,
+    pub digest: &'a hash::Digest,
+    /// escaped like in GNU lines, so it stays on one line.
+    pub path: &'a str,
+    /// bytes hashed.
+    pub size: u64,
+    /// known for regular files only; `-` is written otherwise.
+    pub metadata: Option<check::Metadata>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or(format!("unterminated field in template {:?}", s))?;
+                    let name = &rest[..end];
+                    let field = FIELDS.into_iter().find(|&f| f == name).ok_or(format!(
+                        "unknown field {:?} in template ({})",
+                        name,
+                        FIELDS.join(", ")
+                    ))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(format!("unmatched '}}' in template {:?}", s)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Template(parts))
+    }
+
+    /// writes the line of `fields`, ended by LF.
+    pub fn write<W: Write>(&self, w: &mut W, fields: &Fields) -> io::Result<()> {
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => w.write_all(text.as_bytes())?,
+                Part::Field(name) => write!(w, "{}", Value(name, fields))?,
+            }
+        }
+        writeln!(w)
+    }
+}
+
+impl str::FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Template::parse(s)
+    }
+}
+
+/// value of the field named `.0`.
+struct Value<'a>(&'a str, &'a Fields<'a>);
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self.1;
+        match self.0 {
+            "algorithm" => write!(f, "{}", fields.algorithm),
+            "digest" => write!(f, "{}", fields.digest),
+            "digest_base64" => {
+                let mut out = Vec::new();
+                let mut encoder = encoder::Encoder::new(&mut out);
+                encoder
+                    .write_all(fields.digest.as_bytes())
+                    .and_then(|_| encoder.finish())
+                    .map_err(|_| fmt::Error)?;
+                drop(encoder);
+                write!(f, "{}", String::from_utf8_lossy(&out))
+            }
+            "path" => write!(f, "{}", fields.path),
+            "size" => write!(f, "{}", fields.size),
+            "mtime" => match fields.metadata {
+                Some(ref metadata) => write!(f, "{}", metadata.mtime_string()),
+                None => write!(f, "-"),
+            },
+            name => unreachable!("unknown field {}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    macro_rules! parse_test {
+        ($name:ident,$s:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = Template::parse($s);
+                let expected: Result<Template, String> = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    parse_test!(empty, "", Ok(Template(vec![])));
+    parse_test!(
+        fields,
+        "{digest}  {path}",
+        Ok(Template(vec![
+            Part::Field("digest"),
+            Part::Text("  ".into()),
+            Part::Field("path"),
+        ]))
+    );
+    parse_test!(
+        braces,
+        "{{{size}}}",
+        Ok(Template(vec![
+            Part::Text("{".into()),
+            Part::Field("size"),
+            Part::Text("}".into()),
+        ]))
+    );
+    parse_test!(
+        unknown,
+        "{hash}",
+        Err(
+            "unknown field \"hash\" in template (algorithm, digest, digest_base64, path, size, mtime)"
+                .into()
+        )
+    );
+    parse_test!(
+        unterminated,
+        "{path",
+        Err("unterminated field in template \"{path\"".into())
+    );
+    parse_test!(
+        unmatched,
+        "a}b",
+        Err("unmatched '}' in template \"a}b\"".into())
+    );
+
+    #[test]
+    fn write() {
+        let digest = hash::digest(&b"abc"[..], hash::Func::SHA256).unwrap();
+        let t = Template::parse("{algorithm},{digest_base64},{path},{size},{mtime}").unwrap();
+        let mut fields = Fields {
+            algorithm: "SHA256",
+            digest: &digest,
+            path: "a,b",
+            size: 3,
+            metadata: None,
+        };
+        let mut out = Vec::new();
+        t.write(&mut out, &fields).unwrap();
+        assert_eq!(
+            "SHA256,ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=,a,b,3,-\n",
+            String::from_utf8_lossy(&out)
+        );
+
+        fields.metadata = Some(check::Metadata {
+            size: 3,
+            mtime: Duration::new(1700000000, 5),
+        });
+        let mut out = Vec::new();
+        Template::parse("{mtime} {digest}")
+            .unwrap()
+            .write(&mut out, &fields)
+            .unwrap();
+        assert_eq!(
+            format!("1700000000.000000005 {}\n", digest),
+            String::from_utf8_lossy(&out)
+        );
+    }
+}
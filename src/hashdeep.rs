@@ -0,0 +1,168 @@
+use clap::Args;
+use std::env;
+use std::error;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use crate::libs::hash::{self, Func, Hasher};
+use crate::libs::hashdeep::{self, Entry};
+use crate::libs::input;
+use crate::libs::output;
+use crate::libs::warn;
+
+const BUF_SIZE: usize = 64 * 1024;
+
+#[derive(Args)]
+pub struct Hashdeep {
+    /// Files to digest or audit (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    files: Option<Vec<PathBuf>>,
+
+    /// comma separated hash functions to compute.
+    #[arg(
+        short = 'c',
+        long = "compute",
+        value_name = "FUNCS",
+        value_delimiter = ',',
+        default_value = "md5,sha256"
+    )]
+    funcs: Vec<Func>,
+    /// check that FILEs and the --known manifest match each other:
+    /// every file has a known entry with the same size and digests,
+    /// and every known entry matches a file.
+    #[arg(short, long, requires = "known")]
+    audit: bool,
+    /// hashdeep manifest of known files for --audit.
+    #[arg(short, long, value_name = "FILE")]
+    known: Option<PathBuf>,
+
+    /// write output to FILE instead of standard output.
+    /// the file is replaced atomically when all input is processed.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<PathBuf>,
+    /// octal permissions of the --out file (before umask).
+    #[arg(long, value_name = "MODE", value_parser = output::parse_mode, default_value = "666")]
+    mode: u32,
+}
+
+impl Hashdeep {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let files = self.files.unwrap_or(vec![PathBuf::from(input::STDIN_PATH)]);
+        let out = self.out.unwrap_or(PathBuf::from(output::STDOUT_PATH));
+        let mut out = output::Output::with_mode(&out, self.mode)?;
+
+        let res = match self.known {
+            Some(known) if self.audit => audit(&mut out, &files, &known),
+            _ => manifest(&mut out, &files, &self.funcs),
+        };
+        out.commit()?;
+        res
+    }
+}
+
+fn manifest<W: Write>(
+    w: &mut W,
+    files: &[PathBuf],
+    funcs: &[Func],
+) -> Result<(), Box<dyn error::Error>> {
+    for f in funcs {
+        hash::allowed(*f)?;
+        warn::generating(*f);
+    }
+    write!(w, "{}", hashdeep::header(funcs))?;
+    writeln!(w, "## Invoked from: {}", env::current_dir()?.display())?;
+    writeln!(w, "## $ {}", env::args().collect::<Vec<_>>().join(" "))?;
+    writeln!(w, "## ")?;
+
+    let mut failed = 0;
+    for file in files {
+        match entry(file, funcs) {
+            Ok(entry) => writeln!(w, "{}", entry)?,
+            Err(err) => {
+                eprintln!("hashdeep {:?}: {}", file, err);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} files failed", failed).into());
+    }
+    Ok(())
+}
+
+fn audit<W: Write>(
+    w: &mut W,
+    files: &[PathBuf],
+    known: &PathBuf,
+) -> Result<(), Box<dyn error::Error>> {
+    let known = hashdeep::parse(io::BufReader::new(input::Input::new(known)?))?;
+    for f in known.funcs.iter() {
+        hash::allowed(*f)?;
+    }
+    let mut used = vec![false; known.entries.len()];
+
+    let mut passed = true;
+    for file in files {
+        let entry = match entry(file, &known.funcs) {
+            Ok(entry) => entry,
+            Err(err) => {
+                eprintln!("hashdeep {:?}: {}", file, err);
+                passed = false;
+                continue;
+            }
+        };
+        // files are matched by content, so a moved file still matches.
+        let matched = known
+            .entries
+            .iter()
+            .position(|k| k.size == entry.size && k.digests == entry.digests);
+        match matched {
+            Some(i) => used[i] = true,
+            None => {
+                writeln!(w, "{}: No match", entry.name)?;
+                passed = false;
+            }
+        }
+    }
+    for (k, _) in known.entries.iter().zip(used).filter(|(_, used)| !used) {
+        writeln!(w, "{}: Known file not used", k.name)?;
+        passed = false;
+    }
+
+    if passed {
+        writeln!(w, "hashdeep: Audit passed")?;
+        Ok(())
+    } else {
+        writeln!(w, "hashdeep: Audit failed")?;
+        Err("audit failed".into())
+    }
+}
+
+// size and digests of `file` in one pass.
+fn entry(file: &PathBuf, funcs: &[Func]) -> io::Result<Entry> {
+    let mut r = input::Input::new(file)?;
+    let mut hashers: Vec<Hasher> = funcs.iter().map(|&f| Hasher::new(f)).collect();
+    let mut buf = vec![0; BUF_SIZE];
+    let mut size = 0;
+    loop {
+        let n = match r.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        for hasher in hashers.iter_mut() {
+            hasher.write_all(&buf[..n])?;
+        }
+        size += n as u64;
+    }
+
+    Ok(Entry {
+        size,
+        digests: hashers
+            .into_iter()
+            .map(|h| h.compute().to_string())
+            .collect(),
+        name: file.display().to_string(),
+    })
+}
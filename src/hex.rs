@@ -0,0 +1,77 @@
+pub mod decoder;
+pub mod encoder;
+
+use clap::Args;
+use std::error;
+use std::io::{self, Write};
+use std::path;
+
+use crate::base64::new_liner;
+use crate::libs::input;
+use crate::libs::output;
+
+#[derive(Args)]
+pub struct Hex {
+    #[arg(short, long)]
+    decode: bool,
+    /// when decoding, skip bytes which are not hex digits, like ':' of
+    /// "66:6f:6f". whitespace is always skipped.
+    #[arg(short, long, requires = "decode")]
+    ignore_garbage: bool,
+
+    /// wrap encoded lines after COLS characters; 0 disables wrapping.
+    #[arg(short, long, value_name = "COLS", default_value_t = 76)]
+    wrap: usize,
+    /// encode with upper case digits ('A' to 'F').
+    #[arg(short, long, conflicts_with = "decode")]
+    upper: bool,
+
+    /// File to encode (optional; default is stdin).
+    /// With no FILE, or when FILE is -, read standard input.
+    /// Use ./- for a file named -, and -- to end options before FILE starting with -.
+    file: Option<path::PathBuf>,
+
+    /// read inherited file descriptor N instead of FILE.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(i32).range(0..), conflicts_with = "file")]
+    fd: Option<i32>,
+
+    /// write output to FILE instead of standard output.
+    /// the file is replaced atomically when all input is processed.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<path::PathBuf>,
+    /// octal permissions of the --out file (before umask).
+    #[arg(long, value_name = "MODE", value_parser = output::parse_mode, default_value = "666")]
+    mode: u32,
+}
+
+impl Hex {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let mut input = match self.fd {
+            Some(fd) => input::Input::from_fd(fd)?,
+            None => {
+                let f = self.file.unwrap_or(path::PathBuf::from(input::STDIN_PATH));
+                input::Input::new(&f)?
+            }
+        };
+
+        let out = self.out.unwrap_or(path::PathBuf::from(output::STDOUT_PATH));
+        let mut output = output::Output::with_mode(&out, self.mode)?;
+
+        if self.decode {
+            let mut decoder =
+                decoder::Decoder::new(&mut output).ignore_garbage(self.ignore_garbage);
+            io::copy(&mut input, &mut decoder)?;
+            decoder.finish()?;
+        } else {
+            let new_liner = new_liner::NewLiner::with_line_size(self.wrap, &mut output);
+            let mut encoder = encoder::Encoder::with_case(self.upper, new_liner);
+            io::copy(&mut input, &mut encoder)?;
+            encoder.flush()?;
+            if self.wrap > 0 {
+                writeln!(output)?;
+            }
+        }
+        output.commit()?;
+        Ok(())
+    }
+}
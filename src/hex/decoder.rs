@@ -0,0 +1,172 @@
+use std::io;
+
+/// decoded bytes written at once.
+const CHUNK_BYTE_SIZE: usize = 4096;
+
+/// value of hex digit `c` of either case, or `None` if it is not one.
+fn decode_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// hex (base16) decoder of digits of either case, which writes decoded
+/// data to the inner writer. whitespace in the input is skipped, like
+/// `xxd -r -p`.
+pub struct Decoder<W: io::Write> {
+    /// first digit of a byte whose second digit is not read yet.
+    high: Option<u8>,
+    ignore_garbage: bool,
+    writer: Option<W>,
+}
+
+impl<W: io::Write> io::Write for Decoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.writer.is_none() {
+            panic!("Writer must be present");
+        }
+
+        let mut decode_data = Vec::with_capacity(CHUNK_BYTE_SIZE);
+        for &c in buf {
+            let x = match decode_digit(c) {
+                Some(x) => x,
+                None if c.is_ascii_whitespace() || self.ignore_garbage => continue,
+                None => return Err(invalid_data("invalid hex digit")),
+            };
+            match self.high.take() {
+                Some(high) => decode_data.push(high << 4 | x),
+                None => self.high = Some(x),
+            }
+            if decode_data.len() == CHUNK_BYTE_SIZE {
+                self.writer.as_mut().unwrap().write_all(&decode_data)?;
+                decode_data.clear();
+            }
+        }
+        self.writer.as_mut().unwrap().write_all(&decode_data)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer
+            .as_mut()
+            .expect("Writer must be present")
+            .flush()
+    }
+}
+
+impl<W: io::Write> Drop for Decoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+impl<W: io::Write> Decoder<W> {
+    pub fn new(writer: W) -> Self {
+        Decoder {
+            high: None,
+            ignore_garbage: false,
+            writer: Some(writer),
+        }
+    }
+
+    /// with `ignore` bytes which are not hex digits are skipped
+    /// instead of failing.
+    pub fn ignore_garbage(mut self, ignore: bool) -> Self {
+        self.ignore_garbage = ignore;
+        self
+    }
+
+    /// fails when the input ended inside a byte.
+    pub fn finish(&mut self) -> io::Result<()> {
+        let Some(mut writer) = self.writer.take() else {
+            return Ok(());
+        };
+        if self.high.is_some() {
+            return Err(invalid_data("odd number of hex digits"));
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::encoder::Encoder;
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use std::io::Write;
+    use std::vec::Vec;
+
+    fn decode(data: &str) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out);
+        decoder.write_all(data.as_bytes())?;
+        decoder.finish()?;
+        drop(decoder);
+        Ok(out)
+    }
+
+    macro_rules! decoder {
+        ($name:ident,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = decode($data).ok();
+                let expected: Option<&[u8]> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected.map(<[u8]>::to_vec), actual);
+            }
+        };
+    }
+
+    decoder!(empty, "", Some(b""));
+    decoder!(foobar, "666f6f626172", Some(b"foobar"));
+    decoder!(upper, "DEADbeef", Some(&[0xDE, 0xAD, 0xBE, 0xEF]));
+    decoder!(whitespace, "66 6f\n6f\r\n\t62 61 72\n", Some(b"foobar"));
+    decoder!(odd, "666", None);
+    decoder!(invalid_digit, "6g", None);
+    decoder!(prefix, "0x66", None);
+
+    #[test]
+    fn ignore_garbage() {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out).ignore_garbage(true);
+        decoder.write_all(b"66:6f:6f\n").unwrap();
+        decoder.finish().unwrap();
+        drop(decoder);
+        assert_eq!(out, b"foo");
+    }
+
+    #[test]
+    fn split_byte() {
+        let mut out = Vec::new();
+        let mut decoder = Decoder::new(&mut out);
+        decoder.write_all(b"6").unwrap();
+        decoder.write_all(b"6").unwrap();
+        decoder.finish().unwrap();
+        drop(decoder);
+        assert_eq!(out, b"f");
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(data in vec(any::<u8>(), 0..256), upper: bool) {
+            let mut encoded = Vec::new();
+            Encoder::with_case(upper, &mut encoded).write_all(&data).unwrap();
+
+            let actual = decode(&String::from_utf8(encoded).unwrap()).unwrap();
+
+            prop_assert_eq!(data, actual);
+        }
+    }
+}
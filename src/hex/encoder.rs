@@ -0,0 +1,88 @@
+use std::io;
+
+const LOWER_CODE_VEC: &[u8; 16] = b"0123456789abcdef";
+const UPPER_CODE_VEC: &[u8; 16] = b"0123456789ABCDEF";
+/// input bytes encoded at once.
+const CHUNK_BYTE_SIZE: usize = 4096;
+
+/// hex (base16) encoder, which writes encoded data to the inner writer.
+pub struct Encoder<W: io::Write> {
+    code_vec: &'static [u8; 16],
+    writer: W,
+}
+
+impl<W: io::Write> io::Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(CHUNK_BYTE_SIZE);
+        let mut encode_data = [0u8; 2 * CHUNK_BYTE_SIZE];
+        for (i, &b) in buf[..n].iter().enumerate() {
+            encode_data[2 * i] = self.code_vec[(b >> 4) as usize];
+            encode_data[2 * i + 1] = self.code_vec[(b & 0x0F) as usize];
+        }
+        self.writer.write_all(&encode_data[..2 * n])?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: io::Write> Encoder<W> {
+    /// encoder of lower case digits.
+    pub fn new(writer: W) -> Self {
+        Self::with_case(false, writer)
+    }
+
+    /// encoder of upper case digits with `upper`, else lower case.
+    pub fn with_case(upper: bool, writer: W) -> Self {
+        Encoder {
+            code_vec: match upper {
+                true => UPPER_CODE_VEC,
+                false => LOWER_CODE_VEC,
+            },
+            writer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::vec::Vec;
+
+    macro_rules! encoder {
+        ($name:ident,$upper:expr,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut out = Vec::new();
+                Encoder::with_case($upper, &mut out)
+                    .write_all($data)
+                    .unwrap();
+
+                let actual = String::from_utf8(out).unwrap();
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", $expected);
+
+                assert_eq!($expected, actual);
+            }
+        };
+    }
+
+    // RFC 4648 section 10.
+    encoder!(empty, false, b"", "");
+    encoder!(f, false, b"f", "66");
+    encoder!(foobar, false, b"foobar", "666f6f626172");
+    encoder!(upper, true, b"foobar", "666F6F626172");
+    encoder!(bytes, false, &[0x00, 0x0F, 0xF0, 0xFF], "000ff0ff");
+
+    #[test]
+    fn large() {
+        let data = vec![0xABu8; 3 * CHUNK_BYTE_SIZE + 1];
+        let mut out = Vec::new();
+        Encoder::new(&mut out).write_all(&data).unwrap();
+        assert_eq!(out, "ab".repeat(data.len()).as_bytes());
+    }
+}
@@ -0,0 +1,400 @@
+use clap::Args;
+use std::collections::BTreeMap;
+use std::error;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::hash::check;
+use crate::libs::audit;
+use crate::libs::cancel;
+use crate::libs::hash;
+use crate::libs::input;
+use crate::libs::json::{self, Value};
+use crate::libs::log;
+use crate::libs::notify::{self, Failure};
+use crate::libs::output;
+use crate::libs::toml;
+use crate::libs::walk;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+/// time between runs when the config has no `interval`.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(3600);
+/// how often a sleeping daemon checks for interrupt.
+const WAKEUP_INTERVAL: Duration = Duration::from_millis(500);
+const STATE_VERSION: u64 = 1;
+
+#[derive(Args)]
+pub struct Integrityd {
+    /// config file (TOML), like:
+    ///
+    ///   interval = "1h"                        # s, m, h or d; default 1h
+    ///   state = "/var/lib/ssl/integrityd.json"
+    ///   webhook = "http://127.0.0.1:9000/ssl"  # optional, POSTed on failure
+    ///   on_fail = "page-oncall {path}"          # optional, see `ssl sha256 --on-fail`
+    ///   log_target = "syslog"                  # stderr (default), syslog or journald
+    ///
+    ///   [[manifest]]
+    ///   path = "/srv/SHA256SUMS"
+    ///   root = "/srv"                          # default: directory of the manifest
+    ///
+    ///   [[tree]]
+    ///   path = "/etc"
+    ///
+    /// trees are compared with the SHA-256 digests of their files recorded
+    /// in the state file by the first run (or by --update); added, removed
+    /// and changed files are failures.
+    #[arg(long, value_name = "FILE", verbatim_doc_comment)]
+    config: PathBuf,
+    /// run once and exit with status 1 if anything failed.
+    #[arg(long)]
+    once: bool,
+    /// record the current contents of the trees as their baseline and exit.
+    #[arg(long)]
+    update: bool,
+}
+
+struct Manifest {
+    path: PathBuf,
+    root: PathBuf,
+}
+
+struct Config {
+    interval: Duration,
+    state: PathBuf,
+    notifier: notify::Notifier,
+    log_target: log::Target,
+    manifests: Vec<Manifest>,
+    trees: Vec<PathBuf>,
+}
+
+/// digests of files of a tree by path.
+type Baseline = BTreeMap<String, String>;
+
+impl Integrityd {
+    pub fn exec(self) -> Result<()> {
+        let text = fs::read_to_string(&self.config)
+            .map_err(|err| format!("{:?}: {}", self.config, err))?;
+        let config = parse_config(&text).map_err(|err| format!("{:?}: {}", self.config, err))?;
+        let mut logger = log::open(config.log_target)?;
+        let mut baselines = load_state(&config.state)?;
+        let token = cancel::Token::on_interrupt();
+
+        loop {
+            let failures = run(&config, &mut baselines, self.update, &token);
+            save_state(&config.state, &baselines, failures.len())?;
+            if token.is_cancelled() {
+                return Ok(());
+            }
+            notify(&config, logger.as_mut(), &failures);
+            if self.once || self.update {
+                return match failures.len() {
+                    0 => Ok(()),
+                    n => Err(format!("WARNING: {} FAILS", n).into()),
+                };
+            }
+
+            let mut slept = Duration::ZERO;
+            while slept < config.interval {
+                if token.is_cancelled() {
+                    return Ok(());
+                }
+                let step = WAKEUP_INTERVAL.min(config.interval - slept);
+                thread::sleep(step);
+                slept += step;
+            }
+        }
+    }
+}
+
+/// interval like `90`, `30s`, `15m`, `1h` or `1d`.
+fn parse_interval(s: &str) -> std::result::Result<Duration, String> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(digits);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid interval: {}", s))?;
+    let unit = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("invalid interval unit: {}", unit)),
+    };
+    match number.checked_mul(unit) {
+        Some(0) | None => Err(format!("invalid interval: {}", s)),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+    }
+}
+
+fn parse_config(text: &str) -> std::result::Result<Config, Box<dyn error::Error>> {
+    let v = toml::parse(text)?;
+    let string = |v: &Value, key: &str| -> std::result::Result<Option<String>, String> {
+        match v.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(format!("{} must be a string", key)),
+        }
+    };
+    let tables = |key: &str| -> std::result::Result<&[Value], String> {
+        match v.get(key) {
+            None => Ok(&[]),
+            Some(Value::Array(tables)) => Ok(tables),
+            Some(_) => Err(format!("{} must be an array of tables ([[{}]])", key, key)),
+        }
+    };
+
+    let interval = match v.get("interval") {
+        None => DEFAULT_INTERVAL,
+        Some(Value::Number(n)) => parse_interval(n)?,
+        Some(Value::String(s)) => parse_interval(s)?,
+        Some(_) => return Err("interval must be a string or an integer".into()),
+    };
+    let state = string(&v, "state")?.ok_or("state is required")?;
+    let log_target = match string(&v, "log_target")? {
+        Some(target) => target.parse()?,
+        None => log::Target::Stderr,
+    };
+
+    let mut manifests = Vec::new();
+    for m in tables("manifest")? {
+        let path = PathBuf::from(string(m, "path")?.ok_or("manifest without path")?);
+        let root = match string(m, "root")? {
+            Some(root) => PathBuf::from(root),
+            None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        manifests.push(Manifest { path, root });
+    }
+    let mut trees = Vec::new();
+    for t in tables("tree")? {
+        trees.push(PathBuf::from(
+            string(t, "path")?.ok_or("tree without path")?,
+        ));
+    }
+    if manifests.is_empty() && trees.is_empty() {
+        return Err("nothing to verify: no [[manifest]] or [[tree]]".into());
+    }
+
+    let on_fail = match string(&v, "on_fail")? {
+        Some(cmd) => Some(notify::Template::parse(&cmd)?),
+        None => None,
+    };
+    Ok(Config {
+        interval,
+        state: PathBuf::from(state),
+        notifier: notify::Notifier {
+            on_fail,
+            webhook: string(&v, "webhook")?,
+        },
+        log_target,
+        manifests,
+        trees,
+    })
+}
+
+/// baselines of trees recorded in the state file; none before the first run.
+fn load_state(path: &Path) -> Result<BTreeMap<String, Baseline>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(format!("{:?}: {}", path, err).into()),
+    };
+    let invalid = || format!("{:?}: invalid state file", path);
+    let v = json::parse(&text).map_err(|err| format!("{:?}: {}", path, err))?;
+    if v.get("version").and_then(Value::as_u64) != Some(STATE_VERSION) {
+        return Err(invalid().into());
+    }
+    let mut baselines = BTreeMap::new();
+    if let Some(Value::Object(trees)) = v.get("trees") {
+        for (tree, files) in trees {
+            let Value::Object(files) = files else {
+                return Err(invalid().into());
+            };
+            let mut baseline = Baseline::new();
+            for (file, digest) in files {
+                let digest = digest.as_str().ok_or_else(invalid)?;
+                baseline.insert(file.clone(), digest.to_string());
+            }
+            baselines.insert(tree.clone(), baseline);
+        }
+    }
+    Ok(baselines)
+}
+
+fn save_state(path: &Path, baselines: &BTreeMap<String, Baseline>, failures: usize) -> Result<()> {
+    let trees = baselines
+        .iter()
+        .map(|(tree, files)| {
+            let files = files
+                .iter()
+                .map(|(file, digest)| (file.clone(), digest.as_str().into()))
+                .collect();
+            (tree.clone(), Value::Object(files))
+        })
+        .collect();
+    let v = Value::Object(vec![
+        ("version".to_string(), STATE_VERSION.into()),
+        (
+            "time".to_string(),
+            audit::timestamp(SystemTime::now()).as_str().into(),
+        ),
+        ("failures".to_string(), (failures as u64).into()),
+        ("trees".to_string(), Value::Object(trees)),
+    ]);
+    let mut f = output::AtomicFile::new(path).map_err(|err| format!("{:?}: {}", path, err))?;
+    writeln!(f, "{}", v)?;
+    f.commit().map_err(|err| format!("{:?}: {}", path, err))?;
+    Ok(())
+}
+
+/// verifies everything in the config; stops early when `token` is cancelled.
+fn run(
+    config: &Config,
+    baselines: &mut BTreeMap<String, Baseline>,
+    update: bool,
+    token: &cancel::Token,
+) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    for m in &config.manifests {
+        if token.is_cancelled() {
+            break;
+        }
+        verify_manifest(m, &mut failures, token);
+    }
+    for tree in &config.trees {
+        if token.is_cancelled() {
+            break;
+        }
+        verify_tree(tree, baselines, update, &mut failures, token);
+    }
+    failures
+}
+
+fn verify_manifest(m: &Manifest, failures: &mut Vec<Failure>, token: &cancel::Token) {
+    let source = m.path.to_string_lossy().into_owned();
+    let mut fail =
+        |path: String, expected: Option<String>, actual: Option<String>, error: String| {
+            failures.push(Failure {
+                source: source.clone(),
+                path,
+                expected,
+                actual,
+                error,
+            })
+        };
+    let r = match input::Input::new(&m.path) {
+        Ok(r) => r,
+        Err(err) => return fail(source.clone(), None, None, err.to_string()),
+    };
+    for line in io::BufReader::new(r).lines() {
+        if token.is_cancelled() {
+            return;
+        }
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => return fail(source.clone(), None, None, format!("read line: {}", err)),
+        };
+        let mut entry = match check::parse(&line) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                fail(
+                    source.clone(),
+                    None,
+                    None,
+                    format!("line {:?}: {}", line, err),
+                );
+                continue;
+            }
+        };
+        entry.path = m.root.join(&entry.path);
+        match check::verify(&entry, &check::Options::default()) {
+            Ok(_) => (),
+            Err(err) => {
+                let actual = match err {
+                    check::Error::DigestIncorrect(ref actual) => Some(actual.to_string()),
+                    _ => None,
+                };
+                fail(
+                    entry.path.to_string_lossy().into_owned(),
+                    Some(entry.digest.to_string()),
+                    actual,
+                    err.to_string(),
+                )
+            }
+        }
+    }
+}
+
+fn verify_tree(
+    tree: &Path,
+    baselines: &mut BTreeMap<String, Baseline>,
+    update: bool,
+    failures: &mut Vec<Failure>,
+    token: &cancel::Token,
+) {
+    let source = tree.to_string_lossy().into_owned();
+    let mut fail = |path: &str, expected: Option<&String>, actual: Option<&String>, error: &str| {
+        failures.push(Failure {
+            source: source.clone(),
+            path: path.to_string(),
+            expected: expected.cloned(),
+            actual: actual.cloned(),
+            error: error.to_string(),
+        })
+    };
+    let files = match walk::files(tree) {
+        Ok(files) => files,
+        Err(err) => return fail(&source, None, None, &err.to_string()),
+    };
+
+    let mut current = Baseline::new();
+    for file in files {
+        if token.is_cancelled() {
+            return;
+        }
+        let name = file.to_string_lossy().into_owned();
+        match input::Input::new(&file).and_then(|r| hash::digest(r, hash::Func::SHA256)) {
+            Ok(digest) => {
+                current.insert(name, digest.to_string());
+            }
+            Err(err) => fail(&name, None, None, &err.to_string()),
+        }
+    }
+
+    let baseline = match baselines.get(&source) {
+        Some(baseline) if !update => baseline,
+        // first run records the baseline.
+        _ => {
+            baselines.insert(source.clone(), current);
+            return;
+        }
+    };
+    for (file, digest) in &current {
+        match baseline.get(file) {
+            None => fail(file, None, Some(digest), "added"),
+            Some(expected) if expected != digest => {
+                fail(file, Some(expected), Some(digest), "changed")
+            }
+            Some(_) => (),
+        }
+    }
+    for file in baseline.keys() {
+        if !current.contains_key(file) {
+            fail(file, baseline.get(file), None, "removed");
+        }
+    }
+}
+
+/// reports failures to the log target and the notifier.
+fn notify(config: &Config, logger: &mut dyn log::Backend, failures: &[Failure]) {
+    for f in failures {
+        logger.log(
+            log::Level::Error,
+            &format!("integrity: {:?} in {:?}: {}", f.path, f.source, f.error),
+        );
+    }
+    config.notifier.notify(failures, logger);
+}
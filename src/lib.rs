@@ -2,17 +2,61 @@ use clap::{Parser, Subcommand};
 use std::error;
 use std::fmt;
 
-mod base64;
+mod audit;
+mod audit_verify;
+pub mod base32;
+pub mod base64;
+mod bech32;
+mod cas;
+mod cksum;
+mod cmp_secret;
 mod hash;
-mod libs;
+mod hashdeep;
+pub mod hex;
+mod integrityd;
+pub mod libs;
+mod list;
+mod oci;
+mod pem;
+mod pkcs7;
+mod rand;
+mod rename_by_hash;
+mod repo_check;
+mod tls_dump;
+mod version;
+mod zinfo;
 
 type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// environment variable which turns on --fips when set to 1.
+const FIPS_ENV: &str = "SSL_FIPS";
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// force hash implementation (portable, simd or hw) instead of the
+    /// one selected by the detected cpu extensions; functions without
+    /// the implementation use their portable one.
+    #[arg(long = "impl", value_name = "IMPL", global = true)]
+    implementation: Option<libs::hash::Impl>,
+    /// limit reading of all inputs together to RATE bytes per second,
+    /// like 50M (K, M, G are powers of 1024, KB, MB, GB of 1000).
+    #[arg(long, value_name = "RATE", value_parser = libs::size::parse, global = true)]
+    bwlimit: Option<u64>,
+    /// read files bypassing the page cache (O_DIRECT, linux only), so
+    /// digests of block devices and huge files reflect the bytes on disk.
+    #[arg(long, global = true)]
+    direct: bool,
+    /// refuse hash functions not approved by FIPS 180-4, like MD5.
+    /// also enabled by the environment variable SSL_FIPS=1.
+    #[arg(long, global = true)]
+    fips: bool,
+    /// do not print warnings with CODE, like WEAK_DIGEST; repeatable.
+    #[arg(long, value_name = "CODE", global = true)]
+    no_warn: Vec<libs::warn::Code>,
 }
 
 #[derive(Subcommand)]
@@ -21,7 +65,68 @@ enum Commands {
     MD5(hash::Hash),
     /// compute and check SHA256 message digest
     SHA256(hash::Hash),
+    /// compute and check HASH256 (SHA256 of SHA256) message digest as used by Bitcoin
+    Hash256(hash::Hash),
+    /// report weak algorithms and parameters in certificates, keys and checksum files
+    Audit(audit::Audit),
+    /// check the record chain of a --audit-log file
+    AuditVerify(audit_verify::AuditVerify),
+    /// encode and decode base32 (RFC 4648)
+    Base32(base32::Base32),
     Base64(base64::Base64),
+    /// encode and decode Bech32/Bech32m strings (BIP 173, BIP 350)
+    Bech32(bech32::Bech32),
+    /// put and get blobs of a content-addressed store
+    Cas(cas::Cas),
+    /// compute POSIX cksum CRC or BSD/System V sum checksums
+    Cksum(cksum::Cksum),
+    /// compare two secrets in constant time (exit 0 equal, 1 differ, 2 trouble)
+    CmpSecret(cmp_secret::CmpSecret),
+    /// compute and audit hashdeep manifests with several digests per file
+    Hashdeep(hashdeep::Hashdeep),
+    /// encode and decode hex (base16)
+    Hex(hex::Hex),
+    /// periodically verify configured checksum files and directory trees
+    Integrityd(integrityd::Integrityd),
+    /// list supported algorithms
+    List(list::List),
+    /// verify OCI image layouts
+    Oci(oci::Oci),
+    /// split PEM bundles
+    Pem(pem::Pem),
+    /// pack and unpack PKCS#7 certificate bundles
+    Pkcs7(pkcs7::Pkcs7),
+    /// generate random bytes with ChaCha20 CSPRNG
+    Rand(rand::Rand),
+    /// rename files to their digests, keeping extensions
+    RenameByHash(rename_by_hash::RenameByHash),
+    /// verify files listed by Debian Release/Packages or rpm repomd.xml checksums
+    RepoCheck(repo_check::RepoCheck),
+    /// decode captured TLS records and handshake messages without decryption
+    TlsDump(tls_dump::TlsDump),
+    /// print version, supported algorithms and build information
+    Version(version::Version),
+    /// print gzip/zlib headers and verify stored CRC-32/Adler-32
+    Zinfo(zinfo::Zinfo),
+}
+
+/// exit status for `err` returned by `Cli::run`: `cancel::EXIT_CODE` for
+/// interrupted runs, else 1.
+pub fn exit_code(err: &(dyn error::Error + 'static)) -> i32 {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if err.is::<libs::cancel::Interrupted>() {
+            return libs::cancel::EXIT_CODE;
+        }
+        source = err.source();
+    }
+    1
+}
+
+impl Default for Cli {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Cli {
@@ -30,10 +135,46 @@ impl Cli {
     }
 
     pub fn run(self) -> Result<()> {
+        if let Some(imp) = self.implementation {
+            libs::hash::force_implementation(imp)?;
+        }
+        if let Some(rate) = self.bwlimit {
+            libs::input::set_bwlimit(rate);
+        }
+        if self.direct {
+            libs::input::set_direct()?;
+        }
+        if self.fips || std::env::var_os(FIPS_ENV).is_some_and(|v| v == "1") {
+            libs::hash::set_fips();
+        }
+        for code in self.no_warn.iter() {
+            libs::warn::suppress(*code);
+        }
         match self.command {
             Commands::MD5(cmd) => cmd.exec(hash::Func::MD5)?,
             Commands::SHA256(cmd) => cmd.exec(hash::Func::SHA256)?,
+            Commands::Hash256(cmd) => cmd.exec_double(hash::Func::SHA256)?,
+            Commands::Audit(cmd) => cmd.exec()?,
+            Commands::AuditVerify(cmd) => cmd.exec()?,
+            Commands::Base32(cmd) => cmd.exec()?,
             Commands::Base64(cmd) => cmd.exec()?,
+            Commands::Bech32(cmd) => cmd.exec()?,
+            Commands::Cas(cmd) => cmd.exec()?,
+            Commands::Cksum(cmd) => cmd.exec()?,
+            Commands::CmpSecret(cmd) => cmd.exec()?,
+            Commands::Hashdeep(cmd) => cmd.exec()?,
+            Commands::Hex(cmd) => cmd.exec()?,
+            Commands::Integrityd(cmd) => cmd.exec()?,
+            Commands::List(cmd) => cmd.exec()?,
+            Commands::Oci(cmd) => cmd.exec()?,
+            Commands::Pem(cmd) => cmd.exec()?,
+            Commands::Pkcs7(cmd) => cmd.exec()?,
+            Commands::Rand(cmd) => cmd.exec()?,
+            Commands::RenameByHash(cmd) => cmd.exec()?,
+            Commands::RepoCheck(cmd) => cmd.exec()?,
+            Commands::TlsDump(cmd) => cmd.exec()?,
+            Commands::Version(cmd) => cmd.exec()?,
+            Commands::Zinfo(cmd) => cmd.exec()?,
         }
         Ok(())
     }
@@ -1,3 +1,44 @@
+pub mod art;
+pub mod audit;
+pub mod bech32;
 pub mod bitutils;
-pub mod input;
+pub mod cancel;
+pub mod cas;
+pub mod chacha20;
+pub mod checksum;
+pub mod cpu;
+pub mod der;
+pub mod gzip;
 pub mod hash;
+pub mod hashdeep;
+pub mod hex;
+pub mod inflate;
+pub mod input;
+pub mod json;
+pub mod log;
+pub mod merkle;
+pub mod metrics;
+pub mod notify;
+pub mod oid;
+pub mod output;
+pub mod pem;
+pub mod pkcs7;
+pub mod pool;
+pub mod qr;
+pub mod repo;
+pub mod report;
+pub mod resume;
+pub mod rng;
+pub mod secret;
+pub mod size;
+pub mod tar;
+pub mod throttle;
+pub mod tls;
+pub mod toml;
+pub mod walk;
+pub mod warn;
+pub mod watch;
+pub mod weak;
+pub mod webhook;
+pub mod zip;
+pub mod zlib;
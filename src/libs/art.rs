@@ -0,0 +1,90 @@
+//! OpenSSH randomart ("drunken bishop") visualization of digests.
+
+const WIDTH: usize = 17;
+const HEIGHT: usize = 9;
+// symbols by number of visits; the last two mark start and end of the walk.
+const SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^SE";
+const START: u8 = SYMBOLS.len() as u8 - 2;
+const END: u8 = SYMBOLS.len() as u8 - 1;
+
+/// randomart of `digest` in a 17x9 frame like `ssh-keygen -lv`.
+/// non-empty `title` and `footer` are put in brackets on the top and bottom border.
+pub fn randomart(title: &str, footer: &str, digest: &[u8]) -> String {
+    let mut field = [[0u8; WIDTH]; HEIGHT];
+    let (mut x, mut y) = (WIDTH / 2, HEIGHT / 2);
+
+    for byte in digest {
+        let mut input = *byte;
+        for _ in 0..4 {
+            x = if input & 0x1 != 0 {
+                (x + 1).min(WIDTH - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            y = if input & 0x2 != 0 {
+                (y + 1).min(HEIGHT - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            if field[y][x] < START - 1 {
+                field[y][x] += 1;
+            }
+            input >>= 2;
+        }
+    }
+    field[HEIGHT / 2][WIDTH / 2] = START;
+    field[y][x] = END;
+
+    let mut art = border(title);
+    for row in field {
+        art.push('|');
+        art.extend(row.iter().map(|&v| SYMBOLS[v as usize] as char));
+        art.push_str("|\n");
+    }
+    art.push_str(&border(footer));
+    art
+}
+
+fn border(label: &str) -> String {
+    let label = match label {
+        "" => String::new(),
+        _ => format!("[{}]", label),
+    };
+    let label: String = label.chars().take(WIDTH).collect();
+    let left = (WIDTH - label.len()) / 2;
+    let right = WIDTH - left - label.len();
+    format!("+{}{}{}+\n", "-".repeat(left), label, "-".repeat(right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ssh_keygen() {
+        // `ssh-keygen -lv -E md5` of a generated RSA key.
+        let digest = [
+            0x5d, 0x41, 0x49, 0x49, 0x23, 0x0b, 0x6c, 0x3e, 0x0c, 0x4a, 0xbd, 0xdd, 0xad, 0x6d,
+            0x7b, 0x0b,
+        ];
+        let expected = "\
++---[RSA 2048]----+
+|     . .. .+*o   |
+|    . o o. ooo   |
+|   . . B ....    |
+|    . . =....    |
+|        S..o     |
+|          . o    |
+|           .E.   |
+|            ...  |
+|             ... |
++------[MD5]------+
+";
+        let actual = randomart("RSA 2048", "MD5", &digest);
+
+        println!("  actual:\n{}", actual);
+        println!("expected:\n{}", expected);
+
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,267 @@
+//! tamper-evident log of verification results.
+//! each record is a line of JSON which holds the SHA-256 digest of the previous
+//! line in `prev`, so changing, removing or reordering records breaks the chain:
+//! `{"seq":1,"time":"2024-05-01T12:00:00Z","manifest":"SUMS","line":1,"file":"a",
+//! "algorithm":"SHA256","digest":"...","result":"OK","prev":"000..."}`
+
+use std::error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::libs::hash::{self, Digest, Func};
+use crate::libs::json::{self, Value};
+
+/// `prev` of the first record.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// result of verifying a line of a checksum file.
+pub struct Record<'a> {
+    pub manifest: &'a str,
+    /// 1-based line number in the manifest.
+    pub line: u64,
+    /// unknown when the line is malformed.
+    pub file: Option<&'a str>,
+    pub digest: Option<&'a Digest>,
+    /// `None` when the file matches.
+    pub error: Option<String>,
+}
+
+pub struct Log {
+    file: File,
+    seq: u64,
+    prev: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(usize, json::Error),
+    /// line number and what is wrong with it.
+    Chain(usize, &'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "read: {}", err),
+            Error::Json(line, err) => write!(f, "line {}: {}", line, err),
+            Error::Chain(line, reason) => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            Error::Json(_, ref e) => Some(e),
+            Error::Chain(_, _) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+/// hex SHA-256 digest of a record line (without newline).
+fn link(line: &str) -> String {
+    let mut hasher = hash::Hasher::new(Func::SHA256);
+    hasher
+        .write_all(line.as_bytes())
+        .expect("hasher never fails");
+    hasher.compute().to_string()
+}
+
+/// UTC time in RFC 3339 form, like `2024-05-01T12:00:00Z`.
+pub fn timestamp(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86400, secs % 86400);
+    // civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// checks the chain of records; returns the number of records.
+pub fn verify<R: BufRead>(r: R) -> Result<u64, Error> {
+    let mut prev = GENESIS.to_string();
+    let mut seq = 0;
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let number = i + 1;
+        let record = json::parse(&line).map_err(|err| Error::Json(number, err))?;
+        if record.get("prev").and_then(Value::as_str) != Some(prev.as_str()) {
+            return Err(Error::Chain(number, "previous record digest mismatch"));
+        }
+        if record.get("seq").and_then(Value::as_u64) != Some(seq + 1) {
+            return Err(Error::Chain(number, "unexpected sequence number"));
+        }
+        seq += 1;
+        prev = link(&line);
+    }
+    Ok(seq)
+}
+
+impl Log {
+    /// opens `path` for appending, continuing the chain of its last record.
+    /// the rest of the log is not verified.
+    pub fn open(path: &Path) -> io::Result<Log> {
+        let (seq, prev) = match fs::read_to_string(path) {
+            Ok(text) => match text.lines().last() {
+                Some(last) => {
+                    let seq = json::parse(last)
+                        .ok()
+                        .and_then(|r| r.get("seq").and_then(Value::as_u64))
+                        .ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidData, "last record has no seq")
+                        })?;
+                    (seq, link(last))
+                }
+                None => (0, GENESIS.to_string()),
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (0, GENESIS.to_string()),
+            Err(err) => return Err(err),
+        };
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Log { file, seq, prev })
+    }
+
+    /// appends a record stamped with the current time.
+    pub fn append(&mut self, record: &Record) -> io::Result<()> {
+        self.append_at(record, SystemTime::now())
+    }
+
+    fn append_at(&mut self, record: &Record, time: SystemTime) -> io::Result<()> {
+        let opt = |s: Option<&str>| s.map_or(Value::Null, Value::from);
+        let algorithm = record.digest.map(|d| d.func().to_string());
+        let digest = record.digest.map(|d| d.to_string());
+        let result = match record.error {
+            None => "OK",
+            Some(_) => "FAILED",
+        };
+        let mut fields = vec![
+            ("seq", (self.seq + 1).into()),
+            ("time", timestamp(time).as_str().into()),
+            ("manifest", record.manifest.into()),
+            ("line", record.line.into()),
+            ("file", opt(record.file)),
+            ("algorithm", opt(algorithm.as_deref())),
+            ("digest", opt(digest.as_deref())),
+            ("result", result.into()),
+        ];
+        if let Some(ref err) = record.error {
+            fields.push(("error", err.as_str().into()));
+        }
+        fields.push(("prev", self.prev.as_str().into()));
+        let line = Value::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+        .to_string();
+
+        // one write, so that concurrent appends do not interleave.
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.seq += 1;
+        self.prev = link(&line);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    macro_rules! timestamp_test {
+        ($name:ident,$secs:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = timestamp(UNIX_EPOCH + Duration::from_secs($secs));
+                let expected = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    timestamp_test!(epoch, 0, "1970-01-01T00:00:00Z");
+    timestamp_test!(leap_day, 951782400 + 3661, "2000-02-29T01:01:01Z");
+    timestamp_test!(end_of_year, 1704067199, "2023-12-31T23:59:59Z");
+
+    fn log(path: &Path, files: &[(&str, Option<&str>)]) {
+        let mut log = Log::open(path).unwrap();
+        for (i, (file, error)) in files.iter().enumerate() {
+            let record = Record {
+                manifest: "SUMS",
+                line: i as u64 + 1,
+                file: Some(file),
+                digest: None,
+                error: error.map(String::from),
+            };
+            log.append_at(&record, UNIX_EPOCH).unwrap();
+        }
+    }
+
+    #[test]
+    fn chain() {
+        let path = std::env::temp_dir().join(format!("ssl-audit-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        log(&path, &[("a", None), ("b", Some("digest incorrect"))]);
+        // reopened log continues the chain.
+        log(&path, &[("c", None)]);
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            format!(
+                r#"{{"seq":1,"time":"1970-01-01T00:00:00Z","manifest":"SUMS","line":1,"file":"a","algorithm":null,"digest":null,"result":"OK","prev":"{}"}}"#,
+                GENESIS
+            ),
+            lines[0]
+        );
+        assert!(lines[1].contains(r#""result":"FAILED","error":"digest incorrect""#));
+        assert_eq!(3, verify(text.as_bytes()).unwrap());
+
+        // a changed, removed or reordered record is detected.
+        let changed = text.replacen("FAILED", "OK", 1);
+        assert!(matches!(
+            verify(changed.as_bytes()),
+            Err(Error::Chain(3, "previous record digest mismatch"))
+        ));
+        let removed = format!("{}\n{}\n", lines[0], lines[2]);
+        assert!(verify(removed.as_bytes()).is_err());
+        let reordered = format!("{}\n{}\n{}\n", lines[1], lines[0], lines[2]);
+        assert!(matches!(
+            verify(reordered.as_bytes()),
+            Err(Error::Chain(1, "previous record digest mismatch"))
+        ));
+    }
+}
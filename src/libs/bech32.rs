@@ -0,0 +1,251 @@
+//! Bech32 (BIP 173) and Bech32m (BIP 350) encoding of bytes: a human
+//! readable part, the separator `1`, the data as 5-bit characters and a
+//! 6 character BCH checksum.
+
+use std::error;
+use std::fmt;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const SEPARATOR: char = '1';
+const CHECKSUM_LEN: usize = 6;
+/// longest string allowed by BIP 173.
+const MAX_LEN: usize = 90;
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    Bech32,
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => 0x2bc830a3,
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Bech32 => write!(f, "bech32"),
+            Variant::Bech32m => write!(f, "bech32m"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    TooLong(usize),
+    MixedCase,
+    MissingSeparator,
+    InvalidHrp,
+    InvalidChar(char),
+    InvalidChecksum,
+    InvalidPadding,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TooLong(len) => write!(f, "{} characters, more than {}", len, MAX_LEN),
+            Error::MixedCase => write!(f, "mixed upper and lower case"),
+            Error::MissingSeparator => write!(f, "missing separator '{}'", SEPARATOR),
+            Error::InvalidHrp => write!(f, "invalid human readable part"),
+            Error::InvalidChar(c) => write!(f, "invalid data character {:?}", c),
+            Error::InvalidChecksum => write!(f, "invalid checksum"),
+            Error::InvalidPadding => write!(f, "invalid padding of data"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    let mut chk: u32 = 1;
+    for v in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ v as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes()
+        .map(|c| c >> 5)
+        .chain([0])
+        .chain(hrp.bytes().map(|c| c & 31))
+}
+
+/// regroups `data` of `from` bits per item into items of `to` bits.
+/// with `pad` the last bits are padded with zeros, else they must be
+/// fewer than `to` and zero.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let max = (1 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &v in data {
+        acc = acc << from | v as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push((acc >> bits & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push((acc << (to - bits) & max) as u8);
+        }
+    } else if bits >= from || acc << (to - bits) & max != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+fn valid_hrp(hrp: &str) -> bool {
+    !hrp.is_empty() && hrp.bytes().all(|c| (33..=126).contains(&c))
+}
+
+/// `data` encoded with human readable part `hrp`, in lower case.
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String, Error> {
+    if !valid_hrp(hrp) {
+        return Err(Error::InvalidHrp);
+    }
+    let hrp = hrp.to_ascii_lowercase();
+    let values = convert_bits(data, 8, 5, true).expect("padding never fails");
+    let len = hrp.len() + 1 + values.len() + CHECKSUM_LEN;
+    if len > MAX_LEN {
+        return Err(Error::TooLong(len));
+    }
+
+    let checked = hrp_expand(&hrp)
+        .chain(values.iter().copied())
+        .chain([0; CHECKSUM_LEN]);
+    let checksum = polymod(checked) ^ variant.constant();
+
+    let mut s = String::with_capacity(len);
+    s.push_str(&hrp);
+    s.push(SEPARATOR);
+    for v in values {
+        s.push(CHARSET[v as usize] as char);
+    }
+    for i in 0..CHECKSUM_LEN {
+        s.push(CHARSET[(checksum >> (5 * (5 - i)) & 31) as usize] as char);
+    }
+    Ok(s)
+}
+
+/// human readable part (lower case), data and variant of `s`.
+pub fn decode(s: &str) -> Result<(String, Vec<u8>, Variant), Error> {
+    if s.len() > MAX_LEN {
+        return Err(Error::TooLong(s.len()));
+    }
+    if s.chars().any(|c| c.is_ascii_lowercase()) && s.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err(Error::MixedCase);
+    }
+    let s = s.to_ascii_lowercase();
+    let (hrp, data) = s.rsplit_once(SEPARATOR).ok_or(Error::MissingSeparator)?;
+    if !valid_hrp(hrp) {
+        return Err(Error::InvalidHrp);
+    }
+    if data.len() < CHECKSUM_LEN {
+        return Err(Error::InvalidChecksum);
+    }
+    let values = data
+        .chars()
+        .map(|c| {
+            CHARSET
+                .iter()
+                .position(|&x| x as char == c)
+                .map(|v| v as u8)
+                .ok_or(Error::InvalidChar(c))
+        })
+        .collect::<Result<Vec<u8>, Error>>()?;
+
+    let variant = match polymod(hrp_expand(hrp).chain(values.iter().copied())) {
+        c if c == Variant::Bech32.constant() => Variant::Bech32,
+        c if c == Variant::Bech32m.constant() => Variant::Bech32m,
+        _ => return Err(Error::InvalidChecksum),
+    };
+    let values = &values[..values.len() - CHECKSUM_LEN];
+    let data = convert_bits(values, 5, 8, false).ok_or(Error::InvalidPadding)?;
+    Ok((hrp.to_string(), data, variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! decode_test {
+        ($name:ident,$s:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = decode($s).map(|(hrp, _, variant)| (hrp, variant));
+                let expected: Result<(String, Variant), Error> = $expected;
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    // vectors of BIP 173 and BIP 350.
+    decode_test!(bech32_upper, "A12UEL5L", Ok(("a".into(), Variant::Bech32)));
+    decode_test!(
+        bech32_long_hrp,
+        "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+        Ok((
+            "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio".into(),
+            Variant::Bech32
+        ))
+    );
+    decode_test!(
+        too_long,
+        "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+        Err(Error::TooLong(91))
+    );
+    decode_test!(
+        bech32_split,
+        "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+        Ok(("split".into(), Variant::Bech32))
+    );
+    decode_test!(
+        bech32m_upper,
+        "A1LQFN3A",
+        Ok(("a".into(), Variant::Bech32m))
+    );
+    decode_test!(
+        bech32m_split,
+        "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+        Ok(("split".into(), Variant::Bech32m))
+    );
+    decode_test!(no_separator, "pzry9x0s0muk", Err(Error::MissingSeparator));
+    decode_test!(empty_hrp, "1pzry9x0s0muk", Err(Error::InvalidHrp));
+    decode_test!(invalid_char, "x1b4n0q5v", Err(Error::InvalidChar('b')));
+    decode_test!(short_checksum, "li1dgmt3", Err(Error::InvalidChecksum));
+    decode_test!(uppercase_checksum, "A1G7SGD8", Err(Error::InvalidChecksum));
+    decode_test!(mixed_case, "a12UEL5L", Err(Error::MixedCase));
+
+    #[test]
+    fn round_trip() {
+        let data: Vec<u8> = (0..40).collect();
+        for variant in [Variant::Bech32, Variant::Bech32m] {
+            let s = encode("bc", &data, variant).unwrap();
+            assert_eq!(Ok(("bc".to_string(), data.clone(), variant)), decode(&s));
+        }
+        assert_eq!(
+            Ok("a12uel5l".to_string()),
+            encode("A", &[], Variant::Bech32)
+        );
+        assert!(encode("bc", &[0; 60], Variant::Bech32).is_err());
+    }
+}
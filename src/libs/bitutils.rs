@@ -13,19 +13,19 @@ pub fn as_u32_be(bytes: &[u8]) -> u32 {
     ((bytes[0] as u32) << 24)
         + ((bytes[1] as u32) << 16)
         + ((bytes[2] as u32) << 8)
-        + ((bytes[3] as u32) << 0)
+        + (bytes[3] as u32)
 }
 
 pub fn right_rotate(x: u32, s: usize) -> u32 {
-    (x >> s) | (x << (32 - s))
+    x.rotate_right(s as u32)
 }
 
 pub fn left_rotate(x: u32, s: usize) -> u32 {
-    (x << s) | (x >> (32 - s))
+    x.rotate_left(s as u32)
 }
 
 pub fn as_u32_le(bytes: &[u8]) -> u32 {
-    ((bytes[0] as u32) << 0)
+    (bytes[0] as u32)
         + ((bytes[1] as u32) << 8)
         + ((bytes[2] as u32) << 16)
         + ((bytes[3] as u32) << 24)
@@ -0,0 +1,115 @@
+//! cooperative cancellation on SIGINT (Ctrl-C): long loops stop between
+//! entries and report what was done instead of dying mid-line.
+
+use std::error;
+use std::fmt;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// how often `Token::sleep` looks for cancellation.
+const SLEEP_STEP: Duration = Duration::from_millis(50);
+
+/// exit status of interrupted runs, as shells report a SIGINT death.
+pub const EXIT_CODE: i32 = 130;
+
+#[derive(Clone, Default)]
+pub struct Token(Arc<AtomicBool>);
+
+impl Token {
+    pub fn new() -> Token {
+        Token::default()
+    }
+
+    /// token cancelled by the first SIGINT; a second one exits at once.
+    /// only the first token of a process gets the handler, later ones
+    /// are never cancelled by signals.
+    pub fn on_interrupt() -> Token {
+        let token = Token::new();
+        let handler = token.clone();
+        let _ = ctrlc::set_handler(move || {
+            if handler.0.swap(true, Ordering::SeqCst) {
+                process::exit(EXIT_CODE);
+            }
+        });
+        token
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// sleep for `duration`, or less when cancelled meanwhile.
+    pub fn sleep(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.is_cancelled() {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return;
+            }
+            thread::sleep(left.min(SLEEP_STEP));
+        }
+    }
+}
+
+/// summary of an interrupted run.
+#[derive(Debug, PartialEq)]
+pub struct Interrupted {
+    pub completed: usize,
+    pub failed: usize,
+    pub remaining: usize,
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "interrupted: {} completed, {} failed, {} remaining",
+            self.completed, self.failed, self.remaining
+        )
+    }
+}
+
+impl error::Error for Interrupted {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_state() {
+        let token = Token::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn cancelled_sleep() {
+        let token = Token::new();
+        token.cancel();
+        let start = Instant::now();
+        token.sleep(Duration::from_secs(10));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn summary() {
+        let interrupted = Interrupted {
+            completed: 3,
+            failed: 1,
+            remaining: 7,
+        };
+        assert_eq!(
+            "interrupted: 3 completed, 1 failed, 7 remaining",
+            interrupted.to_string()
+        );
+    }
+}
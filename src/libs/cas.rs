@@ -0,0 +1,180 @@
+//! content-addressed blob store: blobs are named by their SHA-256 digest
+//! and sharded by its first two hex digits, as `DIR/sha256/ab/cdef...`.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::libs::hash::{Func, Hasher};
+use crate::libs::output::AtomicFile;
+
+pub const ALGORITHM: &str = "sha256";
+const DIGEST_LEN: usize = 64;
+const SHARD_LEN: usize = 2;
+/// directory of blobs being written, on the same file system as the blobs.
+const TMP_DIR: &str = "tmp";
+const TMP_NAME: &str = "put";
+const BUF_SIZE: usize = 64 * 1024;
+
+pub struct Store {
+    root: PathBuf,
+}
+
+/// lowercase hex digest of `s`, given as `sha256:HEX` or `HEX`.
+pub fn parse_digest(s: &str) -> Result<String, String> {
+    let hex = match s.split_once(':') {
+        Some((ALGORITHM, hex)) => hex,
+        Some((alg, _)) => return Err(format!("unsupported digest algorithm {:?}", alg)),
+        None => s,
+    };
+    if hex.len() != DIGEST_LEN || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid {} digest {:?}", ALGORITHM, s));
+    }
+    Ok(hex.to_ascii_lowercase())
+}
+
+impl Store {
+    pub fn new(root: &Path) -> Store {
+        Store {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// path of blob with hex `digest`.
+    pub fn path(&self, digest: &str) -> PathBuf {
+        let (shard, rest) = digest.split_at(SHARD_LEN);
+        self.root.join(ALGORITHM).join(shard).join(rest)
+    }
+
+    /// stores data of `r` and returns its hex digest.
+    /// the blob is written to a temporary file and renamed into place,
+    /// an existing blob with the same digest is kept.
+    pub fn put<R: Read>(&self, mut r: R) -> io::Result<String> {
+        let tmp_dir = self.root.join(TMP_DIR);
+        fs::create_dir_all(&tmp_dir)?;
+        let mut tmp = AtomicFile::new(&tmp_dir.join(TMP_NAME))?;
+        let mut hasher = Hasher::new(Func::SHA256);
+        let mut buf = vec![0; BUF_SIZE];
+        loop {
+            let n = match r.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            hasher.write_all(&buf[..n])?;
+            tmp.write_all(&buf[..n])?;
+        }
+
+        let digest = hasher.compute().to_string();
+        let path = self.path(&digest);
+        if !path.exists() {
+            // parent is always present: path is under the store root.
+            fs::create_dir_all(path.parent().unwrap())?;
+            tmp.commit_as(&path)?;
+        }
+        Ok(digest)
+    }
+
+    /// copies blob with hex `digest` to `w`. fails with `InvalidData` after
+    /// the copy when the content does not match the digest.
+    pub fn get<W: Write>(&self, digest: &str, w: &mut W) -> io::Result<()> {
+        let mut blob = fs::File::open(self.path(digest))?;
+        let mut hasher = Hasher::new(Func::SHA256);
+        let mut buf = vec![0; BUF_SIZE];
+        loop {
+            let n = match blob.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            hasher.write_all(&buf[..n])?;
+            w.write_all(&buf[..n])?;
+        }
+
+        let actual = hasher.compute().to_string();
+        if actual != digest {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("blob is corrupted: content digest is {}", actual),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    const HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    fn test_store(name: &str) -> (PathBuf, Store) {
+        let root = env::temp_dir().join(format!("ssl-cas-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        let store = Store::new(&root);
+        (root, store)
+    }
+
+    #[test]
+    fn put_get() {
+        let (root, store) = test_store("put-get");
+        assert_eq!(HELLO, store.put(&b"hello"[..]).unwrap());
+        // stored again without error.
+        assert_eq!(HELLO, store.put(&b"hello"[..]).unwrap());
+        assert_eq!(
+            "hello",
+            fs::read_to_string(root.join("sha256/2c").join(&HELLO[2..])).unwrap()
+        );
+        assert_eq!(0, fs::read_dir(root.join(TMP_DIR)).unwrap().count());
+
+        let mut out = Vec::new();
+        store.get(HELLO, &mut out).unwrap();
+        assert_eq!(b"hello", &out[..]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn get_corrupted() {
+        let (root, store) = test_store("corrupted");
+        store.put(&b"hello"[..]).unwrap();
+        fs::write(store.path(HELLO), b"hellO").unwrap();
+
+        let err = store.get(HELLO, &mut Vec::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn get_missing() {
+        let (_, store) = test_store("missing");
+        let err = store.get(HELLO, &mut Vec::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, err.kind());
+    }
+
+    macro_rules! parse_digest_test {
+        ($name:ident,$input:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse_digest($input).ok();
+                let expected: Option<&str> = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected.map(str::to_string), actual);
+            }
+        };
+    }
+
+    parse_digest_test!(plain, HELLO, Some(HELLO));
+    parse_digest_test!(
+        prefixed_upper,
+        &format!("sha256:{}", HELLO.to_uppercase()),
+        Some(HELLO)
+    );
+    parse_digest_test!(other_algorithm, &format!("md5:{}", HELLO), None);
+    parse_digest_test!(short, &HELLO[1..], None);
+    parse_digest_test!(path, &format!("../{}", &HELLO[3..]), None);
+}
@@ -0,0 +1,162 @@
+use crate::libs::bitutils::{as_u32_le, as_u8_le};
+
+pub const KEY_BYTE_SIZE: usize = 32;
+pub const NONCE_BYTE_SIZE: usize = 12;
+const BLOCK_BYTE_SIZE: usize = 64;
+const STATE_WORD_SIZE: usize = 16;
+const ROUNDS: usize = 20;
+
+/// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+/// ChaCha20 keystream generator (RFC 8439).
+/// after 2^32 blocks the block counter carries into the first nonce word,
+/// as in the original variant with 64-bit counter.
+pub struct ChaCha20 {
+    state: [u32; STATE_WORD_SIZE],
+    block: [u8; BLOCK_BYTE_SIZE],
+    block_seed: usize,
+}
+
+impl ChaCha20 {
+    pub fn new(key: &[u8; KEY_BYTE_SIZE], nonce: &[u8; NONCE_BYTE_SIZE], counter: u32) -> Self {
+        let mut state = [0u32; STATE_WORD_SIZE];
+        state[..4].clone_from_slice(&CONSTANTS);
+        for (i, word) in key.chunks(4).enumerate() {
+            state[4 + i] = as_u32_le(word);
+        }
+        state[12] = counter;
+        for (i, word) in nonce.chunks(4).enumerate() {
+            state[13 + i] = as_u32_le(word);
+        }
+
+        ChaCha20 {
+            state,
+            block: [0; BLOCK_BYTE_SIZE],
+            block_seed: BLOCK_BYTE_SIZE,
+        }
+    }
+
+    /// fill `buf` with the next keystream bytes.
+    pub fn keystream(&mut self, buf: &mut [u8]) {
+        buf.fill(0);
+        self.apply_keystream(buf);
+    }
+
+    /// xor `buf` with the next keystream bytes (encrypt or decrypt).
+    pub fn apply_keystream(&mut self, buf: &mut [u8]) {
+        for x in buf.iter_mut() {
+            if self.block_seed == BLOCK_BYTE_SIZE {
+                self.next_block();
+            }
+            *x ^= self.block[self.block_seed];
+            self.block_seed += 1;
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut working = self.state;
+        for _ in 0..ROUNDS / 2 {
+            // column rounds
+            quarter_round(&mut working, 0, 4, 8, 12);
+            quarter_round(&mut working, 1, 5, 9, 13);
+            quarter_round(&mut working, 2, 6, 10, 14);
+            quarter_round(&mut working, 3, 7, 11, 15);
+            // diagonal rounds
+            quarter_round(&mut working, 0, 5, 10, 15);
+            quarter_round(&mut working, 1, 6, 11, 12);
+            quarter_round(&mut working, 2, 7, 8, 13);
+            quarter_round(&mut working, 3, 4, 9, 14);
+        }
+
+        for (i, word) in working.iter().enumerate() {
+            self.block[i * 4..(i + 1) * 4]
+                .clone_from_slice(&as_u8_le(word.wrapping_add(self.state[i])));
+        }
+        self.block_seed = 0;
+
+        self.state[12] = self.state[12].wrapping_add(1);
+        if self.state[12] == 0 {
+            self.state[13] = self.state[13].wrapping_add(1);
+        }
+    }
+}
+
+fn quarter_round(state: &mut [u32; STATE_WORD_SIZE], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::chacha20::cipher::{KeyIvInit, StreamCipher};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    const KEY: [u8; KEY_BYTE_SIZE] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+
+    #[test]
+    fn rfc8439_encryption() {
+        // RFC 8439 2.4.2
+        let nonce = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let mut data = *b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let expected = [
+            0x6e, 0x2e, 0x35, 0x9a, 0x25, 0x68, 0xf9, 0x80, 0x41, 0xba, 0x07, 0x28, 0xdd, 0x0d,
+            0x69, 0x81,
+        ];
+
+        ChaCha20::new(&KEY, &nonce, 1).apply_keystream(&mut data);
+        let actual = &data[..expected.len()];
+
+        println!("  actual: {:X?}", actual);
+        println!("expected: {:X?}", expected);
+
+        assert_eq!(expected, actual);
+    }
+
+    proptest! {
+        #[test]
+        fn reference(
+            nonce in any::<[u8; NONCE_BYTE_SIZE]>(),
+            len in 0usize..512,
+            chunk in 1usize..130,
+        ) {
+            let mut actual = vec![0u8; len];
+            let mut cipher = ChaCha20::new(&KEY, &nonce, 0);
+            for part in actual.chunks_mut(chunk) {
+                cipher.keystream(part);
+            }
+
+            let mut expected = vec![0u8; len];
+            ::chacha20::ChaCha20::new(&KEY.into(), &nonce.into()).apply_keystream(&mut expected);
+
+            prop_assert_eq!(expected, actual);
+        }
+
+        #[test]
+        fn reference_xor(data in vec(any::<u8>(), 0..256)) {
+            let nonce = [0u8; NONCE_BYTE_SIZE];
+
+            let mut actual = data.clone();
+            ChaCha20::new(&KEY, &nonce, 0).apply_keystream(&mut actual);
+
+            let mut expected = data;
+            ::chacha20::ChaCha20::new(&KEY.into(), &nonce.into()).apply_keystream(&mut expected);
+
+            prop_assert_eq!(expected, actual);
+        }
+    }
+}
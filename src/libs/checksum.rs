@@ -0,0 +1,310 @@
+//! non-cryptographic checksums of Unix tools and zlib/gzip.
+//! every checksummer is an `io::Write` sink, so data can be streamed with `io::copy`.
+
+use std::io::{self, Write};
+
+// CRC-32 generator polynomial x^32 + x^26 + ... + 1, MSB first.
+const POSIX_CRC_POLY: u32 = 0x04C11DB7;
+// reflected CRC-32 polynomial of zlib, gzip and PNG.
+const CRC32_POLY: u32 = 0xEDB88320;
+const ADLER_MOD: u32 = 65521;
+// most bytes which can be summed before the adler sums may overflow u32.
+const ADLER_NMAX: usize = 5552;
+const BSD_BLOCK_SIZE: u64 = 1024;
+const SYSV_BLOCK_SIZE: u64 = 512;
+
+const POSIX_CRC_TABLE: [u32; 256] = posix_crc_table();
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const fn posix_crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ POSIX_CRC_POLY
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC of POSIX `cksum`: CRC-32 without reflection over the data followed
+/// by its length in as few bytes as possible, least significant first.
+#[derive(Default)]
+pub struct PosixCrc {
+    crc: u32,
+    len: u64,
+}
+
+impl PosixCrc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, buf: &[u8]) {
+        for &b in buf {
+            self.crc = (self.crc << 8) ^ POSIX_CRC_TABLE[((self.crc >> 24) as u8 ^ b) as usize];
+        }
+    }
+
+    /// checksum and data length in bytes.
+    pub fn finish(mut self) -> (u32, u64) {
+        let len = self.len;
+        let mut n = len;
+        while n != 0 {
+            self.update(&[n as u8]);
+            n >>= 8;
+        }
+        (!self.crc, len)
+    }
+}
+
+impl Write for PosixCrc {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 16 bit rotating checksum of BSD `sum` (`sum -r`).
+#[derive(Default)]
+pub struct BsdSum {
+    sum: u16,
+    len: u64,
+}
+
+impl BsdSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// checksum and number of 1024 byte blocks.
+    pub fn finish(self) -> (u16, u64) {
+        (self.sum, self.len.div_ceil(BSD_BLOCK_SIZE))
+    }
+}
+
+impl Write for BsdSum {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.sum = self.sum.rotate_right(1).wrapping_add(b as u16);
+        }
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// System V `sum -s` checksum: byte sum folded to 16 bits.
+#[derive(Default)]
+pub struct SysvSum {
+    sum: u32,
+    len: u64,
+}
+
+impl SysvSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// checksum and number of 512 byte blocks.
+    pub fn finish(self) -> (u16, u64) {
+        let r = (self.sum & 0xFFFF) + (self.sum >> 16);
+        let sum = (r & 0xFFFF) + (r >> 16);
+        (sum as u16, self.len.div_ceil(SYSV_BLOCK_SIZE))
+    }
+}
+
+impl Write for SysvSum {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.sum = self.sum.wrapping_add(b as u32);
+        }
+        self.len += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// CRC-32 of zlib and gzip (ISO-HDLC).
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32 { crc: !0 }
+    }
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn finish(self) -> u32 {
+        !self.crc
+    }
+}
+
+impl Write for Crc32 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            self.crc = CRC32_TABLE[((self.crc as u8) ^ b) as usize] ^ (self.crc >> 8);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Adler-32 checksum of zlib (RFC 1950).
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Adler32 { a: 1, b: 0 }
+    }
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn finish(self) -> u32 {
+        self.b << 16 | self.a
+    }
+}
+
+impl Write for Adler32 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for chunk in buf.chunks(ADLER_NMAX) {
+            for &b in chunk {
+                self.a += b as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER_MOD;
+            self.b %= ADLER_MOD;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // expected values are from GNU coreutils `cksum`, `sum -r` and `sum -s`.
+    macro_rules! checksum_test {
+        ($name:ident,$data:expr,$crc:expr,$bsd:expr,$sysv:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = $data;
+                let mut crc = PosixCrc::new();
+                let mut bsd = BsdSum::new();
+                let mut sysv = SysvSum::new();
+                // split writes must not change the result.
+                for chunk in data.chunks(7) {
+                    crc.write_all(chunk).unwrap();
+                    bsd.write_all(chunk).unwrap();
+                    sysv.write_all(chunk).unwrap();
+                }
+                let actual = (crc.finish(), bsd.finish(), sysv.finish());
+                let expected = ($crc, $bsd, $sysv);
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    checksum_test!(empty, b"", (4294967295, 0), (0, 0), (0, 0));
+    checksum_test!(hello, b"hello\n", (3015617425, 6), (36979, 1), (542, 1));
+    checksum_test!(check, b"123456789", (930766865, 9), (53615, 1), (477, 1));
+    checksum_test!(
+        blocks,
+        &[b'x'; 3000],
+        (1370179171, 3000),
+        (5357, 3),
+        (32325, 6)
+    );
+
+    macro_rules! zlib_checksum_test {
+        ($name:ident,$data:expr,$crc32:expr,$adler32:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = $data;
+                let mut crc32 = Crc32::new();
+                let mut adler32 = Adler32::new();
+                for chunk in data.chunks(ADLER_NMAX + 1) {
+                    crc32.write_all(chunk).unwrap();
+                    adler32.write_all(chunk).unwrap();
+                }
+                let actual = (crc32.finish(), adler32.finish());
+                let expected: (u32, u32) = ($crc32, $adler32);
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    zlib_checksum_test!(zlib_empty, b"", 0, 1);
+    zlib_checksum_test!(zlib_check, b"123456789", 0xCBF43926, 0x091E01DE);
+    zlib_checksum_test!(zlib_wikipedia, b"Wikipedia", 0xADAAC02E, 0x11E60398);
+    zlib_checksum_test!(zlib_overflow, &[0xFF; 100_000], 0x68C6CEC4, 0x149A302C);
+}
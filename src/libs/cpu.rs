@@ -0,0 +1,51 @@
+/// cpu extensions relevant to hashing and encoding, detected at runtime.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub fn extensions() -> Vec<&'static str> {
+    let mut extensions = Vec::new();
+    if is_x86_feature_detected!("sse2") {
+        extensions.push("sse2");
+    }
+    if is_x86_feature_detected!("ssse3") {
+        extensions.push("ssse3");
+    }
+    if is_x86_feature_detected!("sse4.1") {
+        extensions.push("sse4.1");
+    }
+    if is_x86_feature_detected!("avx2") {
+        extensions.push("avx2");
+    }
+    if is_x86_feature_detected!("avx512f") {
+        extensions.push("avx512f");
+    }
+    if is_x86_feature_detected!("sha") {
+        extensions.push("sha");
+    }
+    if is_x86_feature_detected!("aes") {
+        extensions.push("aes");
+    }
+    extensions
+}
+
+/// cpu extensions relevant to hashing and encoding, detected at runtime.
+#[cfg(target_arch = "aarch64")]
+pub fn extensions() -> Vec<&'static str> {
+    use std::arch::is_aarch64_feature_detected;
+
+    let mut extensions = Vec::new();
+    if is_aarch64_feature_detected!("neon") {
+        extensions.push("neon");
+    }
+    if is_aarch64_feature_detected!("sha2") {
+        extensions.push("sha2");
+    }
+    if is_aarch64_feature_detected!("aes") {
+        extensions.push("aes");
+    }
+    extensions
+}
+
+/// cpu extensions relevant to hashing and encoding, detected at runtime.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub fn extensions() -> Vec<&'static str> {
+    Vec::new()
+}
@@ -0,0 +1,261 @@
+use std::error;
+use std::fmt;
+
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// tag of context-specific constructed `[n]`.
+pub const fn context(n: u8) -> u8 {
+    0xA0 | n
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Truncated,
+    /// multi-byte tags and indefinite or overlong lengths are not supported.
+    Unsupported,
+    UnexpectedTag {
+        expected: u8,
+        actual: u8,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "truncated DER"),
+            Error::Unsupported => write!(f, "unsupported DER encoding"),
+            Error::UnexpectedTag { expected, actual } => {
+                write!(f, "expected tag 0x{:02x}, got 0x{:02x}", expected, actual)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// DER tag-length-value element.
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+    /// whole encoding with tag and length.
+    pub raw: &'a [u8],
+}
+
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.buf.first().copied()
+    }
+
+    pub fn read(&mut self) -> Result<Tlv<'a>> {
+        let (&tag, rest) = self.buf.split_first().ok_or(Error::Truncated)?;
+        if tag & 0x1F == 0x1F {
+            return Err(Error::Unsupported);
+        }
+        let (&first, mut rest) = rest.split_first().ok_or(Error::Truncated)?;
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let n = (first & 0x7F) as usize;
+            if n == 0 || n > std::mem::size_of::<usize>() {
+                return Err(Error::Unsupported);
+            }
+            if rest.len() < n {
+                return Err(Error::Truncated);
+            }
+            let (bytes, tail) = rest.split_at(n);
+            rest = tail;
+            bytes.iter().fold(0, |len, &b| len << 8 | b as usize)
+        };
+        if rest.len() < len {
+            return Err(Error::Truncated);
+        }
+
+        let header = self.buf.len() - rest.len();
+        let raw = &self.buf[..header + len];
+        self.buf = &self.buf[header + len..];
+        Ok(Tlv {
+            tag,
+            content: &raw[header..],
+            raw,
+        })
+    }
+
+    /// read element with tag `expected`.
+    pub fn expect(&mut self, expected: u8) -> Result<Tlv<'a>> {
+        let tlv = self.read()?;
+        if tlv.tag != expected {
+            return Err(Error::UnexpectedTag {
+                expected,
+                actual: tlv.tag,
+            });
+        }
+        Ok(tlv)
+    }
+}
+
+/// append DER encoding of element `tag` with `content` to `out`.
+pub fn write(out: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    out.push(tag);
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+}
+
+/// DER encoding of element `tag` with `content`.
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(&mut out, tag, content);
+    out
+}
+
+/// append element `tag` whose content is written by `f`, for nesting
+/// without building every level by hand.
+pub fn write_with(out: &mut Vec<u8>, tag: u8, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut content = Vec::new();
+    f(&mut content);
+    write(out, tag, &content);
+}
+
+/// ASN.1 type encoded as a SEQUENCE of its fields. a structure declares
+/// its fields once, in `write_fields` and `read_fields`, and gets the
+/// whole DER encoding and decoding from the provided methods.
+pub trait Sequence<'a>: Sized {
+    /// append the DER encoding of each field, in order.
+    fn write_fields(&self, out: &mut Vec<u8>);
+
+    /// fields read from the content of the SEQUENCE. trailing fields that
+    /// are not needed may be left unread.
+    fn read_fields(r: &mut Reader<'a>) -> Result<Self>;
+
+    /// append the SEQUENCE to `out`.
+    fn write(&self, out: &mut Vec<u8>) {
+        write_with(out, TAG_SEQUENCE, |out| self.write_fields(out));
+    }
+
+    fn to_der(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write(&mut out);
+        out
+    }
+
+    /// read the next SEQUENCE of `r`.
+    fn read(r: &mut Reader<'a>) -> Result<Self> {
+        Self::read_fields(&mut Reader::new(r.expect(TAG_SEQUENCE)?.content))
+    }
+
+    fn from_der(data: &'a [u8]) -> Result<Self> {
+        Self::read(&mut Reader::new(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! read_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = &$data;
+                let actual = Reader::new(data)
+                    .read()
+                    .map(|tlv| (tlv.tag, tlv.content.to_vec()));
+                let expected: Result<(u8, Vec<u8>)> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    read_test!(short_form, Ok((TAG_INTEGER, vec![1])), [2, 1, 1, 0xFF]);
+    read_test!(empty_set, Ok((TAG_SET, vec![])), [0x31, 0]);
+    read_test!(
+        long_form,
+        Ok((TAG_SEQUENCE, vec![7; 0x81])),
+        [[0x30, 0x81, 0x81].as_slice(), &[7; 0x81]].concat()
+    );
+    read_test!(truncated, Err(Error::Truncated), [0x30, 3, 1]);
+    read_test!(indefinite, Err(Error::Unsupported), [0x30, 0x80, 0, 0]);
+    read_test!(multi_byte_tag, Err(Error::Unsupported), [0x1F, 0x81, 0]);
+
+    #[derive(Debug, PartialEq)]
+    struct Pair<'a> {
+        first: &'a [u8],
+        second: &'a [u8],
+    }
+
+    impl<'a> Sequence<'a> for Pair<'a> {
+        fn write_fields(&self, out: &mut Vec<u8>) {
+            write(out, TAG_INTEGER, self.first);
+            write_with(out, context(0), |out| write(out, TAG_INTEGER, self.second));
+        }
+
+        fn read_fields(r: &mut Reader<'a>) -> Result<Self> {
+            let first = r.expect(TAG_INTEGER)?.content;
+            let mut explicit = Reader::new(r.expect(context(0))?.content);
+            let second = explicit.expect(TAG_INTEGER)?.content;
+            Ok(Pair { first, second })
+        }
+    }
+
+    #[test]
+    fn sequence() {
+        let pair = Pair {
+            first: &[1],
+            second: &[2, 3],
+        };
+        let data = pair.to_der();
+        assert_eq!(
+            data,
+            [0x30, 0x09, 0x02, 0x01, 0x01, 0xA0, 0x04, 0x02, 0x02, 0x02, 0x03]
+        );
+        assert_eq!(Pair::from_der(&data), Ok(pair));
+        assert_eq!(
+            Pair::from_der(&data[2..]),
+            Err(Error::UnexpectedTag {
+                expected: TAG_SEQUENCE,
+                actual: TAG_INTEGER
+            })
+        );
+    }
+
+    #[test]
+    fn encode_read() {
+        for len in [0, 1, 0x7F, 0x80, 0xFF, 0x100, 0x10000] {
+            let content = vec![0xAB; len];
+            let data = encode(TAG_SEQUENCE, &content);
+            let mut r = Reader::new(&data);
+            let tlv = r.expect(TAG_SEQUENCE).unwrap();
+            assert_eq!(tlv.content, content.as_slice());
+            assert_eq!(tlv.raw, data.as_slice());
+            assert!(r.is_empty());
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! gzip member format (RFC 1952): header, deflate data, CRC-32 and size trailer.
+
+use std::error;
+use std::fmt;
+
+pub const MAGIC: [u8; 2] = [0x1F, 0x8B];
+pub const TRAILER_LEN: usize = 8;
+
+const METHOD_DEFLATE: u8 = 8;
+const FIXED_HEADER_LEN: usize = 10;
+
+const FLAG_TEXT: u8 = 0x01;
+const FLAG_HCRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+const FLAGS_RESERVED: u8 = 0xE0;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Truncated,
+    Magic,
+    Method(u8),
+    ReservedFlags(u8),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "truncated gzip header"),
+            Error::Magic => write!(f, "not a gzip member"),
+            Error::Method(m) => write!(f, "unsupported gzip compression method {}", m),
+            Error::ReservedFlags(flags) => write!(f, "reserved gzip flags 0x{:02x} are set", flags),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[derive(Debug, PartialEq)]
+pub struct Header {
+    pub text: bool,
+    /// modification time in seconds since the Unix epoch; 0 if unknown.
+    pub mtime: u32,
+    pub extra_flags: u8,
+    pub os: u8,
+    pub extra: Option<Vec<u8>>,
+    /// original file name, ISO 8859-1 without the terminating zero.
+    pub name: Option<Vec<u8>>,
+    pub comment: Option<Vec<u8>>,
+    /// low 16 bits of CRC-32 of the header bytes before it.
+    pub crc16: Option<u16>,
+    /// header length in bytes.
+    pub len: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Trailer {
+    /// CRC-32 of the uncompressed data.
+    pub crc32: u32,
+    /// uncompressed size modulo 2^32.
+    pub size: u32,
+}
+
+pub fn header(data: &[u8]) -> Result<Header, Error> {
+    if data.len() < MAGIC.len() || data[..MAGIC.len()] != MAGIC {
+        return Err(Error::Magic);
+    }
+    if data.len() < FIXED_HEADER_LEN {
+        return Err(Error::Truncated);
+    }
+    if data[2] != METHOD_DEFLATE {
+        return Err(Error::Method(data[2]));
+    }
+    let flags = data[3];
+    if flags & FLAGS_RESERVED != 0 {
+        return Err(Error::ReservedFlags(flags & FLAGS_RESERVED));
+    }
+
+    let mut pos = FIXED_HEADER_LEN;
+    let extra = if flags & FLAG_EXTRA != 0 {
+        let len = u16::from_le_bytes(bytes(data, pos)?) as usize;
+        pos += 2;
+        let extra = data.get(pos..pos + len).ok_or(Error::Truncated)?;
+        pos += len;
+        Some(extra.to_vec())
+    } else {
+        None
+    };
+    let mut zero_terminated = |flag: u8| -> Result<Option<Vec<u8>>, Error> {
+        if flags & flag == 0 {
+            return Ok(None);
+        }
+        let rest = data.get(pos..).ok_or(Error::Truncated)?;
+        let len = rest.iter().position(|&b| b == 0).ok_or(Error::Truncated)?;
+        pos += len + 1;
+        Ok(Some(rest[..len].to_vec()))
+    };
+    let name = zero_terminated(FLAG_NAME)?;
+    let comment = zero_terminated(FLAG_COMMENT)?;
+    let crc16 = if flags & FLAG_HCRC != 0 {
+        pos += 2;
+        Some(u16::from_le_bytes(bytes(data, pos - 2)?))
+    } else {
+        None
+    };
+
+    Ok(Header {
+        text: flags & FLAG_TEXT != 0,
+        mtime: u32::from_le_bytes(bytes(data, 4)?),
+        extra_flags: data[8],
+        os: data[9],
+        extra,
+        name,
+        comment,
+        crc16,
+        len: pos,
+    })
+}
+
+pub fn trailer(data: &[u8]) -> Result<Trailer, Error> {
+    Ok(Trailer {
+        crc32: u32::from_le_bytes(bytes(data, 0)?),
+        size: u32::from_le_bytes(bytes(data, 4)?),
+    })
+}
+
+fn bytes<const N: usize>(data: &[u8], pos: usize) -> Result<[u8; N], Error> {
+    data.get(pos..pos + N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or(Error::Truncated)
+}
+
+/// name of the operating system which created the member.
+pub fn os_name(os: u8) -> Option<&'static str> {
+    match os {
+        0 => Some("FAT"),
+        1 => Some("Amiga"),
+        2 => Some("VMS"),
+        3 => Some("Unix"),
+        4 => Some("VM/CMS"),
+        5 => Some("Atari TOS"),
+        6 => Some("HPFS"),
+        7 => Some("Macintosh"),
+        8 => Some("Z-System"),
+        9 => Some("CP/M"),
+        10 => Some("TOPS-20"),
+        11 => Some("NTFS"),
+        12 => Some("QDOS"),
+        13 => Some("Acorn RISCOS"),
+        255 => Some("unknown"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! header_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = &$data;
+                let actual = header(data);
+                let expected: Result<Header, Error> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    // `gzip -n` output.
+    header_test!(
+        minimal,
+        Ok(Header {
+            text: false,
+            mtime: 0,
+            extra_flags: 0,
+            os: 3,
+            extra: None,
+            name: None,
+            comment: None,
+            crc16: None,
+            len: 10,
+        }),
+        [0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 3, 0x03, 0x00]
+    );
+    header_test!(
+        all_fields,
+        Ok(Header {
+            text: true,
+            mtime: 0x65000000,
+            extra_flags: 2,
+            os: 255,
+            extra: Some(vec![0xAA, 0xBB]),
+            name: Some(b"a.txt".to_vec()),
+            comment: Some(b"c".to_vec()),
+            crc16: Some(0x1234),
+            len: 24,
+        }),
+        [
+            0x1F, 0x8B, 8, 0x1F, 0, 0, 0, 0x65, 2, 255, 2, 0, 0xAA, 0xBB, b'a', b'.', b't', b'x',
+            b't', 0, b'c', 0, 0x34, 0x12
+        ]
+    );
+    header_test!(magic, Err(Error::Magic), [0x1F, 0x9D, 8]);
+    header_test!(
+        method,
+        Err(Error::Method(0)),
+        [0x1F, 0x8B, 0, 0, 0, 0, 0, 0, 0, 3]
+    );
+    header_test!(
+        reserved_flags,
+        Err(Error::ReservedFlags(0x20)),
+        [0x1F, 0x8B, 8, 0x20, 0, 0, 0, 0, 0, 3]
+    );
+    header_test!(
+        unterminated_name,
+        Err(Error::Truncated),
+        [0x1F, 0x8B, 8, 0x08, 0, 0, 0, 0, 0, 3, b'a']
+    );
+}
@@ -1,8 +1,14 @@
 pub mod md5;
 pub mod sha256;
 
+use std::error;
 use std::fmt;
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
+use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::libs::input;
+use crate::libs::oid;
 
 const CHUNK_BYTE_SIZE: usize = 64;
 const PADDING: [u8; CHUNK_BYTE_SIZE] = [
@@ -12,12 +18,17 @@ const PADDING: [u8; CHUNK_BYTE_SIZE] = [
     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
 ];
 
+const CHUNK_BITS_SIZE: u64 = 8 * CHUNK_BYTE_SIZE as u64;
 const DATA_BITS_LENGTH_BYTE_SIZE: usize = 8;
 const END_OF_DATA_BYTE_SIZE: usize = 1;
 
 pub trait Context {
     type Digest;
 
+    /// maximum message length in bits.
+    /// `None` means the length is taken modulo 2^64 (as in md5).
+    const MAX_DATA_BITS_LEN: Option<u64> = None;
+
     fn compress(&mut self, chunk: &[u8; CHUNK_BYTE_SIZE]);
     fn get_digest(self) -> Self::Digest;
 }
@@ -28,12 +39,44 @@ pub enum Endian {
     Little,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Func {
     MD5,
     SHA256,
 }
 
+impl Func {
+    /// all supported hash functions.
+    pub const ALL: [Func; 2] = [Func::MD5, Func::SHA256];
+
+    /// whether the function is approved by FIPS 180-4.
+    pub fn approved(self) -> bool {
+        match self {
+            Func::MD5 => false,
+            Func::SHA256 => true,
+        }
+    }
+
+    /// name of the function in the oid registry.
+    fn oid_name(self) -> &'static str {
+        match self {
+            Func::MD5 => "md5",
+            Func::SHA256 => "sha256",
+        }
+    }
+
+    /// DER content octets of the OBJECT IDENTIFIER of the function.
+    pub fn oid(self) -> Vec<u8> {
+        oid::encode(self.oid_name()).expect("hash OIDs are registered")
+    }
+
+    /// function with the OBJECT IDENTIFIER of DER content octets `der`.
+    pub fn from_oid(der: &[u8]) -> Option<Func> {
+        let name = oid::name(&oid::decode(der).ok()?)?;
+        Func::ALL.into_iter().find(|f| f.oid_name() == name)
+    }
+}
+
 impl fmt::Display for Func {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self {
@@ -43,7 +86,128 @@ impl fmt::Display for Func {
     }
 }
 
-#[derive(PartialEq)]
+impl str::FromStr for Func {
+    type Err = String;
+
+    /// case-insensitive name of the function, like `md5` or `SHA256`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Func::ALL
+            .into_iter()
+            .find(|f| f.to_string().eq_ignore_ascii_case(s))
+            .ok_or(format!("unsupported hash function {:?}", s))
+    }
+}
+
+/// hash functions applied one after another, each to the digest of the
+/// one before, written like `sha256|md5`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain(pub Vec<Func>);
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, func) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            write!(f, "{}", func)?;
+        }
+        Ok(())
+    }
+}
+
+impl str::FromStr for Chain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err("empty chain of hash functions".into());
+        }
+        s.split('|')
+            .map(|name| name.trim().parse())
+            .collect::<Result<_, _>>()
+            .map(Chain)
+    }
+}
+
+/// implementation variants of hash functions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Impl {
+    /// plain Rust, on every cpu.
+    Portable,
+    /// vector instructions.
+    Simd,
+    /// dedicated hash instructions of the cpu.
+    Hw,
+}
+
+impl Impl {
+    pub const ALL: [Impl; 3] = [Impl::Portable, Impl::Simd, Impl::Hw];
+}
+
+impl fmt::Display for Impl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            Impl::Portable => write!(f, "portable"),
+            Impl::Simd => write!(f, "simd"),
+            Impl::Hw => write!(f, "hw"),
+        }
+    }
+}
+
+impl str::FromStr for Impl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Impl::ALL
+            .into_iter()
+            .find(|i| i.to_string().eq_ignore_ascii_case(s))
+            .ok_or(format!("unknown implementation {:?}", s))
+    }
+}
+
+/// implementation of `f` used by new contexts.
+/// by default the fastest one supported by the cpu is selected on first use.
+pub fn implementation(f: Func) -> Impl {
+    match f {
+        Func::MD5 => Impl::Portable,
+        Func::SHA256 => sha256::implementation(),
+    }
+}
+
+static FIPS: AtomicBool = AtomicBool::new(false);
+
+/// functions which are not approved are refused from now on.
+pub fn set_fips() {
+    FIPS.store(true, Ordering::Relaxed);
+}
+
+pub fn fips() -> bool {
+    FIPS.load(Ordering::Relaxed)
+}
+
+/// fails for `f` when it is not approved and FIPS mode is on.
+pub fn allowed(f: Func) -> io::Result<()> {
+    match fips() && !f.approved() {
+        true => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} is not approved in FIPS mode", f),
+        )),
+        false => Ok(()),
+    }
+}
+
+/// forces implementation `imp` for the functions which have it,
+/// the others use their portable one.
+/// fails when the cpu does not support `imp` for some function.
+pub fn force_implementation(imp: Impl) -> Result<(), String> {
+    let sha256_imp = match sha256::IMPLEMENTATIONS.contains(&imp) {
+        true => imp,
+        false => Impl::Portable,
+    };
+    sha256::set_implementation(sha256_imp)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Digest {
     MD5(md5::Digest),
     SHA256(sha256::Digest),
@@ -58,22 +222,179 @@ impl fmt::Display for Digest {
     }
 }
 
+impl Digest {
+    /// hash function which computed the digest.
+    pub fn func(&self) -> Func {
+        match &self {
+            Digest::MD5(_) => Func::MD5,
+            Digest::SHA256(_) => Func::SHA256,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self {
+            Digest::MD5(digest) => digest.as_bytes(),
+            Digest::SHA256(digest) => digest.as_bytes(),
+        }
+    }
+}
+
 pub struct Writer<Ctx: Context> {
     buf: [u8; CHUNK_BYTE_SIZE],
     buf_seed: usize,
-    data_bytes_len: usize,
+    data_bits_len: u64,
     endian: Endian,
     hasher: Ctx,
 }
 
+/// streaming hasher of any supported function,
+/// for computing several digests of the same data in one pass.
+pub enum Hasher {
+    MD5(Writer<md5::Context>),
+    SHA256(Writer<sha256::Context>),
+}
+
+impl Hasher {
+    pub fn new(f: Func) -> Hasher {
+        match f {
+            Func::MD5 => Hasher::MD5(Writer::new(md5::Context::new(), Endian::Little)),
+            Func::SHA256 => Hasher::SHA256(Writer::new(sha256::Context::new(), Endian::Big)),
+        }
+    }
+
+    pub fn compute(self) -> Digest {
+        match self {
+            Hasher::MD5(w) => Digest::MD5(w.compute()),
+            Hasher::SHA256(w) => Digest::SHA256(w.compute()),
+        }
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Hasher::MD5(w) => w.write(buf),
+            Hasher::SHA256(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// read error after `bytes` bytes of the input were hashed.
+#[derive(Debug)]
+pub struct PartialError {
+    pub bytes: u64,
+    /// digest of the `bytes` bytes read before the error.
+    pub digest: Digest,
+    pub error: io::Error,
+}
+
+impl fmt::Display for PartialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} after {} bytes; {} of them: {}",
+            input::error_class(&self.error),
+            self.error,
+            self.bytes,
+            self.digest.func(),
+            self.digest
+        )
+    }
+}
+
+impl error::Error for PartialError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// digest of `r` like `digest`, but on a read error the digest of the
+/// data read before it is kept, for salvaging data from failing media.
+pub fn digest_partial<R: io::Read>(mut r: R, f: Func) -> Result<Digest, PartialError> {
+    let mut hasher = Hasher::new(f);
+    let mut buf = [0u8; 64 * 1024];
+    let mut bytes = 0;
+    loop {
+        let res = match r.read(&mut buf) {
+            Ok(0) => return Ok(hasher.compute()),
+            Ok(n) => hasher.write_all(&buf[..n]).map(|_| n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => Err(err),
+        };
+        match res {
+            Ok(n) => bytes += n as u64,
+            Err(error) => {
+                return Err(PartialError {
+                    bytes,
+                    digest: hasher.compute(),
+                    error,
+                })
+            }
+        }
+    }
+}
+
 pub fn digest<R: io::Read>(r: R, f: Func) -> io::Result<Digest> {
+    allowed(f)?;
     match f {
         Func::MD5 => Ok(Digest::MD5(md5(r)?)),
         Func::SHA256 => Ok(Digest::SHA256(sha256(r)?)),
     }
 }
 
+/// digest of the first `bits_len` bits of `r`.
+/// `r` must contain exactly as many bytes as needed to hold `bits_len` bits.
+pub fn digest_bits<R: io::Read>(r: R, f: Func, bits_len: u64) -> io::Result<Digest> {
+    allowed(f)?;
+    match f {
+        Func::MD5 => {
+            let hasher = Writer::new(md5::Context::new(), Endian::Little);
+            Ok(Digest::MD5(compute_bits(r, hasher, bits_len)?))
+        }
+        Func::SHA256 => {
+            let hasher = Writer::new(sha256::Context::new(), Endian::Big);
+            Ok(Digest::SHA256(compute_bits(r, hasher, bits_len)?))
+        }
+    }
+}
+
+fn compute_bits<R: io::Read, Ctx: Context>(
+    mut r: R,
+    mut hasher: Writer<Ctx>,
+    bits_len: u64,
+) -> io::Result<Ctx::Digest> {
+    let bytes_len = bits_len / 8;
+    let partial_bits_len = (bits_len % 8) as u8;
+
+    if io::copy(&mut r.by_ref().take(bytes_len), &mut hasher)? != bytes_len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "input is shorter than bits length",
+        ));
+    }
+
+    // read one byte more than needed to detect a longer input
+    let mut rest = Vec::with_capacity(2);
+    r.take(2).read_to_end(&mut rest)?;
+    if rest.len() != (partial_bits_len != 0) as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "input length does not match bits length",
+        ));
+    }
+
+    match rest.first() {
+        Some(&last) => hasher.compute_bits(last, partial_bits_len),
+        None => Ok(hasher.compute()),
+    }
+}
+
 pub fn md5<R: io::Read>(mut r: R) -> io::Result<md5::Digest> {
+    allowed(Func::MD5)?;
     let ctx = md5::Context::new();
     let mut hasher = Writer::new(ctx, Endian::Little);
     io::copy(&mut r, &mut hasher)?;
@@ -81,6 +402,17 @@ pub fn md5<R: io::Read>(mut r: R) -> io::Result<md5::Digest> {
     Ok(hasher.compute())
 }
 
+/// md5 digest of `r` and whether `r` contains a block completing
+/// a known md5 collision attack.
+pub fn md5_detect_collisions<R: io::Read>(mut r: R) -> io::Result<(md5::Digest, bool)> {
+    allowed(Func::MD5)?;
+    let ctx = md5::DetectContext::new();
+    let mut hasher = Writer::new(ctx, Endian::Little);
+    io::copy(&mut r, &mut hasher)?;
+
+    Ok(hasher.compute())
+}
+
 pub fn sha256<R: io::Read>(mut r: R) -> io::Result<sha256::Digest> {
     let ctx = sha256::Context::new();
     let mut hasher = Writer::new(ctx, Endian::Big);
@@ -91,6 +423,7 @@ pub fn sha256<R: io::Read>(mut r: R) -> io::Result<sha256::Digest> {
 
 impl<Ctx: Context> Write for Writer<Ctx> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data_bits_len = self.next_data_bits_len(8 * buf.len() as u64)?;
         self.consume(buf);
 
         Ok(buf.len())
@@ -106,14 +439,59 @@ impl<Ctx: Context> Writer<Ctx> {
         Writer {
             buf: [0; CHUNK_BYTE_SIZE],
             buf_seed: 0,
-            data_bytes_len: 0,
+            data_bits_len: 0,
             hasher,
             endian,
         }
     }
 
-    pub fn compute(mut self) -> Ctx::Digest {
-        let data_bits_len = (self.data_bytes_len as u64).wrapping_mul(8);
+    /// continue hashing with the context which already compressed
+    /// `data_bits_len` bits of the message. So `data_bits_len` must be
+    /// a multiple of the chunk size.
+    pub fn resume(hasher: Ctx, endian: Endian, data_bits_len: u64) -> io::Result<Writer<Ctx>> {
+        if !data_bits_len.is_multiple_of(CHUNK_BITS_SIZE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "resumed data length is not a multiple of chunk size",
+            ));
+        }
+        if Ctx::MAX_DATA_BITS_LEN.is_some_and(|max| data_bits_len > max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "resumed data length exceeds the message length limit",
+            ));
+        }
+
+        let mut writer = Writer::new(hasher, endian);
+        writer.data_bits_len = data_bits_len;
+        Ok(writer)
+    }
+
+    pub fn compute(self) -> Ctx::Digest {
+        let data_bits_len = self.data_bits_len;
+        self.finalize(PADDING[0], data_bits_len)
+    }
+
+    /// compute digest of the message which ends with
+    /// `bits_len` (less than 8) high-order bits of `last` byte.
+    pub fn compute_bits(self, last: u8, bits_len: u8) -> io::Result<Ctx::Digest> {
+        if bits_len >= 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "partial byte must have less than 8 bits",
+            ));
+        }
+        let data_bits_len = self.next_data_bits_len(bits_len as u64)?;
+        // data bits are followed by the single `1` bit of padding
+        let mask = !(0xffu8 >> bits_len);
+        let end_of_data = (last & mask) | (PADDING[0] >> bits_len);
+
+        Ok(self.finalize(end_of_data, data_bits_len))
+    }
+
+    /// pad the rest of data with `end_of_data` byte (which starts the padding),
+    /// zeros and message length, then compress the final chunk(s).
+    fn finalize(mut self, end_of_data: u8, data_bits_len: u64) -> Ctx::Digest {
         // check self.buf_seed
         // if buf_seed > 64 - 9 => two final chunks
         // else => one final chunk
@@ -121,6 +499,7 @@ impl<Ctx: Context> Writer<Ctx> {
             let pading_bytes_len = CHUNK_BYTE_SIZE - DATA_BITS_LENGTH_BYTE_SIZE - self.buf_seed;
             self.buf[self.buf_seed..self.buf_seed + pading_bytes_len]
                 .clone_from_slice(&PADDING[..pading_bytes_len]);
+            self.buf[self.buf_seed] = end_of_data;
             self.fill_data_len(data_bits_len);
             self.hasher.compress(&self.buf);
         } else {
@@ -128,13 +507,16 @@ impl<Ctx: Context> Writer<Ctx> {
             let pading_bytes_len = CHUNK_BYTE_SIZE - self.buf_seed;
             self.buf[self.buf_seed..self.buf_seed + pading_bytes_len]
                 .clone_from_slice(&PADDING[..pading_bytes_len]);
+            if pading_bytes_len != 0 {
+                self.buf[self.buf_seed] = end_of_data;
+            }
             self.hasher.compress(&self.buf);
 
             // chunk 2
             self.buf[..CHUNK_BYTE_SIZE - DATA_BITS_LENGTH_BYTE_SIZE]
                 .clone_from_slice(&PADDING[DATA_BITS_LENGTH_BYTE_SIZE..]);
             if pading_bytes_len == 0 {
-                self.buf[0] = PADDING[0];
+                self.buf[0] = end_of_data;
             }
             self.fill_data_len(data_bits_len);
             self.hasher.compress(&self.buf);
@@ -168,9 +550,25 @@ impl<Ctx: Context> Writer<Ctx> {
         }
     }
 
-    fn consume(&mut self, mut buf: &[u8]) {
-        self.data_bytes_len = self.data_bytes_len.wrapping_add(buf.len());
+    /// message length in bits after `bits_len` more bits.
+    /// fails if the length exceeds the limit of the hash function.
+    fn next_data_bits_len(&self, bits_len: u64) -> io::Result<u64> {
+        match Ctx::MAX_DATA_BITS_LEN {
+            None => Ok(self.data_bits_len.wrapping_add(bits_len)),
+            Some(max) => self
+                .data_bits_len
+                .checked_add(bits_len)
+                .filter(|&data_bits_len| data_bits_len <= max)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "message exceeds the length limit of the hash function",
+                    )
+                }),
+        }
+    }
 
+    fn consume(&mut self, mut buf: &[u8]) {
         while self.buf_seed + buf.len() > CHUNK_BYTE_SIZE {
             self.buf[self.buf_seed..CHUNK_BYTE_SIZE]
                 .clone_from_slice(&buf[..CHUNK_BYTE_SIZE - self.buf_seed]);
@@ -182,3 +580,240 @@ impl<Ctx: Context> Writer<Ctx> {
         self.buf_seed += buf.len();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::sha2::Digest as _;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    #[test]
+    fn oids() {
+        // AlgorithmIdentifier OIDs of RFC 3279 and RFC 5754.
+        for (f, dotted) in [
+            (Func::MD5, "1.2.840.113549.2.5"),
+            (Func::SHA256, "2.16.840.1.101.3.4.2.1"),
+        ] {
+            assert_eq!(oid::decode(&f.oid()), Ok(dotted.to_string()));
+            assert_eq!(Func::from_oid(&f.oid()), Some(f));
+        }
+        let sha1 = oid::encode("sha1").unwrap();
+        assert_eq!(Func::from_oid(&sha1), None);
+        assert_eq!(Func::from_oid(&[]), None);
+    }
+
+    macro_rules! reference {
+        ($name:ident,$ctx:expr,$endian:expr,$reference:ty) => {
+            proptest! {
+                #[test]
+                fn $name(
+                    data in vec(any::<u8>(), 0..4 * CHUNK_BYTE_SIZE),
+                    chunk in 1..2 * CHUNK_BYTE_SIZE,
+                ) {
+                    let mut hasher = Writer::new($ctx, $endian);
+                    for part in data.chunks(chunk) {
+                        hasher.write_all(part).unwrap();
+                    }
+
+                    let actual = hasher.compute().to_string();
+                    let expected = format!("{:x}", <$reference>::digest(&data));
+
+                    prop_assert_eq!(expected, actual);
+                }
+            }
+        };
+    }
+
+    /// context which records compressed chunks instead of hashing them.
+    struct Chunks(Vec<[u8; CHUNK_BYTE_SIZE]>);
+
+    impl Context for Chunks {
+        type Digest = Vec<[u8; CHUNK_BYTE_SIZE]>;
+
+        fn compress(&mut self, chunk: &[u8; CHUNK_BYTE_SIZE]) {
+            self.0.push(*chunk);
+        }
+
+        fn get_digest(self) -> Self::Digest {
+            self.0
+        }
+    }
+
+    macro_rules! data_len {
+        ($name:ident,$endian:expr,$resume_bits_len:expr,$data_len:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut hasher =
+                    Writer::resume(Chunks(Vec::new()), $endian, $resume_bits_len).unwrap();
+
+                hasher.write_all(&[0x41; $data_len]).unwrap();
+
+                let chunks = hasher.compute();
+                let last = chunks.last().unwrap();
+                let actual = &last[CHUNK_BYTE_SIZE - DATA_BITS_LENGTH_BYTE_SIZE..];
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", $expected);
+
+                assert_eq!($expected, actual);
+            }
+        };
+    }
+
+    data_len!(
+        len_zero,
+        Endian::Big,
+        0,
+        0,
+        [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    data_len!(
+        // 2^35 bytes, more than u32 can hold
+        len_32gb_big,
+        Endian::Big,
+        1 << 38,
+        1,
+        [0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x08]
+    );
+    data_len!(
+        len_32gb_little,
+        Endian::Little,
+        1 << 38,
+        1,
+        [0x08, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00]
+    );
+    data_len!(
+        // last representable length
+        len_max,
+        Endian::Big,
+        u64::MAX - (CHUNK_BITS_SIZE - 1),
+        63,
+        [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf8]
+    );
+    data_len!(
+        // length is taken modulo 2^64
+        len_wrap,
+        Endian::Little,
+        u64::MAX - (CHUNK_BITS_SIZE - 1),
+        65,
+        [0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+
+    #[test]
+    fn resume_not_whole_chunk() {
+        assert!(Writer::resume(Chunks(Vec::new()), Endian::Big, 8).is_err());
+    }
+
+    #[test]
+    fn sha256_len_limit() {
+        let resume_bits_len = u64::MAX - (CHUNK_BITS_SIZE - 1);
+        let mut hasher =
+            Writer::resume(sha256::Context::new(), Endian::Big, resume_bits_len).unwrap();
+
+        hasher.write_all(&[0x41; 63]).unwrap();
+        assert!(hasher.write_all(&[0x41]).is_err());
+    }
+
+    macro_rules! bits {
+        ($name:ident,$f:expr,$expected:expr,$data:expr,$bits_len:expr) => {
+            #[test]
+            fn $name() {
+                let actual = digest_bits(&$data[..], $f, $bits_len).unwrap().to_string();
+
+                println!("  actual: {}", actual);
+                println!("expected: {}", $expected);
+
+                assert_eq!($expected, actual);
+            }
+        };
+    }
+
+    bits!(
+        // RFC 6234 extra bits test
+        sha256_bits_5,
+        Func::SHA256,
+        "d6d3e02a31a84a8caa9718ed6c2057be09db45e7823eb5079ce7a573a3760f95",
+        [0x68],
+        5
+    );
+    bits!(
+        md5_bits_5,
+        Func::MD5,
+        "7aecc0f7268cc128fe17c3f439922034",
+        [0x68],
+        5
+    );
+    bits!(
+        sha256_bits_whole_bytes,
+        Func::SHA256,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        [b'a', b'b', b'c'],
+        24
+    );
+
+    #[test]
+    fn bits_input_len_mismatch() {
+        assert!(digest_bits(&[0x68, 0x00][..], Func::SHA256, 5).is_err());
+        assert!(digest_bits(&[][..], Func::SHA256, 5).is_err());
+        assert!(digest_bits(&[0x68][..], Func::SHA256, 16).is_err());
+    }
+
+    #[test]
+    fn bits_padding() {
+        let hasher = Writer::new(Chunks(Vec::new()), Endian::Big);
+
+        let chunks = hasher.compute_bits(0b1010_1111, 3).unwrap();
+        let last = chunks.last().unwrap();
+
+        assert_eq!(0b1011_0000, last[0]);
+        assert_eq!(3, last[CHUNK_BYTE_SIZE - 1]);
+    }
+
+    /// reader which fails after its data.
+    struct Failing<'a>(&'a [u8]);
+
+    impl io::Read for Failing<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("bad sector"));
+            }
+            let n = self.0.read(buf)?;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn partial_digest() {
+        let err = digest_partial(Failing(b"hello"), Func::SHA256).unwrap_err();
+        assert_eq!(5, err.bytes);
+        assert_eq!(digest(&b"hello"[..], Func::SHA256).unwrap(), err.digest);
+        assert_eq!(
+            digest(&b"hello"[..], Func::MD5).unwrap(),
+            digest_partial(&b"hello"[..], Func::MD5).unwrap()
+        );
+    }
+
+    #[test]
+    fn chain_parse() {
+        let chain: Chain = "sha256 | MD5".parse().unwrap();
+        assert_eq!(Chain(vec![Func::SHA256, Func::MD5]), chain);
+        assert_eq!("SHA256|MD5", chain.to_string());
+        assert!("".parse::<Chain>().is_err());
+        assert!("sha256|".parse::<Chain>().is_err());
+        assert!("sha256|ripemd160".parse::<Chain>().is_err());
+    }
+
+    reference!(
+        md5_reference,
+        md5::Context::new(),
+        Endian::Little,
+        ::md5::Md5
+    );
+    reference!(
+        sha256_reference,
+        sha256::Context::new(),
+        Endian::Big,
+        ::sha2::Sha256
+    );
+}
@@ -29,16 +29,13 @@ const CHUNK_BYTE_SIZE: usize = 64;
 pub const DIGEST_BYTE_SIZE: usize = 16;
 pub const DIGEST_STR_LEN: usize = 32;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Digest([u8; DIGEST_BYTE_SIZE]);
 
 impl fmt::Display for Digest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for byte in self.0.iter() {
-            let res = write!(f, "{:0>2x}", byte);
-            if res.is_err() {
-                return res;
-            }
+            write!(f, "{:0>2x}", byte)?;
         }
         Ok(())
     }
@@ -49,6 +46,10 @@ impl Digest {
         Digest(digest)
     }
 
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
     fn from_state(a_s: u32, b_s: u32, c_s: u32, d_s: u32) -> Digest {
         let mut digest = [0u8; DIGEST_BYTE_SIZE];
         digest[0..4].clone_from_slice(&as_u8_le(a_s));
@@ -58,7 +59,6 @@ impl Digest {
 
         Digest(digest)
     }
-
 }
 
 pub struct Context {
@@ -68,6 +68,12 @@ pub struct Context {
     d_s: u32,
 }
 
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Context {
     pub fn new() -> Context {
         Context {
@@ -84,43 +90,130 @@ impl hash::Context for Context {
     fn compress(&mut self, chunk: &[u8; CHUNK_BYTE_SIZE]) {
         let words = split_words(chunk);
 
-        let (mut a_temp, mut b_temp, mut c_temp, mut d_temp) =
-            (self.a_s, self.b_s, self.c_s, self.d_s);
-
-        let mut f_temp: u32 = 0;
-        let mut g_temp: usize = 0;
-        for i in 0usize..64 {
-            if i < 16 {
-                f_temp = (b_temp & c_temp) | ((!b_temp) & d_temp);
-                g_temp = i;
-            } else if i < 32 {
-                f_temp = (d_temp & b_temp) | ((!d_temp) & c_temp);
-                g_temp = (5 * i + 1) % 16;
-            } else if i < 48 {
-                f_temp = b_temp ^ c_temp ^ d_temp;
-                g_temp = (3 * i + 5) % 16;
-            } else if i < 64 {
-                f_temp = c_temp ^ (b_temp | (!d_temp));
-                g_temp = (7 * i) % 16;
+        [self.a_s, self.b_s, self.c_s, self.d_s] =
+            compress([self.a_s, self.b_s, self.c_s, self.d_s], &words);
+    }
+    fn get_digest(self) -> Digest {
+        Digest::from_state(self.a_s, self.b_s, self.c_s, self.d_s)
+    }
+}
+
+/// md5 context which also checks every chunk for being the last block
+/// of an identical-prefix collision built with the differential of Wang et al.
+/// (used by the original md5 collisions and `fastcoll`).
+///
+/// such a block completes a collision if the same chunk with
+/// `COLLISION_WORDS_DIFF` applied, compressed from the state with
+/// `COLLISION_STATE_DIFF` applied, gives the same state.
+pub struct DetectContext {
+    ctx: Context,
+    collision: bool,
+}
+
+/// state difference after the first block of a collision.
+const COLLISION_STATE_DIFF: [u32; 4] = [
+    1 << 31,
+    (1 << 31) + (1 << 25),
+    (1 << 31) + (1 << 25),
+    (1 << 31) + (1 << 25),
+];
+/// (word index, difference) of the second block of a collision.
+const COLLISION_WORDS_DIFF: [(usize, u32); 3] = [
+    (4, 1 << 31),
+    (11, (1u32 << 15).wrapping_neg()),
+    (14, 1 << 31),
+];
+
+impl Default for DetectContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DetectContext {
+    pub fn new() -> DetectContext {
+        DetectContext {
+            ctx: Context::new(),
+            collision: false,
+        }
+    }
+
+    fn is_collision_block(state: [u32; 4], words: &[u32; 16], next_state: [u32; 4]) -> bool {
+        // the chunk may be taken from any of the two colliding messages.
+        for sign in [1u32, 1u32.wrapping_neg()] {
+            let mut sister_state = state;
+            for (x, diff) in sister_state.iter_mut().zip(COLLISION_STATE_DIFF) {
+                *x = x.wrapping_add(diff.wrapping_mul(sign));
+            }
+            let mut sister_words = *words;
+            for (i, diff) in COLLISION_WORDS_DIFF {
+                sister_words[i] = sister_words[i].wrapping_add(diff.wrapping_mul(sign));
             }
 
-            f_temp = f_temp.wrapping_add(a_temp.wrapping_add(K[i]).wrapping_add(words[g_temp]));
-            a_temp = d_temp;
-            d_temp = c_temp;
-            c_temp = b_temp;
-            b_temp = b_temp.wrapping_add(left_rotate(f_temp, S[i]));
+            if compress(sister_state, &sister_words) == next_state {
+                return true;
+            }
         }
+        false
+    }
+}
 
-        self.a_s = self.a_s.wrapping_add(a_temp);
-        self.b_s = self.b_s.wrapping_add(b_temp);
-        self.c_s = self.c_s.wrapping_add(c_temp);
-        self.d_s = self.d_s.wrapping_add(d_temp);
+impl hash::Context for DetectContext {
+    /// digest and whether a collision block was found.
+    type Digest = (Digest, bool);
+
+    fn compress(&mut self, chunk: &[u8; CHUNK_BYTE_SIZE]) {
+        let words = split_words(chunk);
+        let state = [self.ctx.a_s, self.ctx.b_s, self.ctx.c_s, self.ctx.d_s];
+
+        let next_state = compress(state, &words);
+        if Self::is_collision_block(state, &words, next_state) {
+            self.collision = true;
+        }
+
+        [self.ctx.a_s, self.ctx.b_s, self.ctx.c_s, self.ctx.d_s] = next_state;
     }
-    fn get_digest(self) -> Digest {
-        Digest::from_state(self.a_s, self.b_s, self.c_s, self.d_s)
+
+    fn get_digest(self) -> (Digest, bool) {
+        (hash::Context::get_digest(self.ctx), self.collision)
     }
 }
 
+fn compress(state: [u32; 4], words: &[u32; 16]) -> [u32; 4] {
+    let [mut a_temp, mut b_temp, mut c_temp, mut d_temp] = state;
+
+    let mut f_temp: u32 = 0;
+    let mut g_temp: usize = 0;
+    for i in 0usize..64 {
+        if i < 16 {
+            f_temp = (b_temp & c_temp) | ((!b_temp) & d_temp);
+            g_temp = i;
+        } else if i < 32 {
+            f_temp = (d_temp & b_temp) | ((!d_temp) & c_temp);
+            g_temp = (5 * i + 1) % 16;
+        } else if i < 48 {
+            f_temp = b_temp ^ c_temp ^ d_temp;
+            g_temp = (3 * i + 5) % 16;
+        } else if i < 64 {
+            f_temp = c_temp ^ (b_temp | (!d_temp));
+            g_temp = (7 * i) % 16;
+        }
+
+        f_temp = f_temp.wrapping_add(a_temp.wrapping_add(K[i]).wrapping_add(words[g_temp]));
+        a_temp = d_temp;
+        d_temp = c_temp;
+        c_temp = b_temp;
+        b_temp = b_temp.wrapping_add(left_rotate(f_temp, S[i]));
+    }
+
+    [
+        state[0].wrapping_add(a_temp),
+        state[1].wrapping_add(b_temp),
+        state[2].wrapping_add(c_temp),
+        state[3].wrapping_add(d_temp),
+    ]
+}
+
 fn split_words(chunk: &[u8; 64]) -> [u32; 16] {
     let mut words: [u32; 16] = [0; 16];
 
@@ -143,7 +236,7 @@ mod tests {
                 let ctx = Context::new();
                 let mut hasher = hash::Writer::new(ctx, hash::Endian::Little);
 
-                hasher.write(&$data).unwrap();
+                hasher.write_all(&$data).unwrap();
 
                 let actual = hasher.compute().0;
 
@@ -161,7 +254,7 @@ mod tests {
                 let mut hasher = hash::Writer::new(ctx, hash::Endian::Little);
 
                 for chunk in $data.chunks($chunks) {
-                    hasher.write(&chunk).unwrap();
+                    hasher.write_all(&chunk).unwrap();
                 }
 
                 let actual = hasher.compute().0;
@@ -269,6 +362,68 @@ mod tests {
             0x16, 0x2b, 0x6d, 0x6e, 0xb1, 0x7c, 0xd9, 0xda, 0x55, 0xf9, 0x5f, 0x8c, 0x73, 0xa3,
             0x2d, 0xda
         ],
-        ['A' as u8; 65]
+        [b'A'; 65]
     );
+
+    // collision of Wang et al.
+    const COLLISION_1: [u8; 128] = [
+        0xd1, 0x31, 0xdd, 0x02, 0xc5, 0xe6, 0xee, 0xc4, 0x69, 0x3d, 0x9a, 0x06, 0x98, 0xaf, 0xf9,
+        0x5c, 0x2f, 0xca, 0xb5, 0x87, 0x12, 0x46, 0x7e, 0xab, 0x40, 0x04, 0x58, 0x3e, 0xb8, 0xfb,
+        0x7f, 0x89, 0x55, 0xad, 0x34, 0x06, 0x09, 0xf4, 0xb3, 0x02, 0x83, 0xe4, 0x88, 0x83, 0x25,
+        0x71, 0x41, 0x5a, 0x08, 0x51, 0x25, 0xe8, 0xf7, 0xcd, 0xc9, 0x9f, 0xd9, 0x1d, 0xbd, 0xf2,
+        0x80, 0x37, 0x3c, 0x5b, 0xd8, 0x82, 0x3e, 0x31, 0x56, 0x34, 0x8f, 0x5b, 0xae, 0x6d, 0xac,
+        0xd4, 0x36, 0xc9, 0x19, 0xc6, 0xdd, 0x53, 0xe2, 0xb4, 0x87, 0xda, 0x03, 0xfd, 0x02, 0x39,
+        0x63, 0x06, 0xd2, 0x48, 0xcd, 0xa0, 0xe9, 0x9f, 0x33, 0x42, 0x0f, 0x57, 0x7e, 0xe8, 0xce,
+        0x54, 0xb6, 0x70, 0x80, 0xa8, 0x0d, 0x1e, 0xc6, 0x98, 0x21, 0xbc, 0xb6, 0xa8, 0x83, 0x93,
+        0x96, 0xf9, 0x65, 0x2b, 0x6f, 0xf7, 0x2a, 0x70,
+    ];
+    const COLLISION_2: [u8; 128] = [
+        0xd1, 0x31, 0xdd, 0x02, 0xc5, 0xe6, 0xee, 0xc4, 0x69, 0x3d, 0x9a, 0x06, 0x98, 0xaf, 0xf9,
+        0x5c, 0x2f, 0xca, 0xb5, 0x07, 0x12, 0x46, 0x7e, 0xab, 0x40, 0x04, 0x58, 0x3e, 0xb8, 0xfb,
+        0x7f, 0x89, 0x55, 0xad, 0x34, 0x06, 0x09, 0xf4, 0xb3, 0x02, 0x83, 0xe4, 0x88, 0x83, 0x25,
+        0xf1, 0x41, 0x5a, 0x08, 0x51, 0x25, 0xe8, 0xf7, 0xcd, 0xc9, 0x9f, 0xd9, 0x1d, 0xbd, 0x72,
+        0x80, 0x37, 0x3c, 0x5b, 0xd8, 0x82, 0x3e, 0x31, 0x56, 0x34, 0x8f, 0x5b, 0xae, 0x6d, 0xac,
+        0xd4, 0x36, 0xc9, 0x19, 0xc6, 0xdd, 0x53, 0xe2, 0x34, 0x87, 0xda, 0x03, 0xfd, 0x02, 0x39,
+        0x63, 0x06, 0xd2, 0x48, 0xcd, 0xa0, 0xe9, 0x9f, 0x33, 0x42, 0x0f, 0x57, 0x7e, 0xe8, 0xce,
+        0x54, 0xb6, 0x70, 0x80, 0x28, 0x0d, 0x1e, 0xc6, 0x98, 0x21, 0xbc, 0xb6, 0xa8, 0x83, 0x93,
+        0x96, 0xf9, 0x65, 0xab, 0x6f, 0xf7, 0x2a, 0x70,
+    ];
+
+    macro_rules! detect {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let ctx = DetectContext::new();
+                let mut hasher = hash::Writer::new(ctx, hash::Endian::Little);
+
+                hasher.write_all(&$data).unwrap();
+
+                let (_, actual) = hasher.compute();
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", $expected);
+
+                assert_eq!($expected, actual);
+            }
+        };
+    }
+
+    detect!(detect_nothing, false, []);
+    detect!(detect_a_1000, false, [0x41; 1000]);
+    detect!(detect_collision_1, true, COLLISION_1);
+    detect!(detect_collision_2, true, COLLISION_2);
+    detect!(detect_collision_prefix, false, COLLISION_1[..64]);
+
+    #[test]
+    fn detect_same_digest() {
+        let mut hasher = hash::Writer::new(DetectContext::new(), hash::Endian::Little);
+        hasher.write_all(&COLLISION_1).unwrap();
+        let (actual, _) = hasher.compute();
+
+        let mut hasher = hash::Writer::new(Context::new(), hash::Endian::Little);
+        hasher.write_all(&COLLISION_2).unwrap();
+        let expected = hasher.compute();
+
+        assert_eq!(expected, actual);
+    }
 }
@@ -1,7 +1,8 @@
 use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 use crate::libs::bitutils::{as_u32_be, as_u8_be, right_rotate};
-use crate::libs::hash;
+use crate::libs::hash::{self, Impl};
 
 const DIGEST_WORD_SIZE: usize = 8;
 const BYTES_IN_WORD: usize = 4;
@@ -20,16 +21,22 @@ const K: [u32; 64] = [
     0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
 ];
 
-#[derive(Debug, PartialEq)]
+/// implementations of the compression function, see `implementation`.
+pub const IMPLEMENTATIONS: [Impl; 2] = [Impl::Portable, Impl::Hw];
+
+/// `Impl` of new contexts as its index in `Impl::ALL`, or `UNSELECTED`.
+static SELECTED: AtomicU8 = AtomicU8::new(UNSELECTED);
+const UNSELECTED: u8 = u8::MAX;
+
+type Compress = fn(&mut [u32; DIGEST_WORD_SIZE], &[u8; CHUNK_BYTE_SIZE]);
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Digest([u8; DIGEST_BYTE_SIZE]);
 
 impl fmt::Display for Digest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for byte in self.0.iter() {
-            let res = write!(f, "{:0>2x}", byte);
-            if res.is_err() {
-                return res;
-            }
+            write!(f, "{:0>2x}", byte)?;
         }
         Ok(())
     }
@@ -39,69 +46,100 @@ impl Digest {
     pub fn new(digest: [u8; DIGEST_BYTE_SIZE]) -> Digest {
         Digest(digest)
     }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Digest> for [u8; DIGEST_BYTE_SIZE] {
+    fn from(digest: Digest) -> Self {
+        digest.0
+    }
 }
 
 pub struct Context {
     state: [u32; DIGEST_WORD_SIZE],
+    compress: Compress,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Context {
+    /// context with the selected implementation, see `implementation`.
     pub fn new() -> Context {
-        Context {
+        // the selected implementation is always supported.
+        Context::with_implementation(implementation()).unwrap()
+    }
+
+    /// context with implementation `imp`, or None when it is not supported
+    /// by this cpu.
+    pub fn with_implementation(imp: Impl) -> Option<Context> {
+        let compress: Compress = match imp {
+            Impl::Portable => compress_portable,
+            #[cfg(target_arch = "x86_64")]
+            Impl::Hw if x86::supported() => x86::compress,
+            _ => return None,
+        };
+        Some(Context {
             state: [
                 0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
                 0x5be0cd19,
             ],
+            compress,
+        })
+    }
+}
+
+/// whether this cpu supports implementation `imp`.
+pub fn supported(imp: Impl) -> bool {
+    match imp {
+        Impl::Portable => true,
+        #[cfg(target_arch = "x86_64")]
+        Impl::Hw => x86::supported(),
+        _ => false,
+    }
+}
+
+/// implementation used by new contexts: the one set by `set_implementation`,
+/// else hardware instructions when the cpu has them.
+pub fn implementation() -> Impl {
+    match SELECTED.load(Ordering::Relaxed) {
+        UNSELECTED => {
+            let imp = if supported(Impl::Hw) {
+                Impl::Hw
+            } else {
+                Impl::Portable
+            };
+            SELECTED.store(imp as u8, Ordering::Relaxed);
+            imp
         }
+        i => Impl::ALL[i as usize],
+    }
+}
+
+pub fn set_implementation(imp: Impl) -> Result<(), String> {
+    if !supported(imp) {
+        return Err(format!(
+            "{} implementation of SHA256 is not supported by this cpu",
+            imp
+        ));
     }
+    SELECTED.store(imp as u8, Ordering::Relaxed);
+    Ok(())
 }
 
 impl hash::Context for Context {
     type Digest = Digest;
 
+    const MAX_DATA_BITS_LEN: Option<u64> = Some(u64::MAX);
+
     fn compress(&mut self, chunk: &[u8; CHUNK_BYTE_SIZE]) {
-        let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h) = (
-            self.state[0],
-            self.state[1],
-            self.state[2],
-            self.state[3],
-            self.state[4],
-            self.state[5],
-            self.state[6],
-            self.state[7],
-        );
-        let words = get_words(chunk);
-
-        for i in 0..64 {
-            let s1 = right_rotate(e, 6) ^ right_rotate(e, 11) ^ right_rotate(e, 25);
-            let ch = (e & f) ^ ((!e) & g);
-            let temp1 = h.wrapping_add(
-                s1.wrapping_add(ch)
-                    .wrapping_add(K[i])
-                    .wrapping_add(words[i]),
-            );
-
-            let s0 = right_rotate(a, 2) ^ right_rotate(a, 13) ^ right_rotate(a, 22);
-            let maj = (a & b) ^ (a & c) ^ (b & c);
-            let temp2 = s0.wrapping_add(maj);
-
-            h = g;
-            g = f;
-            f = e;
-            e = d.wrapping_add(temp1);
-            d = c;
-            c = b;
-            b = a;
-            a = temp1.wrapping_add(temp2);
-        }
-        self.state[0] = a.wrapping_add(self.state[0]);
-        self.state[1] = b.wrapping_add(self.state[1]);
-        self.state[2] = c.wrapping_add(self.state[2]);
-        self.state[3] = d.wrapping_add(self.state[3]);
-        self.state[4] = e.wrapping_add(self.state[4]);
-        self.state[5] = f.wrapping_add(self.state[5]);
-        self.state[6] = g.wrapping_add(self.state[6]);
-        self.state[7] = h.wrapping_add(self.state[7]);
+        (self.compress)(&mut self.state, chunk)
     }
 
     fn get_digest(self) -> Digest {
@@ -113,6 +151,120 @@ impl hash::Context for Context {
     }
 }
 
+fn compress_portable(state: &mut [u32; DIGEST_WORD_SIZE], chunk: &[u8; CHUNK_BYTE_SIZE]) {
+    let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h) = (
+        state[0], state[1], state[2], state[3], state[4], state[5], state[6], state[7],
+    );
+    let words = get_words(chunk);
+
+    for i in 0..64 {
+        let s1 = right_rotate(e, 6) ^ right_rotate(e, 11) ^ right_rotate(e, 25);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = h.wrapping_add(
+            s1.wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(words[i]),
+        );
+
+        let s0 = right_rotate(a, 2) ^ right_rotate(a, 13) ^ right_rotate(a, 22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+    state[0] = a.wrapping_add(state[0]);
+    state[1] = b.wrapping_add(state[1]);
+    state[2] = c.wrapping_add(state[2]);
+    state[3] = d.wrapping_add(state[3]);
+    state[4] = e.wrapping_add(state[4]);
+    state[5] = f.wrapping_add(state[5]);
+    state[6] = g.wrapping_add(state[6]);
+    state[7] = h.wrapping_add(state[7]);
+}
+
+/// compression with the SHA extensions of x86-64 (Intel SHA-NI).
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{CHUNK_BYTE_SIZE, DIGEST_WORD_SIZE, K};
+    use std::arch::x86_64::*;
+
+    pub fn supported() -> bool {
+        is_x86_feature_detected!("sha")
+            && is_x86_feature_detected!("sse2")
+            && is_x86_feature_detected!("ssse3")
+            && is_x86_feature_detected!("sse4.1")
+    }
+
+    pub fn compress(state: &mut [u32; DIGEST_WORD_SIZE], chunk: &[u8; CHUNK_BYTE_SIZE]) {
+        // SAFETY: contexts use this function only when `supported`.
+        unsafe { compress_sha(state, chunk) }
+    }
+
+    /// four rounds with message words `w` (already scheduled).
+    #[target_feature(enable = "sha,sse2")]
+    unsafe fn rounds4(abef: &mut __m128i, cdgh: &mut __m128i, w: __m128i, i: usize) {
+        let k = _mm_loadu_si128(K[4 * i..].as_ptr() as *const __m128i);
+        let wk = _mm_add_epi32(w, k);
+        *cdgh = _mm_sha256rnds2_epu32(*cdgh, *abef, wk);
+        *abef = _mm_sha256rnds2_epu32(*abef, *cdgh, _mm_shuffle_epi32(wk, 0x0E));
+    }
+
+    /// next four message words from the previous sixteen.
+    #[target_feature(enable = "sha,sse2,ssse3")]
+    unsafe fn schedule(w0: __m128i, w1: __m128i, w2: __m128i, w3: __m128i) -> __m128i {
+        let t = _mm_add_epi32(_mm_sha256msg1_epu32(w0, w1), _mm_alignr_epi8(w3, w2, 4));
+        _mm_sha256msg2_epu32(t, w3)
+    }
+
+    #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+    unsafe fn compress_sha(state: &mut [u32; DIGEST_WORD_SIZE], chunk: &[u8; CHUNK_BYTE_SIZE]) {
+        // big endian words of the chunk.
+        let mask = _mm_set_epi64x(
+            0x0C0D_0E0F_0809_0A0Bu64 as i64,
+            0x0405_0607_0001_0203u64 as i64,
+        );
+        let state_ptr = state.as_ptr() as *const __m128i;
+        let dcba = _mm_loadu_si128(state_ptr);
+        let efgh = _mm_shuffle_epi32(_mm_loadu_si128(state_ptr.add(1)), 0x1B);
+        let cdab = _mm_shuffle_epi32(dcba, 0xB1);
+        // the instructions keep state as (a, b, e, f) and (c, d, g, h).
+        let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+        let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xF0);
+        let (abef_save, cdgh_save) = (abef, cdgh);
+
+        let chunk_ptr = chunk.as_ptr() as *const __m128i;
+        let mut w = [
+            _mm_shuffle_epi8(_mm_loadu_si128(chunk_ptr), mask),
+            _mm_shuffle_epi8(_mm_loadu_si128(chunk_ptr.add(1)), mask),
+            _mm_shuffle_epi8(_mm_loadu_si128(chunk_ptr.add(2)), mask),
+            _mm_shuffle_epi8(_mm_loadu_si128(chunk_ptr.add(3)), mask),
+        ];
+        for (i, &w) in w.iter().enumerate() {
+            rounds4(&mut abef, &mut cdgh, w, i);
+        }
+        for i in 4..16 {
+            let next = schedule(w[i % 4], w[(i + 1) % 4], w[(i + 2) % 4], w[(i + 3) % 4]);
+            w[i % 4] = next;
+            rounds4(&mut abef, &mut cdgh, next, i);
+        }
+
+        abef = _mm_add_epi32(abef, abef_save);
+        cdgh = _mm_add_epi32(cdgh, cdgh_save);
+        let feba = _mm_shuffle_epi32(abef, 0x1B);
+        let dchg = _mm_shuffle_epi32(cdgh, 0xB1);
+        let state_ptr = state.as_mut_ptr() as *mut __m128i;
+        _mm_storeu_si128(state_ptr, _mm_blend_epi16(feba, dchg, 0xF0));
+        _mm_storeu_si128(state_ptr.add(1), _mm_alignr_epi8(dchg, feba, 8));
+    }
+}
+
 fn get_words(chunk: &[u8; CHUNK_BYTE_SIZE]) -> [u32; 64] {
     let mut words: [u32; 64] = [0; 64];
     for (i, word) in chunk.chunks(BYTES_IN_WORD).enumerate() {
@@ -141,7 +293,7 @@ mod tests {
                 let ctx = Context::new();
                 let mut hasher = hash::Writer::new(ctx, hash::Endian::Big);
 
-                hasher.write(&$data).unwrap();
+                hasher.write_all(&$data).unwrap();
 
                 let actual = hasher.compute().0;
 
@@ -170,7 +322,7 @@ mod tests {
             0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
             0xf2, 0x00, 0x15, 0xad
         ],
-        ['a' as u8, 'b' as u8, 'c' as u8]
+        [b'a', b'b', b'c']
     );
     ctx_test!(
         a_51,
@@ -179,7 +331,7 @@ mod tests {
             0xce, 0x65, 0x4a, 0x3b, 0x65, 0xbc, 0xed, 0x71, 0x34, 0xc2, 0x2e, 0x17, 0x9a, 0x2e,
             0x25, 0x15, 0x50, 0x09
         ],
-        ['A' as u8; 51]
+        [b'A'; 51]
     );
     ctx_test!(
         a_64,
@@ -188,7 +340,7 @@ mod tests {
             0xa1, 0x09, 0xbf, 0x15, 0xc4, 0x78, 0x41, 0x0a, 0x3f, 0x5e, 0xb4, 0xd4, 0xc4, 0xe2,
             0x6c, 0xd0, 0x81, 0xf6
         ],
-        ['A' as u8; 64]
+        [b'A'; 64]
     );
     ctx_test!(
         a_55,
@@ -197,7 +349,7 @@ mod tests {
             0xa3, 0x05, 0x9b, 0x3d, 0x65, 0x54, 0x8a, 0x77, 0x54, 0x2a, 0x15, 0x59, 0xe3, 0xd2,
             0x02, 0xe6, 0xab, 0x00
         ],
-        ['A' as u8; 55]
+        [b'A'; 55]
     );
     ctx_test!(
         a_1000,
@@ -206,6 +358,32 @@ mod tests {
             0x39, 0x31, 0x8b, 0x63, 0x64, 0xf6, 0xdc, 0xd8, 0x35, 0xd0, 0xa5, 0x19, 0x10, 0x5a,
             0x1e, 0xad, 0xd6, 0xe4
         ],
-        ['A' as u8; 1000]
+        [b'A'; 1000]
     );
+
+    #[test]
+    fn implementations_agree() {
+        // xorshift64, deterministic.
+        let mut x: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        for imp in IMPLEMENTATIONS.into_iter().filter(|&imp| supported(imp)) {
+            let compress = Context::with_implementation(imp).unwrap().compress;
+            for _ in 0..1000 {
+                let mut state = [0u32; DIGEST_WORD_SIZE];
+                state.iter_mut().for_each(|w| *w = next() as u32);
+                let mut chunk = [0u8; CHUNK_BYTE_SIZE];
+                chunk.iter_mut().for_each(|b| *b = next() as u8);
+
+                let mut expected = state;
+                compress_portable(&mut expected, &chunk);
+                compress(&mut state, &chunk);
+                assert_eq!(expected, state, "{} implementation", imp);
+            }
+        }
+    }
 }
@@ -0,0 +1,213 @@
+//! hashdeep manifest format: a header naming the columns, then one
+//! `size,digest...,filename` line per file. lines starting with `#` are comments.
+
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::libs::hash::Func;
+
+const HEADER: &str = "%%%% HASHDEEP-1.0";
+const COLUMNS_PREFIX: &str = "%%%% ";
+const SIZE_COLUMN: &str = "size";
+const FILENAME_COLUMN: &str = "filename";
+const COMMENT_PREFIX: &str = "#";
+
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub size: u64,
+    /// lowercase hex digests in the order of the manifest functions.
+    pub digests: Vec<String>,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Manifest {
+    pub funcs: Vec<Func>,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(io::Error),
+    Header(String),
+    Line { number: usize, line: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read(err) => write!(f, "read: {}", err),
+            Error::Header(reason) => write!(f, "invalid hashdeep header: {}", reason),
+            Error::Line { number, line } => write!(f, "invalid line {}: {:?}", number, line),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Read(ref e) => Some(e),
+            Error::Header(_) => None,
+            Error::Line { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Read(err)
+    }
+}
+
+/// header lines of a manifest with `funcs` digests.
+pub fn header(funcs: &[Func]) -> String {
+    let mut columns = vec![SIZE_COLUMN.to_string()];
+    columns.extend(funcs.iter().map(|f| f.to_string().to_lowercase()));
+    columns.push(FILENAME_COLUMN.to_string());
+    format!("{}\n{}{}\n", HEADER, COLUMNS_PREFIX, columns.join(","))
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},", self.size)?;
+        for digest in &self.digests {
+            write!(f, "{},", digest)?;
+        }
+        write!(f, "{}", self.name)
+    }
+}
+
+pub fn parse<R: BufRead>(r: R) -> Result<Manifest, Error> {
+    let mut lines = r.lines();
+    if lines.next().transpose()?.as_deref() != Some(HEADER) {
+        return Err(Error::Header(format!("first line is not {:?}", HEADER)));
+    }
+    let funcs = match lines.next().transpose()? {
+        Some(line) => columns(&line)?,
+        None => return Err(Error::Header("no columns line".to_string())),
+    };
+
+    let mut entries = Vec::new();
+    // header takes 2 lines.
+    for (number, line) in (3..).zip(lines) {
+        let line = line?;
+        if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+            continue;
+        }
+        entries.push(entry(&line, funcs.len()).ok_or(Error::Line { number, line })?);
+    }
+    Ok(Manifest { funcs, entries })
+}
+
+fn columns(line: &str) -> Result<Vec<Func>, Error> {
+    let columns: Vec<&str> = line
+        .strip_prefix(COLUMNS_PREFIX)
+        .ok_or(Error::Header("no columns line".to_string()))?
+        .split(',')
+        .collect();
+    match columns.as_slice() {
+        [SIZE_COLUMN, funcs @ .., FILENAME_COLUMN] if !funcs.is_empty() => funcs
+            .iter()
+            .map(|f| f.parse().map_err(Error::Header))
+            .collect(),
+        _ => Err(Error::Header(format!("unexpected columns {:?}", line))),
+    }
+}
+
+// file names may contain commas, so they take the rest of the line.
+fn entry(line: &str, digests: usize) -> Option<Entry> {
+    let mut fields = line.splitn(digests + 2, ',');
+    let size = fields.next()?.parse().ok()?;
+    let digests = (0..digests)
+        .map(|_| fields.next().map(|d| d.to_lowercase()))
+        .collect::<Option<Vec<String>>>()?;
+    let name = fields.next()?.to_string();
+    Some(Entry {
+        size,
+        digests,
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! parse_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse($data.as_bytes()).ok();
+                let expected: Option<Manifest> = $expected;
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    parse_test!(
+        hashdeep_output,
+        Some(Manifest {
+            funcs: vec![Func::MD5, Func::SHA256],
+            entries: vec![Entry {
+                size: 3,
+                digests: vec![
+                    "764efa883dda1e11db47671c4a3bbd9e".to_string(),
+                    "98ea6e4f216f2fb4b69fff9b3a44842c38686ca685f3f55dc48c5d3fb1107be4".to_string()
+                ],
+                name: "/tmp/a,b".to_string(),
+            }],
+        }),
+        "%%%% HASHDEEP-1.0\n\
+         %%%% size,md5,sha256,filename\n\
+         ## Invoked from: /tmp\n\
+         ## $ hashdeep /tmp/a,b\n\
+         ## \n\
+         3,764EFA883DDA1E11DB47671C4A3BBD9E,\
+         98ea6e4f216f2fb4b69fff9b3a44842c38686ca685f3f55dc48c5d3fb1107be4,/tmp/a,b\n"
+    );
+    parse_test!(
+        no_entries,
+        Some(Manifest {
+            funcs: vec![Func::SHA256],
+            entries: vec![],
+        }),
+        "%%%% HASHDEEP-1.0\n%%%% size,sha256,filename\n"
+    );
+    parse_test!(no_header, None, "3,764efa883dda1e11db47671c4a3bbd9e,a\n");
+    parse_test!(
+        unsupported_hash,
+        None,
+        "%%%% HASHDEEP-1.0\n%%%% size,tiger,filename\n"
+    );
+    parse_test!(
+        missing_digest,
+        None,
+        "%%%% HASHDEEP-1.0\n%%%% size,md5,sha256,filename\n3,764efa883dda1e11db47671c4a3bbd9e\n"
+    );
+
+    #[test]
+    fn header_entry() {
+        let entry = Entry {
+            size: 0,
+            digests: vec!["d41d8cd98f00b204e9800998ecf8427e".to_string()],
+            name: "empty".to_string(),
+        };
+        let text = format!("{}{}\n", header(&[Func::MD5]), entry);
+        assert_eq!(
+            text,
+            "%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n0,d41d8cd98f00b204e9800998ecf8427e,empty\n"
+        );
+        assert_eq!(
+            parse(text.as_bytes()).unwrap(),
+            Manifest {
+                funcs: vec![Func::MD5],
+                entries: vec![entry],
+            }
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    OddLength,
+    InvalidChar(char),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::OddLength => write!(f, "odd number of hex digits"),
+            DecodeError::InvalidChar(c) => write!(f, "invalid hex digit: {:?}", c),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// decode hex string (upper or lower case) into bytes.
+pub fn decode(s: &str) -> Result<Vec<u8>, DecodeError> {
+    if !s.len().is_multiple_of(2) {
+        return Err(DecodeError::OddLength);
+    }
+
+    let digit = |c: char| c.to_digit(16).ok_or(DecodeError::InvalidChar(c));
+    let chars: Vec<char> = s.chars().collect();
+    chars
+        .chunks(2)
+        .map(|pair| Ok((digit(pair[0])? << 4 | digit(pair[1])?) as u8))
+        .collect()
+}
+
+/// encode bytes as lower case hex string.
+pub fn encode(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! decode_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let actual = decode($data);
+                let expected: Result<Vec<u8>, DecodeError> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    decode_test!(empty, Ok(vec![]), "");
+    decode_test!(lower, Ok(vec![0x0a, 0xbc]), "0abc");
+    decode_test!(upper, Ok(vec![0xde, 0xad]), "DEAD");
+    decode_test!(odd, Err(DecodeError::OddLength), "abc");
+    decode_test!(invalid, Err(DecodeError::InvalidChar('g')), "0g");
+
+    #[test]
+    fn encode_decode() {
+        let data = vec![0x00, 0x0a, 0xbc, 0xff];
+        assert_eq!("000abcff", encode(&data));
+        assert_eq!(Ok(data.clone()), decode(&encode(&data)));
+    }
+}
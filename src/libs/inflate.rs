@@ -0,0 +1,451 @@
+//! DEFLATE (RFC 1951) decoder.
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::io::{self, Read};
+
+const MAX_BITS: usize = 15;
+const END_OF_BLOCK: u16 = 256;
+const FIXED_LITERAL_CODES: usize = 288;
+const FIXED_DISTANCE_CODES: usize = 30;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// order of code length code lengths in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Truncated,
+    InvalidBlockType,
+    StoredLength,
+    InvalidCodeLengths,
+    InvalidSymbol,
+    DistanceTooFar,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "truncated deflate stream"),
+            Error::InvalidBlockType => write!(f, "invalid deflate block type"),
+            Error::StoredLength => write!(f, "stored block length does not match its complement"),
+            Error::InvalidCodeLengths => write!(f, "invalid huffman code lengths"),
+            Error::InvalidSymbol => write!(f, "invalid huffman symbol"),
+            Error::DistanceTooFar => write!(f, "distance refers before start of data"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// bytes of output a match can refer back to.
+const WINDOW_SIZE: usize = 32 * 1024;
+/// output decoded at once before it is handed to the reader.
+const OUTPUT_CHUNK_SIZE: usize = 16 * 1024;
+
+// bits are taken from the least significant end of every byte.
+// bytes are read from `r` only when their bits are needed.
+struct BitReader<R> {
+    r: R,
+    buf: u32,
+    count: u32,
+}
+
+impl<R: Read> BitReader<R> {
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let mut b = [0u8; 1];
+            match self.r.read(&mut b) {
+                Ok(0) => return Err(Error::Truncated.into()),
+                Ok(_) => (),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+            self.buf |= (b[0] as u32) << self.count;
+            self.count += 8;
+        }
+        let v = self.buf & ((1u64 << n) - 1) as u32;
+        self.buf >>= n;
+        self.count -= n;
+        Ok(v)
+    }
+
+    // drop bits up to the byte boundary.
+    fn align(&mut self) {
+        self.buf = 0;
+        self.count = 0;
+    }
+}
+
+// canonical huffman code by number of codes of every length and symbols
+// ordered by code.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        // over-subscribed codes can't be decoded; incomplete ones are allowed.
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(Error::InvalidCodeLengths);
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, r: &mut BitReader<R>) -> io::Result<u16> {
+        // first code and index of the first symbol of the current length.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= r.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::InvalidSymbol.into())
+    }
+}
+
+enum State {
+    /// next is a block header, or the end after the last block.
+    Header,
+    /// bytes of a stored block left.
+    Stored(usize),
+    /// compressed block with its literal/length and distance codes.
+    Codes(Huffman, Huffman),
+    Done,
+}
+
+/// streaming decoder of raw deflate data read from `r`, which keeps only
+/// the window of the last 32 KiB and the output not yet read in memory.
+/// `r` is read one byte at a time and never past the end of the stream,
+/// so it should be buffered.
+pub struct Inflater<R> {
+    bits: BitReader<R>,
+    state: State,
+    last: bool,
+    window: Vec<u8>,
+    /// bytes decoded so far.
+    total: u64,
+    pending: VecDeque<u8>,
+}
+
+impl<R: Read> Inflater<R> {
+    pub fn new(r: R) -> Self {
+        Inflater {
+            bits: BitReader {
+                r,
+                buf: 0,
+                count: 0,
+            },
+            state: State::Header,
+            last: false,
+            window: vec![0; WINDOW_SIZE],
+            total: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn emit(&mut self, b: u8) {
+        self.window[self.total as usize % WINDOW_SIZE] = b;
+        self.total += 1;
+        self.pending.push_back(b);
+    }
+
+    /// decodes a block header or up to about `OUTPUT_CHUNK_SIZE` bytes of a block.
+    fn step(&mut self) -> io::Result<()> {
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::Header if self.last => (),
+            State::Header => {
+                self.last = self.bits.bits(1)? == 1;
+                self.state = match self.bits.bits(2)? {
+                    0 => {
+                        self.bits.align();
+                        let len = self.bits.bits(16)?;
+                        if self.bits.bits(16)? != !len & 0xFFFF {
+                            return Err(Error::StoredLength.into());
+                        }
+                        State::Stored(len as usize)
+                    }
+                    1 => {
+                        let (literals, distances) = fixed()?;
+                        State::Codes(literals, distances)
+                    }
+                    2 => {
+                        let (literals, distances) = dynamic(&mut self.bits)?;
+                        State::Codes(literals, distances)
+                    }
+                    _ => return Err(Error::InvalidBlockType.into()),
+                };
+            }
+            State::Stored(len) => {
+                let n = len.min(OUTPUT_CHUNK_SIZE);
+                for _ in 0..n {
+                    let b = self.bits.bits(8)? as u8;
+                    self.emit(b);
+                }
+                self.state = match len - n {
+                    0 => State::Header,
+                    left => State::Stored(left),
+                };
+            }
+            State::Codes(literals, distances) => {
+                let end = self.codes(&literals, &distances)?;
+                self.state = match end {
+                    true => State::Header,
+                    false => State::Codes(literals, distances),
+                };
+            }
+            State::Done => (),
+        }
+        Ok(())
+    }
+
+    /// decodes symbols until about `OUTPUT_CHUNK_SIZE` bytes are pending.
+    /// returns whether the end of the block was reached.
+    fn codes(&mut self, literals: &Huffman, distances: &Huffman) -> io::Result<bool> {
+        while self.pending.len() < OUTPUT_CHUNK_SIZE {
+            let symbol = literals.decode(&mut self.bits)?;
+            if symbol < END_OF_BLOCK {
+                self.emit(symbol as u8);
+                continue;
+            }
+            if symbol == END_OF_BLOCK {
+                return Ok(true);
+            }
+
+            let i = (symbol - END_OF_BLOCK - 1) as usize;
+            if i >= LENGTH_BASE.len() {
+                return Err(Error::InvalidSymbol.into());
+            }
+            let len = LENGTH_BASE[i] as usize + self.bits.bits(LENGTH_EXTRA[i] as u32)? as usize;
+
+            let i = distances.decode(&mut self.bits)? as usize;
+            if i >= DISTANCE_BASE.len() {
+                return Err(Error::InvalidSymbol.into());
+            }
+            let distance =
+                DISTANCE_BASE[i] as usize + self.bits.bits(DISTANCE_EXTRA[i] as u32)? as usize;
+            if distance as u64 > self.total {
+                return Err(Error::DistanceTooFar.into());
+            }
+            // source and destination may overlap, so copy byte by byte.
+            for _ in 0..len {
+                let b = self.window[(self.total as usize - distance) % WINDOW_SIZE];
+                self.emit(b);
+            }
+        }
+        Ok(false)
+    }
+
+    /// reader the stream was read from, positioned after its last byte.
+    pub fn into_inner(self) -> R {
+        self.bits.r
+    }
+}
+
+impl<R: Read> Read for Inflater<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() && !matches!(self.state, State::Done) {
+            self.step()?;
+        }
+        let n = buf.len().min(self.pending.len());
+        for (b, p) in buf.iter_mut().zip(self.pending.drain(..n)) {
+            *b = p;
+        }
+        Ok(n)
+    }
+}
+
+/// decompress raw deflate `data`.
+/// returns decompressed data and number of bytes of `data` taken by the
+/// stream, which is where a zlib or gzip trailer starts.
+pub fn inflate(data: &[u8]) -> Result<(Vec<u8>, usize)> {
+    let mut inflater = Inflater::new(data);
+    let mut out = Vec::new();
+    inflater.read_to_end(&mut out).map_err(|err| {
+        // reading a slice fails only on invalid data.
+        err.into_inner()
+            .and_then(|err| err.downcast::<Error>().ok())
+            .map_or(Error::Truncated, |err| *err)
+    })?;
+    // bits left in the buffer belong to the last byte taken.
+    Ok((out, data.len() - inflater.into_inner().len()))
+}
+
+fn fixed() -> Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; FIXED_LITERAL_CODES];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    Ok((
+        Huffman::new(&lengths)?,
+        Huffman::new(&[5; FIXED_DISTANCE_CODES])?,
+    ))
+}
+
+fn dynamic<R: Read>(r: &mut BitReader<R>) -> io::Result<(Huffman, Huffman)> {
+    let literals = r.bits(5)? as usize + 257;
+    let distances = r.bits(5)? as usize + 1;
+    let code_lengths = r.bits(4)? as usize + 4;
+
+    let mut lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = r.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths)?;
+
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (len, repeat) = match code.decode(r)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let &prev = lengths.last().ok_or(Error::InvalidCodeLengths)?;
+                (prev, 3 + r.bits(2)?)
+            }
+            17 => (0, 3 + r.bits(3)?),
+            _ => (0, 11 + r.bits(7)?),
+        };
+        if lengths.len() + repeat as usize > literals + distances {
+            return Err(Error::InvalidCodeLengths.into());
+        }
+        lengths.extend((0..repeat).map(|_| len));
+    }
+    if lengths[END_OF_BLOCK as usize] == 0 {
+        return Err(Error::InvalidCodeLengths.into());
+    }
+
+    let (literal_lengths, distance_lengths) = lengths.split_at(literals);
+    Ok((
+        Huffman::new(literal_lengths)?,
+        Huffman::new(distance_lengths)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use proptest::prelude::*;
+    use std::io::Write;
+
+    macro_rules! inflate_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = &$data;
+                let actual = inflate(data);
+                let expected: Result<(Vec<u8>, usize)> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    inflate_test!(
+        stored_block,
+        Ok((b"abc".to_vec(), 8)),
+        [0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c', 0xAA]
+    );
+    // `zlib.compress(b"aaaaaaaaaa")` without zlib header and trailer.
+    inflate_test!(
+        fixed_block,
+        Ok((b"aaaaaaaaaa".to_vec(), 5)),
+        [0x4B, 0x4C, 0x84, 0x01, 0x00]
+    );
+    inflate_test!(empty_input, Err(Error::Truncated), []);
+    inflate_test!(reserved_block_type, Err(Error::InvalidBlockType), [0x07]);
+    inflate_test!(
+        stored_length,
+        Err(Error::StoredLength),
+        [0x01, 0x03, 0x00, 0xFC, 0xFE]
+    );
+    // fixed block starting with a match.
+    inflate_test!(distance_too_far, Err(Error::DistanceTooFar), [0x03, 0x02]);
+
+    // compressible data with matches at all distances: runs of a small
+    // alphabet mixed with random bytes.
+    fn sample(mut seed: u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                match seed % 4 {
+                    0 => (seed >> 8) as u8,
+                    n => b'a' + n as u8,
+                }
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn reference(seed in 1..u64::MAX, len in 0..100_000usize, level in 0..10u32) {
+            let data = sample(seed, len);
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(&data).unwrap();
+            let mut compressed = encoder.finish().unwrap();
+            let len = compressed.len();
+            compressed.extend_from_slice(b"trailer");
+
+            let (actual, taken) = inflate(&compressed).unwrap();
+            assert_eq!(actual, data);
+            assert_eq!(taken, len);
+        }
+    }
+}
@@ -1,32 +1,411 @@
 use std::fs;
 use std::io;
 use std::path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::libs::throttle::Limiter;
+
+/// path which means standard input.
+/// a file named `-` can still be read as `./-`.
+pub const STDIN_PATH: &str = "-";
+
+/// bandwidth limit of all inputs, see `set_bwlimit`.
+static BWLIMIT: OnceLock<Limiter> = OnceLock::new();
+
+/// limits reading of all inputs together to `rate` bytes per second.
+/// only the first call has effect.
+pub fn set_bwlimit(rate: u64) {
+    let _ = BWLIMIT.set(Limiter::new(rate));
+}
+
+/// files are opened with O_DIRECT, see `set_direct`.
+static DIRECT: AtomicBool = AtomicBool::new(false);
+/// alignment of buffers, offsets and lengths of O_DIRECT reads,
+/// enough for the logical block size of common devices.
+const DIRECT_ALIGN: usize = 4096;
+const DIRECT_BUF_SIZE: usize = 1 << 20;
+
+/// files opened afterwards are read bypassing the page cache, so their
+/// digests reflect the bytes on the device. linux only.
+pub fn set_direct() -> io::Result<()> {
+    if cfg!(target_os = "linux") {
+        DIRECT.store(true, Ordering::Relaxed);
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "direct I/O is supported only on linux",
+        ))
+    }
+}
+
+/// coarse class of a read error, telling failing media from other trouble.
+pub fn error_class(err: &io::Error) -> &'static str {
+    #[cfg(unix)]
+    const MEDIA_ERRORS: &[i32] = &[libc::EIO, libc::ENXIO];
+    // ERROR_CRC, ERROR_SECTOR_NOT_FOUND, ERROR_READ_FAULT, ERROR_DEVICE_HARDWARE_ERROR.
+    #[cfg(windows)]
+    const MEDIA_ERRORS: &[i32] = &[23, 27, 30, 483];
+    #[cfg(not(any(unix, windows)))]
+    const MEDIA_ERRORS: &[i32] = &[];
+
+    if err
+        .raw_os_error()
+        .is_some_and(|code| MEDIA_ERRORS.contains(&code))
+    {
+        return "media error";
+    }
+    match err.kind() {
+        io::ErrorKind::NotFound => "not found",
+        io::ErrorKind::PermissionDenied => "permission denied",
+        io::ErrorKind::UnexpectedEof => "truncated",
+        io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            "transient"
+        }
+        _ => "I/O error",
+    }
+}
 
 pub enum Input<'a> {
     File(fs::File),
     Stdin(io::StdinLock<'a>),
+    /// standard input which is never read, see `with_stdin_null`.
+    Empty(io::Empty),
+    /// inherited file descriptor, see `from_fd`.
+    Fd(fs::File),
+    /// file read bypassing the page cache, see `set_direct`.
+    Direct(Direct),
 }
 
 impl<'a> Input<'a> {
     pub fn new(file: &path::PathBuf) -> io::Result<Input<'a>> {
-        match fs::File::open(file) {
-            Ok(file) => Ok(Input::File(file)),
-            Err(err) => match err.kind() {
-                io::ErrorKind::NotFound => match file.to_str() {
-                    Some("-") => Ok(Input::Stdin(io::stdin().lock())),
-                    _ => Err(err),
-                },
-                _ => Err(err),
-            },
+        Input::with_stdin_null(file, false)
+    }
+
+    /// like `new`, but standard input is an empty input when `stdin_null`,
+    /// so scripts can hash nothing without blocking on a terminal or pipe.
+    pub fn with_stdin_null(file: &path::PathBuf, stdin_null: bool) -> io::Result<Input<'a>> {
+        if file.as_os_str() == STDIN_PATH {
+            if stdin_null {
+                return Ok(Input::Empty(io::empty()));
+            }
+            return Ok(Input::Stdin(io::stdin().lock()));
+        }
+        if DIRECT.load(Ordering::Relaxed) {
+            return Ok(Input::Direct(Direct::open(file)?));
+        }
+        Ok(Input::File(fs::File::open(file)?))
+    }
+
+    /// reads inherited file descriptor `fd` (like `3` of `3<secret`).
+    /// the descriptor is duplicated, so it stays open for the caller.
+    #[cfg(unix)]
+    pub fn from_fd(fd: i32) -> io::Result<Input<'a>> {
+        use std::os::fd::BorrowedFd;
+
+        if fd < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "negative file descriptor",
+            ));
         }
+        // SAFETY: the descriptor is only borrowed to duplicate it, which
+        // fails with EBADF when it is not open.
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+        Ok(Input::Fd(fs::File::from(fd)))
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_fd(_fd: i32) -> io::Result<Input<'a>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "file descriptors are supported only on unix",
+        ))
+    }
+
+    /// name of file descriptor `fd` in output, as a shell process substitution.
+    pub fn fd_name(fd: i32) -> String {
+        format!("/dev/fd/{}", fd)
     }
 }
 
 impl<'a> io::Read for Input<'a> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        match *self {
+        let n = match *self {
             Input::File(ref mut file) => file.read(buf),
             Input::Stdin(ref mut stdin) => stdin.read(buf),
+            Input::Empty(ref mut empty) => empty.read(buf),
+            Input::Fd(ref mut file) => file.read(buf),
+            Input::Direct(ref mut direct) => direct.read(buf),
+        }?;
+        if let Some(limiter) = BWLIMIT.get() {
+            limiter.consume(n);
         }
+        Ok(n)
     }
 }
+
+impl<'a> io::Seek for Input<'a> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        match *self {
+            Input::File(ref mut file) | Input::Fd(ref mut file) => file.seek(pos),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "standard input can not seek",
+            )),
+        }
+    }
+}
+
+/// file opened with O_DIRECT and read through a buffer aligned for it.
+pub struct Direct {
+    file: fs::File,
+    buf: Vec<u8>,
+    /// offset of the aligned part of `buf`.
+    start: usize,
+    pos: usize,
+    len: usize,
+}
+
+impl Direct {
+    #[cfg(target_os = "linux")]
+    fn open(path: &path::Path) -> io::Result<Direct> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)
+            .map_err(|err| match err.raw_os_error() {
+                Some(libc::EINVAL) => io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "the file system does not support direct I/O",
+                ),
+                _ => err,
+            })?;
+        let buf = vec![0; DIRECT_BUF_SIZE + DIRECT_ALIGN];
+        let start = buf.as_ptr().align_offset(DIRECT_ALIGN);
+        Ok(Direct {
+            file,
+            buf,
+            start,
+            pos: 0,
+            len: 0,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open(_path: &path::Path) -> io::Result<Direct> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "direct I/O is supported only on linux",
+        ))
+    }
+}
+
+impl io::Read for Direct {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.len {
+            // whole aligned blocks are read, only the last read is short.
+            let aligned = &mut self.buf[self.start..self.start + DIRECT_BUF_SIZE];
+            self.len = self.file.read(aligned)?;
+            self.pos = 0;
+        }
+        let data = &self.buf[self.start + self.pos..self.start + self.len];
+        let n = data.len().min(out.len());
+        out[..n].copy_from_slice(&data[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// inputs read one after another as one stream.
+/// each input is opened only when the previous one is exhausted.
+pub struct Concat<'a> {
+    files: std::vec::IntoIter<path::PathBuf>,
+    current: Option<Input<'a>>,
+    stdin_null: bool,
+}
+
+impl<'a> Concat<'a> {
+    pub fn new(files: Vec<path::PathBuf>, stdin_null: bool) -> Concat<'a> {
+        Concat {
+            files: files.into_iter(),
+            current: None,
+            stdin_null,
+        }
+    }
+}
+
+impl<'a> io::Read for Concat<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let current = match self.current {
+                Some(ref mut current) => current,
+                None => match self.files.next() {
+                    Some(file) => self.current.insert(
+                        Input::with_stdin_null(&file, self.stdin_null).map_err(|err| {
+                            io::Error::new(err.kind(), format!("{:?}: {}", file, err))
+                        })?,
+                    ),
+                    None => return Ok(0),
+                },
+            };
+            let n = current.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            self.current = None;
+        }
+    }
+}
+
+/// reader which turns CRLF line ends into LF, so a text file checked out
+/// with Windows line ends has the same digest as on unix.
+pub struct Text<R> {
+    inner: io::BufReader<R>,
+    /// a CR was read but not yet passed on, as the next byte decides.
+    cr: bool,
+}
+
+impl<R: io::Read> Text<R> {
+    pub fn new(r: R) -> Text<R> {
+        Text {
+            inner: io::BufReader::new(r),
+            cr: false,
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for Text<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        use io::BufRead;
+
+        if out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let data = self.inner.fill_buf()?;
+            if data.is_empty() {
+                if self.cr {
+                    self.cr = false;
+                    out[0] = b'\r';
+                    return Ok(1);
+                }
+                return Ok(0);
+            }
+
+            let (mut n, mut used) = (0, 0);
+            for &b in data {
+                if self.cr && b != b'\n' {
+                    if n == out.len() {
+                        break;
+                    }
+                    out[n] = b'\r';
+                    n += 1;
+                }
+                // a CR before LF is dropped.
+                self.cr = false;
+                if n == out.len() {
+                    break;
+                }
+                used += 1;
+                match b {
+                    b'\r' => self.cr = true,
+                    b => {
+                        out[n] = b;
+                        n += 1;
+                    }
+                }
+            }
+            self.inner.consume(used);
+            if n > 0 {
+                return Ok(n);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn direct_read() {
+        let path = std::env::temp_dir().join(format!("ssl-direct-{}", std::process::id()));
+        let data: Vec<u8> = (0..DIRECT_BUF_SIZE + 5000).map(|i| i as u8).collect();
+        fs::write(&path, &data).unwrap();
+        let mut direct = match Direct::open(&path) {
+            Ok(direct) => direct,
+            // file systems like overlayfs may refuse O_DIRECT.
+            Err(err) if err.kind() == io::ErrorKind::Unsupported => return,
+            Err(err) => panic!("{}", err),
+        };
+        let mut actual = Vec::new();
+        direct.read_to_end(&mut actual).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(actual == data);
+    }
+
+    #[test]
+    fn error_classes() {
+        #[cfg(unix)]
+        assert_eq!(
+            "media error",
+            error_class(&io::Error::from_raw_os_error(libc::EIO))
+        );
+        assert_eq!(
+            "not found",
+            error_class(&io::Error::from(io::ErrorKind::NotFound))
+        );
+        assert_eq!("I/O error", error_class(&io::Error::other("broken")));
+    }
+
+    /// reader which returns at most `chunk` bytes per read.
+    struct Chunks<'a>(&'a [u8], usize);
+
+    impl io::Read for Chunks<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.0.len().min(self.1).min(buf.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    macro_rules! text_test {
+        ($name:ident,$data:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let expected: &[u8] = $expected;
+                // every split of the input and of the output buffer.
+                for chunk in 1..=$data.len().max(1) {
+                    for out in 1..=3 {
+                        let mut r = Text::new(Chunks($data, chunk));
+                        let mut actual = Vec::new();
+                        let mut buf = vec![0u8; out];
+                        loop {
+                            let n = r.read(&mut buf).unwrap();
+                            if n == 0 {
+                                break;
+                            }
+                            actual.extend_from_slice(&buf[..n]);
+                        }
+                        println!("  actual: {:?} (chunk {}, out {})", actual, chunk, out);
+                        println!("expected: {:?}", expected);
+                        assert_eq!(expected, &actual[..]);
+                    }
+                }
+            }
+        };
+    }
+
+    text_test!(text_empty, b"", b"");
+    text_test!(text_lf, b"a\nb\n", b"a\nb\n");
+    text_test!(text_crlf, b"a\r\nb\r\n", b"a\nb\n");
+    text_test!(text_lone_cr, b"a\rb\r", b"a\rb\r");
+    text_test!(text_cr_crlf, b"\r\r\n\r", b"\r\n\r");
+}
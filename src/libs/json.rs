@@ -0,0 +1,526 @@
+//! minimal JSON (RFC 8259) parser and compact writer.
+
+use std::fmt;
+
+/// nesting depth limit of arrays and objects.
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// number as written, so integers of any size are kept exactly.
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// members in document order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// value of the first member named `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// compact JSON text of the value.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write_string(f, s),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Value {
+        Value::Number(n.to_string())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Error {
+    pub offset: usize,
+    pub msg: &'static str,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "json: offset {}: {}", self.offset, self.msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn parse(s: &str) -> Result<Value, Error> {
+    let mut p = Parser {
+        s: s.as_bytes(),
+        pos: 0,
+    };
+    let v = p.value(0)?;
+    p.whitespace();
+    if p.pos != p.s.len() {
+        return Err(p.error("trailing characters"));
+    }
+    Ok(v)
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &'static str) -> Error {
+        Error {
+            offset: self.pos,
+            msg,
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.s.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8, msg: &'static str) -> Result<(), Error> {
+        self.whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(msg));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, v: Value) -> Result<Value, Error> {
+        if !self.s[self.pos..].starts_with(word.as_bytes()) {
+            return Err(self.error("invalid literal"));
+        }
+        self.pos += word.len();
+        Ok(v)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.whitespace();
+        match self.peek() {
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'[') => self.array(depth),
+            Some(b'{') => self.object(depth),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected member name"));
+            }
+            let key = self.string()?;
+            self.expect(b':', "expected ':'")?;
+            members.push((key, self.value(depth + 1)?));
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<Value, Error> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let int_start = self.pos;
+        match self.digits() {
+            0 => return Err(self.error("invalid number")),
+            n if n > 1 && self.s[int_start] == b'0' => {
+                return Err(self.error("leading zero in number"))
+            }
+            _ => (),
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return Err(self.error("invalid fraction"));
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(self.error("invalid exponent"));
+            }
+        }
+        // only ASCII was consumed.
+        let n = std::str::from_utf8(&self.s[start..self.pos]).unwrap();
+        Ok(Value::Number(n.to_string()))
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .s
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .filter(|d| d.bytes().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let e = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    let c = match e {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                0..=0x1F => return Err(self.error("control character in string")),
+                _ => out.push(c),
+            }
+        }
+        // input is a str and escapes are valid chars.
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, Error> {
+        let hi = self.hex4()?;
+        let code = match hi {
+            0xD800..=0xDBFF => {
+                if !self.s[self.pos..].starts_with(b"\\u") {
+                    return Err(self.error("unpaired surrogate"));
+                }
+                self.pos += 2;
+                let lo = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&lo) {
+                    return Err(self.error("unpaired surrogate"));
+                }
+                0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+            }
+            0xDC00..=0xDFFF => return Err(self.error("unpaired surrogate")),
+            c => c,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid \\u escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(v: &str) -> Value {
+        Value::String(v.to_string())
+    }
+
+    fn n(v: &str) -> Value {
+        Value::Number(v.to_string())
+    }
+
+    macro_rules! parse_test {
+        ($name:ident,$input:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse($input);
+                let expected = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    parse_test!(
+        literals,
+        " [null, true,false ] ",
+        Ok(Value::Array(vec![
+            Value::Null,
+            Value::Bool(true),
+            Value::Bool(false)
+        ]))
+    );
+    parse_test!(
+        numbers,
+        "[0, -1, 18446744073709551616, 1.5e-3, 2E+2]",
+        Ok(Value::Array(vec![
+            n("0"),
+            n("-1"),
+            n("18446744073709551616"),
+            n("1.5e-3"),
+            n("2E+2")
+        ]))
+    );
+    parse_test!(
+        escapes,
+        r#""a\"\\\/\b\f\n\r\té😀""#,
+        Ok(s("a\"\\/\u{8}\u{c}\n\r\t\u{e9}\u{1f600}"))
+    );
+    parse_test!(utf8, "\"h\u{e9}llo\"", Ok(s("h\u{e9}llo")));
+    parse_test!(
+        object,
+        r#"{"a": {}, "b": [], "a": 1}"#,
+        Ok(Value::Object(vec![
+            ("a".to_string(), Value::Object(vec![])),
+            ("b".to_string(), Value::Array(vec![])),
+            ("a".to_string(), n("1")),
+        ]))
+    );
+    parse_test!(
+        empty,
+        " ",
+        Err(Error {
+            offset: 1,
+            msg: "unexpected end of input"
+        })
+    );
+    parse_test!(
+        trailing,
+        "{} x",
+        Err(Error {
+            offset: 3,
+            msg: "trailing characters"
+        })
+    );
+    parse_test!(
+        trailing_comma,
+        "[1,]",
+        Err(Error {
+            offset: 3,
+            msg: "unexpected character"
+        })
+    );
+    parse_test!(
+        leading_zero,
+        "01",
+        Err(Error {
+            offset: 2,
+            msg: "leading zero in number"
+        })
+    );
+    parse_test!(
+        fraction,
+        "1.",
+        Err(Error {
+            offset: 2,
+            msg: "invalid fraction"
+        })
+    );
+    parse_test!(
+        unterminated,
+        r#"{"a"#,
+        Err(Error {
+            offset: 3,
+            msg: "unterminated string"
+        })
+    );
+    parse_test!(
+        control,
+        "\"a\nb\"",
+        Err(Error {
+            offset: 3,
+            msg: "control character in string"
+        })
+    );
+    parse_test!(
+        surrogate,
+        r#""\udc00""#,
+        Err(Error {
+            offset: 7,
+            msg: "unpaired surrogate"
+        })
+    );
+    parse_test!(
+        name,
+        "{1:2}",
+        Err(Error {
+            offset: 1,
+            msg: "expected member name"
+        })
+    );
+    parse_test!(
+        colon,
+        r#"{"a" 2}"#,
+        Err(Error {
+            offset: 5,
+            msg: "expected ':'"
+        })
+    );
+
+    #[test]
+    fn depth() {
+        let deep = "[".repeat(MAX_DEPTH + 2);
+        assert_eq!(parse(&deep).unwrap_err().msg, "nesting too deep");
+        let ok = "[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH);
+        assert!(parse(&ok).is_ok());
+    }
+
+    #[test]
+    fn accessors() {
+        let v = parse(r#"{"size": 42, "digest": "sha256:ab", "layers": [1]}"#).unwrap();
+        assert_eq!(v.get("size").and_then(Value::as_u64), Some(42));
+        assert_eq!(v.get("digest").and_then(Value::as_str), Some("sha256:ab"));
+        assert_eq!(
+            v.get("layers").and_then(Value::as_array).map(<[_]>::len),
+            Some(1)
+        );
+        assert_eq!(v.get("missing"), None);
+        assert_eq!(n("-1").as_u64(), None);
+    }
+
+    #[test]
+    fn display() {
+        let text = r#"{"a":[null,true,-1.5e3,"q\"\\\n\u0001é"],"":{}}"#;
+        let v = parse(text).unwrap();
+        assert_eq!(text, v.to_string());
+        assert_eq!(Ok(v), parse(&parse(text).unwrap().to_string()));
+    }
+}
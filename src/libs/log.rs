@@ -0,0 +1,220 @@
+//! destinations of diagnostics: standard error, syslog or the systemd journal.
+//! syslog and journal messages are sent over their local datagram sockets,
+//! so no client library is needed.
+
+use std::fmt;
+use std::io;
+use std::str;
+
+/// name of the program in syslog and journal messages.
+pub const IDENT: &str = "ssl";
+/// syslog socket of the local daemon.
+pub const SYSLOG_PATH: &str = "/dev/log";
+/// native protocol socket of systemd-journald.
+pub const JOURNAL_PATH: &str = "/run/systemd/journal/socket";
+
+/// `user-level messages` syslog facility.
+const FACILITY_USER: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Level {
+    /// syslog severity, also used as journal PRIORITY.
+    fn severity(self) -> u8 {
+        match self {
+            Level::Error => 3,
+            Level::Warning => 4,
+            Level::Info => 6,
+        }
+    }
+}
+
+pub trait Backend {
+    /// writes one message. messages which can not be delivered
+    /// are written to standard error instead.
+    fn log(&mut self, level: Level, msg: &str);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    Stderr,
+    Syslog,
+    Journald,
+}
+
+impl Target {
+    pub const ALL: [Target; 3] = [Target::Stderr, Target::Syslog, Target::Journald];
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Stderr => write!(f, "stderr"),
+            Target::Syslog => write!(f, "syslog"),
+            Target::Journald => write!(f, "journald"),
+        }
+    }
+}
+
+impl str::FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Target::ALL
+            .into_iter()
+            .find(|t| t.to_string() == s)
+            .ok_or(format!(
+                "unsupported log target {:?} (stderr, syslog or journald)",
+                s
+            ))
+    }
+}
+
+/// connects a backend to `target`.
+pub fn open(target: Target) -> io::Result<Box<dyn Backend>> {
+    match target {
+        Target::Stderr => Ok(Box::new(Stderr)),
+        #[cfg(unix)]
+        Target::Syslog => Ok(Box::new(Datagram::connect(SYSLOG_PATH, Format::Syslog)?)),
+        #[cfg(unix)]
+        Target::Journald => Ok(Box::new(Datagram::connect(JOURNAL_PATH, Format::Journal)?)),
+        #[cfg(not(unix))]
+        _ => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("log target {} is only supported on unix", target),
+        )),
+    }
+}
+
+pub struct Stderr;
+
+impl Backend for Stderr {
+    fn log(&mut self, _level: Level, msg: &str) {
+        eprintln!("{}", msg);
+    }
+}
+
+/// RFC 3164 message as accepted by the local syslog socket,
+/// which adds the time itself.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn syslog_message(level: Level, pid: u32, msg: &str) -> Vec<u8> {
+    let pri = FACILITY_USER * 8 + level.severity();
+    format!("<{}>{}[{}]: {}", pri, IDENT, pid, msg).into_bytes()
+}
+
+/// journal native protocol message: `KEY=value` lines, or for values
+/// with newlines the key, a newline, the little-endian 64-bit length
+/// and the value.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn journal_message(level: Level, msg: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let fields = [
+        ("PRIORITY", level.severity().to_string()),
+        ("SYSLOG_IDENTIFIER", IDENT.to_string()),
+        ("MESSAGE", msg.to_string()),
+    ];
+    for (key, value) in fields {
+        buf.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            buf.push(b'\n');
+            buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            buf.push(b'=');
+        }
+        buf.extend_from_slice(value.as_bytes());
+        buf.push(b'\n');
+    }
+    buf
+}
+
+#[cfg(unix)]
+enum Format {
+    Syslog,
+    Journal,
+}
+
+/// backend which sends every message as one datagram to a local socket.
+#[cfg(unix)]
+struct Datagram {
+    socket: std::os::unix::net::UnixDatagram,
+    format: Format,
+}
+
+#[cfg(unix)]
+impl Datagram {
+    fn connect<P: AsRef<std::path::Path>>(path: P, format: Format) -> io::Result<Datagram> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        socket
+            .connect(path.as_ref())
+            .map_err(|err| io::Error::new(err.kind(), format!("{:?}: {}", path.as_ref(), err)))?;
+        Ok(Datagram { socket, format })
+    }
+}
+
+#[cfg(unix)]
+impl Backend for Datagram {
+    fn log(&mut self, level: Level, msg: &str) {
+        let buf = match self.format {
+            Format::Syslog => syslog_message(level, std::process::id(), msg),
+            Format::Journal => journal_message(level, msg),
+        };
+        if let Err(err) = self.socket.send(&buf) {
+            eprintln!("{} (not logged: {})", msg, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target() {
+        for t in Target::ALL {
+            assert_eq!(Ok(t), t.to_string().parse());
+        }
+        assert!("file".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn syslog() {
+        assert_eq!(
+            b"<11>ssl[42]: f: digest incorrect".to_vec(),
+            syslog_message(Level::Error, 42, "f: digest incorrect")
+        );
+        assert_eq!(
+            b"<14>ssl[1]: ok".to_vec(),
+            syslog_message(Level::Info, 1, "ok")
+        );
+    }
+
+    #[test]
+    fn journal() {
+        let mut expected = b"PRIORITY=4\nSYSLOG_IDENTIFIER=ssl\nMESSAGE\n".to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        assert_eq!(expected, journal_message(Level::Warning, "a\nb"));
+        assert!(journal_message(Level::Error, "x").ends_with(b"\nMESSAGE=x\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn datagram() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = std::env::temp_dir().join(format!("ssl-log-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+        let mut backend = Datagram::connect(&path, Format::Journal).unwrap();
+        backend.log(Level::Error, "failed");
+        let mut buf = [0u8; 256];
+        let n = server.recv(&mut buf).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(journal_message(Level::Error, "failed"), buf[..n]);
+    }
+}
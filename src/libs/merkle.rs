@@ -0,0 +1,137 @@
+//! Merkle tree hash of data split into fixed size chunks, as defined for
+//! Certificate Transparency (RFC 6962): leaves are H(0x00 || chunk), nodes
+//! H(0x01 || left || right), and the left subtree holds the largest power of
+//! two of the leaves. chunks are independent, so one large input is hashed
+//! on several cores.
+
+use std::io::{self, Read, Write};
+
+use crate::libs::hash::{self, Digest, Func};
+use crate::libs::pool;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Merkle tree hash of `r` in chunks of `chunk_size` bytes, hashing up to
+/// `jobs` chunks at once. the empty input has the digest of no data.
+pub fn digest<R: Read>(mut r: R, f: Func, chunk_size: usize, jobs: usize) -> io::Result<Digest> {
+    if chunk_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "chunk size must not be zero",
+        ));
+    }
+    let jobs = jobs.max(1);
+    let mut leaves = Vec::new();
+    loop {
+        let mut chunks = Vec::with_capacity(jobs);
+        while chunks.len() < jobs {
+            let mut chunk = Vec::with_capacity(chunk_size);
+            r.by_ref().take(chunk_size as u64).read_to_end(&mut chunk)?;
+            if chunk.is_empty() {
+                break;
+            }
+            chunks.push(chunk);
+        }
+        let last = chunks.len() < jobs;
+        pool::ordered_map(jobs, &chunks, |chunk| leaf(chunk, f), |d| leaves.push(d));
+        if last {
+            break;
+        }
+    }
+
+    match leaves.is_empty() {
+        true => hash::digest(io::empty(), f),
+        false => Ok(root(&leaves, f)),
+    }
+}
+
+fn leaf(chunk: &[u8], f: Func) -> Digest {
+    let mut hasher = hash::Hasher::new(f);
+    hasher
+        .write_all(&[LEAF_PREFIX])
+        .and_then(|_| hasher.write_all(chunk))
+        .expect("chunk is far shorter than the longest message");
+    hasher.compute()
+}
+
+fn root(leaves: &[Digest], f: Func) -> Digest {
+    if leaves.len() == 1 {
+        return leaves[0].clone();
+    }
+    let split = leaves.len().next_power_of_two() / 2;
+    let (left, right) = (root(&leaves[..split], f), root(&leaves[split..], f));
+    let mut hasher = hash::Hasher::new(f);
+    for data in [&[NODE_PREFIX][..], left.as_bytes(), right.as_bytes()] {
+        hasher
+            .write_all(data)
+            .expect("node is far shorter than the longest message");
+    }
+    hasher.compute()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256(data: &[u8]) -> Digest {
+        hash::digest(data, Func::SHA256).unwrap()
+    }
+
+    fn node(left: &Digest, right: &Digest) -> Digest {
+        sha256(&[&[NODE_PREFIX][..], left.as_bytes(), right.as_bytes()].concat())
+    }
+
+    macro_rules! merkle_test {
+        ($name:ident,$data:expr,$chunk:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                for jobs in [1, 2, 4] {
+                    let actual = digest(&$data[..], Func::SHA256, $chunk, jobs).unwrap();
+                    let expected: Digest = $expected;
+
+                    println!("  actual: {}", actual);
+                    println!("expected: {}", expected);
+
+                    assert_eq!(expected, actual);
+                }
+            }
+        };
+    }
+
+    merkle_test!(empty, b"", 2, sha256(b""));
+    merkle_test!(one_chunk, b"ab", 2, sha256(b"\x00ab"));
+    merkle_test!(
+        two_chunks,
+        b"abc",
+        2,
+        node(&sha256(b"\x00ab"), &sha256(b"\x00c"))
+    );
+    // RFC 6962: the left subtree holds two of three leaves.
+    merkle_test!(
+        three_chunks,
+        b"abcde",
+        2,
+        node(
+            &node(&sha256(b"\x00ab"), &sha256(b"\x00cd")),
+            &sha256(b"\x00e")
+        )
+    );
+    merkle_test!(
+        five_chunks,
+        b"abcde",
+        1,
+        node(
+            &node(
+                &node(&sha256(b"\x00a"), &sha256(b"\x00b")),
+                &node(&sha256(b"\x00c"), &sha256(b"\x00d"))
+            ),
+            &sha256(b"\x00e")
+        )
+    );
+
+    #[test]
+    fn zero_chunk_size() {
+        assert!(digest(&b"a"[..], Func::SHA256, 0, 1).is_err());
+    }
+}
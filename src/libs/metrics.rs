@@ -0,0 +1,206 @@
+//! counters of verifications exposed in the Prometheus text format
+//! on `/metrics` of a small HTTP server.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// upper bounds in seconds of the verification duration histogram buckets.
+const BUCKETS: [f64; 8] = [0.001, 0.01, 0.1, 0.5, 1.0, 10.0, 60.0, 600.0];
+/// longest accepted request head.
+const MAX_REQUEST: usize = 8192;
+/// how long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Histogram {
+    /// number of observations per bucket (not cumulative).
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    verified: AtomicU64,
+    failures: AtomicU64,
+    bytes: AtomicU64,
+    failing: AtomicU64,
+    durations: Mutex<Histogram>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// counts a verification of a file which read `bytes` bytes in `duration`.
+    pub fn record(&self, ok: bool, bytes: u64, duration: Duration) {
+        self.verified.fetch_add(1, Ordering::Relaxed);
+        if !ok {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+
+        let secs = duration.as_secs_f64();
+        let mut h = self.durations.lock().unwrap();
+        if let Some(i) = BUCKETS.iter().position(|&le| secs <= le) {
+            h.counts[i] += 1;
+        }
+        h.count += 1;
+        h.sum += secs;
+    }
+
+    /// sets the number of files failing their last verification.
+    pub fn set_failing(&self, files: u64) {
+        self.failing.store(files, Ordering::Relaxed);
+    }
+
+    /// metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut s = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            let _ = writeln!(s, "# HELP {} {}", name, help);
+            let _ = writeln!(s, "# TYPE {} {}", name, kind);
+            let _ = writeln!(s, "{} {}", name, value);
+        };
+        let load = |v: &AtomicU64| v.load(Ordering::Relaxed);
+        metric(
+            "ssl_files_verified_total",
+            "counter",
+            "Verifications of files against their expected digest.",
+            load(&self.verified),
+        );
+        metric(
+            "ssl_verification_failures_total",
+            "counter",
+            "Verifications which did not match or could not read the file.",
+            load(&self.failures),
+        );
+        metric(
+            "ssl_bytes_hashed_total",
+            "counter",
+            "Bytes read by verifications.",
+            load(&self.bytes),
+        );
+        metric(
+            "ssl_files_failing",
+            "gauge",
+            "Files failing their last verification.",
+            load(&self.failing),
+        );
+
+        let name = "ssl_verification_duration_seconds";
+        let h = self.durations.lock().unwrap();
+        let _ = writeln!(s, "# HELP {} Duration of verifications of files.", name);
+        let _ = writeln!(s, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (le, count) in BUCKETS.iter().zip(h.counts) {
+            cumulative += count;
+            let _ = writeln!(s, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+        }
+        let _ = writeln!(s, "{}_bucket{{le=\"+Inf\"}} {}", name, h.count);
+        let _ = writeln!(s, "{}_sum {}", name, h.sum);
+        let _ = writeln!(s, "{}_count {}", name, h.count);
+        s
+    }
+}
+
+/// serves `metrics` on `addr` from a background thread.
+/// returns the bound address (useful with port 0).
+pub fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let res = stream.and_then(|stream| respond(stream, &metrics));
+            if let Err(err) = res {
+                eprintln!("metrics: {}", err);
+            }
+        }
+    });
+    Ok(local)
+}
+
+fn respond(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    let line = head.split(|&b| b == b'\r').next().unwrap_or_default();
+    let mut parts = line.split(|&b| b == b' ');
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some(b"GET"), Some(b"/metrics")) => ("200 OK", metrics.render()),
+        (Some(b"GET"), _) => ("404 Not Found", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "method not allowed\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let m = Metrics::new();
+        m.record(true, 100, Duration::from_millis(5));
+        m.record(false, 20, Duration::from_secs(2));
+        m.record(true, 0, Duration::from_secs(3600));
+        m.set_failing(1);
+        let text = m.render();
+        print!("{}", text);
+        for line in [
+            "ssl_files_verified_total 3",
+            "ssl_verification_failures_total 1",
+            "ssl_bytes_hashed_total 120",
+            "ssl_files_failing 1",
+            "# TYPE ssl_verification_duration_seconds histogram",
+            "ssl_verification_duration_seconds_bucket{le=\"0.001\"} 0",
+            "ssl_verification_duration_seconds_bucket{le=\"0.01\"} 1",
+            "ssl_verification_duration_seconds_bucket{le=\"10\"} 2",
+            "ssl_verification_duration_seconds_bucket{le=\"600\"} 2",
+            "ssl_verification_duration_seconds_bucket{le=\"+Inf\"} 3",
+            "ssl_verification_duration_seconds_count 3",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {:?}", line);
+        }
+    }
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serve() {
+        let m = Arc::new(Metrics::new());
+        let addr = super::serve("127.0.0.1:0".parse().unwrap(), m.clone()).unwrap();
+        m.record(true, 7, Duration::ZERO);
+
+        let response = get(addr, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\nssl_bytes_hashed_total 7\n"));
+        assert!(get(addr, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
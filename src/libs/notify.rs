@@ -0,0 +1,211 @@
+//! notifications of verification failures: a command run for every failure,
+//! with placeholders substituted in its arguments, and a JSON webhook.
+
+use std::process::Command;
+use std::time::SystemTime;
+
+use crate::libs::audit;
+use crate::libs::json::Value;
+use crate::libs::log;
+use crate::libs::webhook;
+
+/// placeholders of command templates.
+const PLACEHOLDERS: [&str; 5] = ["{path}", "{expected}", "{actual}", "{error}", "{source}"];
+
+pub struct Failure {
+    /// checksum file or tree the file was verified from.
+    pub source: String,
+    pub path: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    pub error: String,
+}
+
+impl Failure {
+    fn placeholder(&self, name: &str) -> &str {
+        match name {
+            "{path}" => &self.path,
+            "{expected}" => self.expected.as_deref().unwrap_or_default(),
+            "{actual}" => self.actual.as_deref().unwrap_or_default(),
+            "{error}" => &self.error,
+            "{source}" => &self.source,
+            _ => unreachable!("unknown placeholder {}", name),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let opt = |s: &Option<String>| s.as_deref().map_or(Value::Null, Value::from);
+        Value::Object(vec![
+            ("source".to_string(), self.source.as_str().into()),
+            ("path".to_string(), self.path.as_str().into()),
+            ("expected".to_string(), opt(&self.expected)),
+            ("actual".to_string(), opt(&self.actual)),
+            ("error".to_string(), self.error.as_str().into()),
+        ])
+    }
+}
+
+/// command split into arguments like a shell does for quotes (no escapes,
+/// variables or globs). it is run without a shell, so substituted paths can
+/// not inject commands.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<String>);
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Template, String> {
+        let mut args = Vec::new();
+        let mut arg: Option<String> = None;
+        let mut quote = None;
+        for c in s.chars() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), c) => arg.get_or_insert_with(String::new).push(c),
+                (None, '\'' | '"') => {
+                    quote = Some(c);
+                    arg.get_or_insert_with(String::new);
+                }
+                (None, c) if c.is_whitespace() => args.extend(arg.take()),
+                (None, c) => arg.get_or_insert_with(String::new).push(c),
+            }
+        }
+        if quote.is_some() {
+            return Err(format!("unterminated quote in command {:?}", s));
+        }
+        args.extend(arg);
+        if args.is_empty() {
+            return Err("empty command".to_string());
+        }
+        Ok(Template(args))
+    }
+
+    /// arguments with placeholders replaced by the values of `failure`.
+    pub fn render(&self, failure: &Failure) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|arg| {
+                PLACEHOLDERS
+                    .iter()
+                    .fold(arg.clone(), |arg, p| arg.replace(p, failure.placeholder(p)))
+            })
+            .collect()
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Template::parse(s)
+    }
+}
+
+#[derive(Default)]
+pub struct Notifier {
+    pub on_fail: Option<Template>,
+    pub webhook: Option<String>,
+}
+
+impl Notifier {
+    /// runs the command for every failure and posts all of them
+    /// in one webhook request. problems are reported to `logger`.
+    pub fn notify(&self, failures: &[Failure], logger: &mut dyn log::Backend) {
+        if failures.is_empty() {
+            return;
+        }
+        if let Some(ref template) = self.on_fail {
+            for f in failures {
+                let args = template.render(f);
+                match Command::new(&args[0]).args(&args[1..]).status() {
+                    Ok(status) if status.success() => (),
+                    Ok(status) => logger.log(
+                        log::Level::Warning,
+                        &format!("on-fail {:?}: {}", args[0], status),
+                    ),
+                    Err(err) => logger.log(
+                        log::Level::Warning,
+                        &format!("on-fail {:?}: {}", args[0], err),
+                    ),
+                }
+            }
+        }
+        if let Some(ref url) = self.webhook {
+            if let Err(err) = webhook::post(url, &body(failures, SystemTime::now()).to_string()) {
+                logger.log(log::Level::Warning, &format!("webhook {}: {}", url, err));
+            }
+        }
+    }
+}
+
+/// webhook request body.
+fn body(failures: &[Failure], time: SystemTime) -> Value {
+    Value::Object(vec![
+        ("event".to_string(), "verification-failure".into()),
+        ("time".to_string(), audit::timestamp(time).as_str().into()),
+        (
+            "failures".to_string(),
+            Value::Array(failures.iter().map(Failure::to_json).collect()),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    macro_rules! template_test {
+        ($name:ident,$s:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = Template::parse($s);
+                let expected: Result<Vec<&str>, String> = $expected;
+                let expected =
+                    expected.map(|args| Template(args.iter().map(|a| a.to_string()).collect()));
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    template_test!(words, " page  {path} ", Ok(vec!["page", "{path}"]));
+    template_test!(
+        quotes,
+        r#"mail -s "bad {path}" 'ops team' a""b"#,
+        Ok(vec!["mail", "-s", "bad {path}", "ops team", "ab"])
+    );
+    template_test!(empty_arg, "cmd ''", Ok(vec!["cmd", ""]));
+    template_test!(
+        unterminated,
+        "cmd 'x",
+        Err("unterminated quote in command \"cmd 'x\"".to_string())
+    );
+    template_test!(empty, "  ", Err("empty command".to_string()));
+
+    fn failure() -> Failure {
+        Failure {
+            source: "SUMS".to_string(),
+            path: "a b; rm -rf /".to_string(),
+            expected: Some("00".to_string()),
+            actual: None,
+            error: "digest incorrect".to_string(),
+        }
+    }
+
+    #[test]
+    fn render() {
+        let t = Template::parse("echo {path} {expected}:{actual} '{error} in {source}'").unwrap();
+        assert_eq!(
+            vec!["echo", "a b; rm -rf /", "00:", "digest incorrect in SUMS"],
+            t.render(&failure())
+        );
+    }
+
+    #[test]
+    fn webhook_body() {
+        assert_eq!(
+            r#"{"event":"verification-failure","time":"1970-01-01T00:00:00Z","failures":[{"source":"SUMS","path":"a b; rm -rf /","expected":"00","actual":null,"error":"digest incorrect"}]}"#,
+            body(&[failure()], UNIX_EPOCH).to_string()
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! registry of object identifiers: dotted form, friendly name and DER
+//! content octets of the OBJECT IDENTIFIER, looked up in either direction.
+
+use std::error;
+use std::fmt;
+
+/// dotted form and name of known OIDs: hashes, signatures, public keys,
+/// curves, extended key usages, certificate extensions and PKCS#7 types.
+const TABLE: &[(&str, &str)] = &[
+    // hashes
+    ("1.2.840.113549.2.5", "md5"),
+    ("1.3.14.3.2.26", "sha1"),
+    ("2.16.840.1.101.3.4.2.1", "sha256"),
+    ("2.16.840.1.101.3.4.2.2", "sha384"),
+    ("2.16.840.1.101.3.4.2.3", "sha512"),
+    ("2.16.840.1.101.3.4.2.4", "sha224"),
+    // public keys and signatures
+    ("1.2.840.113549.1.1.1", "rsaEncryption"),
+    ("1.2.840.113549.1.1.4", "md5WithRSAEncryption"),
+    ("1.2.840.113549.1.1.5", "sha1WithRSAEncryption"),
+    ("1.2.840.113549.1.1.10", "rsassaPss"),
+    ("1.2.840.113549.1.1.11", "sha256WithRSAEncryption"),
+    ("1.2.840.113549.1.1.12", "sha384WithRSAEncryption"),
+    ("1.2.840.113549.1.1.13", "sha512WithRSAEncryption"),
+    ("1.2.840.10045.2.1", "id-ecPublicKey"),
+    ("1.2.840.10045.4.1", "ecdsa-with-SHA1"),
+    ("1.2.840.10045.4.3.2", "ecdsa-with-SHA256"),
+    ("1.2.840.10045.4.3.3", "ecdsa-with-SHA384"),
+    ("1.2.840.10045.4.3.4", "ecdsa-with-SHA512"),
+    ("1.3.101.112", "Ed25519"),
+    ("1.3.101.110", "X25519"),
+    // curves
+    ("1.2.840.10045.3.1.1", "prime192v1"),
+    ("1.3.132.0.33", "secp224r1"),
+    ("1.2.840.10045.3.1.7", "prime256v1"),
+    ("1.3.132.0.34", "secp384r1"),
+    ("1.3.132.0.35", "secp521r1"),
+    ("1.3.132.0.10", "secp256k1"),
+    // extended key usages
+    ("1.3.6.1.5.5.7.3.1", "serverAuth"),
+    ("1.3.6.1.5.5.7.3.2", "clientAuth"),
+    ("1.3.6.1.5.5.7.3.3", "codeSigning"),
+    ("1.3.6.1.5.5.7.3.4", "emailProtection"),
+    ("1.3.6.1.5.5.7.3.8", "timeStamping"),
+    ("1.3.6.1.5.5.7.3.9", "OCSPSigning"),
+    // certificate extensions
+    ("2.5.29.14", "subjectKeyIdentifier"),
+    ("2.5.29.15", "keyUsage"),
+    ("2.5.29.17", "subjectAltName"),
+    ("2.5.29.19", "basicConstraints"),
+    ("2.5.29.31", "cRLDistributionPoints"),
+    ("2.5.29.32", "certificatePolicies"),
+    ("2.5.29.35", "authorityKeyIdentifier"),
+    ("2.5.29.37", "extendedKeyUsage"),
+    ("1.3.6.1.5.5.7.1.1", "authorityInfoAccess"),
+    // PKCS#7
+    ("1.2.840.113549.1.7.1", "pkcs7-data"),
+    ("1.2.840.113549.1.7.2", "pkcs7-signedData"),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// dotted form with fewer than two arcs, a first arc above 2, a second
+    /// arc above 39 under 0 or 1, or an arc which is not a number.
+    InvalidDotted(String),
+    /// empty content, a truncated arc, or an arc with a leading 0x80 octet.
+    InvalidDer,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidDotted(s) => write!(f, "invalid OID {:?}", s),
+            Error::InvalidDer => write!(f, "invalid DER OBJECT IDENTIFIER"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// name of the OID in dotted form.
+pub fn name(dotted: &str) -> Option<&'static str> {
+    TABLE.iter().find(|(d, _)| *d == dotted).map(|(_, n)| *n)
+}
+
+/// dotted form of the OID named `name`, ignoring case.
+pub fn dotted(name: &str) -> Option<&'static str> {
+    TABLE
+        .iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(d, _)| *d)
+}
+
+/// name of the OID with DER content octets `der`, or its dotted form when
+/// it is not in the registry.
+pub fn describe(der: &[u8]) -> Result<String, Error> {
+    let dotted = decode(der)?;
+    Ok(name(&dotted).map(String::from).unwrap_or(dotted))
+}
+
+/// DER content octets of the OID in dotted form, or named `s`.
+pub fn encode(s: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::InvalidDotted(s.to_string());
+    let arcs = dotted(s)
+        .unwrap_or(s)
+        .split('.')
+        .map(|arc| arc.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u64>, Error>>()?;
+    if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] > 39) {
+        return Err(invalid());
+    }
+    let first = arcs[0]
+        .checked_mul(40)
+        .and_then(|n| n.checked_add(arcs[1]))
+        .ok_or_else(invalid)?;
+
+    let mut out = Vec::new();
+    for arc in [first].into_iter().chain(arcs[2..].iter().copied()) {
+        let groups = (64 - arc.leading_zeros()).div_ceil(7).max(1);
+        for i in (0..groups).rev() {
+            let more = if i > 0 { 0x80 } else { 0 };
+            out.push(more | (arc >> (7 * i) & 0x7F) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// dotted form of the OID with DER content octets `der`.
+pub fn decode(der: &[u8]) -> Result<String, Error> {
+    if der.is_empty() || der[der.len() - 1] & 0x80 != 0 {
+        return Err(Error::InvalidDer);
+    }
+    let mut arcs = Vec::new();
+    let mut arc: u64 = 0;
+    let mut start = true;
+    for &b in der {
+        if start && b == 0x80 {
+            return Err(Error::InvalidDer);
+        }
+        if arc >> 57 != 0 {
+            return Err(Error::InvalidDer);
+        }
+        arc = arc << 7 | (b & 0x7F) as u64;
+        start = b & 0x80 == 0;
+        if start {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+
+    let first = arcs[0];
+    let (a, b) = match first {
+        0..=39 => (0, first),
+        40..=79 => (1, first - 40),
+        _ => (2, first - 80),
+    };
+    let mut s = format!("{}.{}", a, b);
+    for arc in &arcs[1..] {
+        s.push_str(&format!(".{}", arc));
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! encode_test {
+        ($name:ident,$s:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = encode($s);
+                let expected: Result<Vec<u8>, Error> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+                if let Ok(der) = actual {
+                    assert_eq!(decode(&der).as_deref(), Ok(dotted($s).unwrap_or($s)));
+                }
+            }
+        };
+    }
+
+    encode_test!(
+        sha256,
+        "2.16.840.1.101.3.4.2.1",
+        Ok(vec![0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01])
+    );
+    encode_test!(
+        by_name,
+        "SHA256WithRSAEncryption",
+        Ok(vec![0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B])
+    );
+    encode_test!(
+        curve,
+        "1.3.132.0.10",
+        Ok(vec![0x2B, 0x81, 0x04, 0x00, 0x0A])
+    );
+    encode_test!(large_first_arc, "2.999.3", Ok(vec![0x88, 0x37, 0x03]));
+    encode_test!(one_arc, "1", Err(Error::InvalidDotted("1".into())));
+    encode_test!(second_arc, "1.40", Err(Error::InvalidDotted("1.40".into())));
+    encode_test!(
+        not_number,
+        "1.2.x",
+        Err(Error::InvalidDotted("1.2.x".into()))
+    );
+
+    #[test]
+    fn table_round_trip() {
+        for (d, n) in TABLE {
+            let der = encode(d).unwrap();
+            assert_eq!(decode(&der).as_deref(), Ok(*d));
+            assert_eq!(describe(&der).as_deref(), Ok(*n));
+            assert_eq!(dotted(n), Some(*d));
+        }
+    }
+
+    #[test]
+    fn invalid_der() {
+        for der in [&[][..], &[0x2A, 0x86], &[0x2A, 0x80, 0x01]] {
+            assert_eq!(decode(der), Err(Error::InvalidDer));
+        }
+        assert_eq!(describe(&[0x2A, 0x03]).as_deref(), Ok("1.2.3"));
+    }
+}
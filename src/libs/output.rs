@@ -0,0 +1,197 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path;
+use std::process;
+
+/// path which means standard output.
+pub const STDOUT_PATH: &str = "-";
+
+/// permissions of created files (before umask).
+pub const DEFAULT_MODE: u32 = 0o666;
+/// permissions of files with private keys, derived keys or decrypted data.
+pub const SECRET_MODE: u32 = 0o600;
+
+pub enum Output<'a> {
+    File(AtomicFile),
+    Stdout(io::StdoutLock<'a>),
+}
+
+impl<'a> Output<'a> {
+    pub fn new(file: &path::Path) -> io::Result<Output<'a>> {
+        Output::with_mode(file, DEFAULT_MODE)
+    }
+
+    /// output which creates a file with `mode` permissions (unix only).
+    pub fn with_mode(file: &path::Path, mode: u32) -> io::Result<Output<'a>> {
+        if file.as_os_str() == STDOUT_PATH {
+            return Ok(Output::Stdout(io::stdout().lock()));
+        }
+        Ok(Output::File(AtomicFile::with_mode(file, mode)?))
+    }
+
+    /// finish writing. a file output appears at its path only now.
+    pub fn commit(self) -> io::Result<()> {
+        match self {
+            Output::File(file) => file.commit(),
+            Output::Stdout(mut stdout) => stdout.flush(),
+        }
+    }
+}
+
+impl<'a> Write for Output<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Output::File(ref mut file) => file.write(buf),
+            Output::Stdout(ref mut stdout) => stdout.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Output::File(ref mut file) => file.flush(),
+            Output::Stdout(ref mut stdout) => stdout.flush(),
+        }
+    }
+}
+
+/// file which is written to a temporary file beside it
+/// and renamed into its path on commit, so an interrupted write
+/// never leaves a truncated file. not committed temporary file is removed.
+pub struct AtomicFile {
+    path: path::PathBuf,
+    tmp_path: path::PathBuf,
+    file: Option<io::BufWriter<fs::File>>,
+}
+
+impl AtomicFile {
+    pub fn new(path: &path::Path) -> io::Result<AtomicFile> {
+        AtomicFile::with_mode(path, DEFAULT_MODE)
+    }
+
+    /// the temporary file is created with `mode` permissions (unix only),
+    /// so the data is never readable with wider permissions.
+    pub fn with_mode(path: &path::Path, mode: u32) -> io::Result<AtomicFile> {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "output path has no file name")
+        })?;
+
+        let mut tmp_name = OsString::from(".");
+        tmp_name.push(name);
+        tmp_name.push(format!(".{}.tmp", process::id()));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+        #[cfg(not(unix))]
+        let _ = mode;
+        let file = options.open(&tmp_path)?;
+
+        Ok(AtomicFile {
+            path: path.to_path_buf(),
+            tmp_path,
+            file: Some(io::BufWriter::new(file)),
+        })
+    }
+
+    pub fn commit(self) -> io::Result<()> {
+        let path = self.path.clone();
+        self.commit_as(&path)
+    }
+
+    /// commits into `path` instead of the path the file was created for.
+    /// `path` must be on the same file system, as the file is renamed.
+    pub fn commit_as(mut self, path: &path::Path) -> io::Result<()> {
+        let file = self.file.take().expect("file must be present");
+
+        let result = file
+            .into_inner()
+            .map_err(|err| err.into_error())
+            .and_then(|file| file.sync_all())
+            .and_then(|_| fs::rename(&self.tmp_path, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+        result
+    }
+}
+
+/// parse octal file permissions, like `600`.
+pub fn parse_mode(s: &str) -> Result<u32, String> {
+    match u32::from_str_radix(s, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("invalid octal file mode: {}", s)),
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.as_mut().expect("file must be present").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().expect("file must be present").flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn test_path(name: &str) -> path::PathBuf {
+        let path = env::temp_dir().join(format!("ssl-output-{}-{}", process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn atomic_file_commit() {
+        let path = test_path("commit");
+        let mut file = AtomicFile::new(&path).unwrap();
+
+        file.write_all(b"data").unwrap();
+        assert!(!path.exists());
+
+        file.commit().unwrap();
+        assert_eq!("data", fs::read_to_string(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = test_path("mode");
+        let file = AtomicFile::with_mode(&path, SECRET_MODE).unwrap();
+
+        file.commit().unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(SECRET_MODE, mode & 0o777);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn atomic_file_drop() {
+        let path = test_path("drop");
+        let mut file = AtomicFile::new(&path).unwrap();
+        let tmp_path = file.tmp_path.clone();
+
+        file.write_all(b"data").unwrap();
+        drop(file);
+
+        assert!(!path.exists());
+        assert!(!tmp_path.exists());
+    }
+}
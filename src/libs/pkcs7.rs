@@ -0,0 +1,136 @@
+use crate::libs::der::{self, Sequence};
+use crate::libs::oid;
+
+/// names of the content types in the oid registry.
+const DATA: &str = "pkcs7-data";
+const SIGNED_DATA: &str = "pkcs7-signedData";
+
+pub const PEM_LABEL: &str = "PKCS7";
+
+/// DER content octets of the registered OID `name`.
+fn oid(name: &str) -> Vec<u8> {
+    oid::encode(name).expect("pkcs7 OIDs are registered")
+}
+
+/// certificates and CRLs of a "certs-only" SignedData, as DER.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Bundle<'a> {
+    pub certificates: Vec<&'a [u8]>,
+    pub crls: Vec<&'a [u8]>,
+}
+
+/// SignedData which has no content and no signers (RFC 2315 degenerate
+/// case). certificates and CRLs are copied as is, in the given order.
+impl<'a> der::Sequence<'a> for Bundle<'a> {
+    fn write_fields(&self, out: &mut Vec<u8>) {
+        der::write(out, der::TAG_INTEGER, &[1]);
+        der::write(out, der::TAG_SET, &[]);
+        der::write_with(out, der::TAG_SEQUENCE, |out| {
+            der::write(out, der::TAG_OID, &oid(DATA))
+        });
+        if !self.certificates.is_empty() {
+            der::write(out, der::context(0), &self.certificates.concat());
+        }
+        if !self.crls.is_empty() {
+            der::write(out, der::context(1), &self.crls.concat());
+        }
+        der::write(out, der::TAG_SET, &[]);
+    }
+
+    fn read_fields(r: &mut der::Reader<'a>) -> der::Result<Self> {
+        r.expect(der::TAG_INTEGER)?;
+        r.expect(der::TAG_SET)?;
+        r.expect(der::TAG_SEQUENCE)?;
+
+        let mut bundle = Bundle::default();
+        for (n, list) in [(0, &mut bundle.certificates), (1, &mut bundle.crls)] {
+            if r.peek_tag() != Some(der::context(n)) {
+                continue;
+            }
+            let mut set = der::Reader::new(r.read()?.content);
+            while !set.is_empty() {
+                list.push(set.read()?.raw);
+            }
+        }
+        Ok(bundle)
+    }
+}
+
+/// ContentInfo holding SignedData.
+struct ContentInfo<'a>(Bundle<'a>);
+
+impl<'a> der::Sequence<'a> for ContentInfo<'a> {
+    fn write_fields(&self, out: &mut Vec<u8>) {
+        der::write(out, der::TAG_OID, &oid(SIGNED_DATA));
+        der::write_with(out, der::context(0), |out| self.0.write(out));
+    }
+
+    fn read_fields(r: &mut der::Reader<'a>) -> der::Result<Self> {
+        let content_type = r.expect(der::TAG_OID)?;
+        if content_type.content != oid(SIGNED_DATA) {
+            return Err(der::Error::Unsupported);
+        }
+        let mut explicit = der::Reader::new(r.expect(der::context(0))?.content);
+        Ok(ContentInfo(Bundle::read(&mut explicit)?))
+    }
+}
+
+/// encode `bundle` as ContentInfo with certs-only SignedData.
+pub fn encode(bundle: &Bundle) -> Vec<u8> {
+    ContentInfo(bundle.clone()).to_der()
+}
+
+/// certificates and CRLs of PKCS#7 SignedData. signatures are not checked.
+pub fn decode(data: &[u8]) -> der::Result<Bundle<'_>> {
+    ContentInfo::from_der(data).map(|info| info.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let cert1 = der::encode(der::TAG_SEQUENCE, &[1, 2, 3]);
+        let cert2 = der::encode(der::TAG_SEQUENCE, &[4; 300]);
+        let crl = der::encode(der::TAG_SEQUENCE, &[5]);
+
+        for bundle in [
+            Bundle::default(),
+            Bundle {
+                certificates: vec![&cert1, &cert2],
+                crls: vec![],
+            },
+            Bundle {
+                certificates: vec![&cert1],
+                crls: vec![&crl],
+            },
+        ] {
+            let data = encode(&bundle);
+            assert_eq!(decode(&data), Ok(bundle));
+        }
+    }
+
+    #[test]
+    fn openssl_certs_only() {
+        // `openssl crl2pkcs7 -nocrl -outform DER` without certificates.
+        let data = [
+            0x30, 0x23, 0x06, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02, 0xA0,
+            0x16, 0x30, 0x14, 0x02, 0x01, 0x01, 0x31, 0x00, 0x30, 0x0B, 0x06, 0x09, 0x2A, 0x86,
+            0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x01, 0x31, 0x00,
+        ];
+        assert_eq!(encode(&Bundle::default()), data);
+    }
+
+    #[test]
+    fn registered_oids() {
+        assert_eq!(oid::dotted(DATA), Some("1.2.840.113549.1.7.1"));
+        assert_eq!(oid::dotted(SIGNED_DATA), Some("1.2.840.113549.1.7.2"));
+    }
+
+    #[test]
+    fn not_signed_data() {
+        let data = der::encode(der::TAG_SEQUENCE, &der::encode(der::TAG_OID, &oid(DATA)));
+        assert_eq!(decode(&data), Err(der::Error::Unsupported));
+    }
+}
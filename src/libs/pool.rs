@@ -0,0 +1,197 @@
+//! bounded pool of scoped worker threads for batch work, used by hash
+//! and merkle. workers claim the next item through a shared atomic index
+//! (there is no work stealing), so slow items do not hold back the others.
+//! long-lived threads, like the metrics server, are spawned on their own.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::num::NonZeroUsize;
+use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// number of workers when not configured: the available parallelism.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// cpus to run workers on, written like `0-7,16`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuList(pub Vec<usize>);
+
+impl fmt::Display for CpuList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cpus: Vec<String> = self.0.iter().map(|cpu| cpu.to_string()).collect();
+        write!(f, "{}", cpus.join(","))
+    }
+}
+
+impl str::FromStr for CpuList {
+    type Err = String;
+
+    /// comma separated cpus and inclusive ranges, as in /sys cpulist files.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cpu = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid cpu {:?}", s))
+        };
+        let mut cpus = Vec::new();
+        for part in s.split(',') {
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let (first, last) = (cpu(first)?, cpu(last)?);
+                    if first > last {
+                        return Err(format!("invalid cpu range {:?}", part));
+                    }
+                    cpus.extend(first..=last);
+                }
+                None => cpus.push(cpu(part)?),
+            }
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(CpuList(cpus))
+    }
+}
+
+/// pins the calling thread to `cpu`.
+#[cfg(target_os = "linux")]
+fn pin(cpu: usize) -> io::Result<()> {
+    // SAFETY: cpu_set_t is plain data, zeroed is the empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    if cpu >= 8 * std::mem::size_of::<libc::cpu_set_t>() {
+        return Err(io::Error::from_raw_os_error(libc::EINVAL));
+    }
+    // SAFETY: `cpu` is within the set, checked above.
+    unsafe { libc::CPU_SET(cpu, &mut set) };
+    // SAFETY: the set is valid for its size; 0 is the calling thread.
+    match unsafe { libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// affinity is only a hint for throughput, so elsewhere threads run anywhere.
+#[cfg(not(target_os = "linux"))]
+fn pin(_cpu: usize) -> io::Result<()> {
+    Ok(())
+}
+
+/// calls `f` for every item on at most `jobs` threads and passes the
+/// results to `sink` in the order of `items`, as soon as all earlier
+/// results are passed. with one job no thread is spawned.
+pub fn ordered_map<T, R, F, S>(jobs: usize, items: &[T], f: F, sink: S)
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    S: FnMut(R),
+{
+    ordered_map_on(jobs, &[], items, f, sink)
+}
+
+/// like `ordered_map`, but worker n is pinned to cpu `cpus[n % cpus.len()]`
+/// (on linux), so the workers stay on the cores, and memory, of one socket.
+pub fn ordered_map_on<T, R, F, S>(jobs: usize, cpus: &[usize], items: &[T], f: F, mut sink: S)
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    S: FnMut(R),
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 && cpus.is_empty() {
+        items.iter().map(f).for_each(sink);
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    // bounded, so workers wait for a slow sink instead of buffering.
+    let (tx, rx) = mpsc::sync_channel(jobs);
+    thread::scope(|scope| {
+        for n in 0..jobs {
+            let tx = tx.clone();
+            let (next, f) = (&next, &f);
+            let cpu = cpus.get(n % cpus.len().max(1)).copied();
+            scope.spawn(move || {
+                if let Some(cpu) = cpu {
+                    if let Err(err) = pin(cpu) {
+                        eprintln!("pin worker to cpu {}: {}", cpu, err);
+                    }
+                }
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= items.len() || tx.send((i, f(&items[i]))).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let mut pending = BTreeMap::new();
+        let mut want = 0;
+        for (i, result) in rx {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&want) {
+                sink(result);
+                want += 1;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    macro_rules! ordered_map_test {
+        ($name:ident,$jobs:expr,$len:expr) => {
+            #[test]
+            fn $name() {
+                let items: Vec<u64> = (0..$len).collect();
+                let mut actual = Vec::new();
+                ordered_map(
+                    $jobs,
+                    &items,
+                    |&i| {
+                        // later items finish first.
+                        thread::sleep(Duration::from_micros(($len - i) * 10));
+                        i * i
+                    },
+                    |r| actual.push(r),
+                );
+                let expected: Vec<u64> = items.iter().map(|i| i * i).collect();
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    #[test]
+    fn pinned() {
+        let items: Vec<u64> = (0..20).collect();
+        let mut actual = Vec::new();
+        ordered_map_on(2, &[0], &items, |&i| i + 1, |r| actual.push(r));
+        let expected: Vec<u64> = (1..21).collect();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn cpu_list() {
+        assert_eq!(Ok(CpuList(vec![0, 1, 2, 3, 8])), "8,0-3,2".parse());
+        assert_eq!("0,1,2", "0-2".parse::<CpuList>().unwrap().to_string());
+        assert!("3-1".parse::<CpuList>().is_err());
+        assert!("a".parse::<CpuList>().is_err());
+        assert!("".parse::<CpuList>().is_err());
+    }
+
+    ordered_map_test!(one_job, 1, 10);
+    ordered_map_test!(four_jobs, 4, 100);
+    ordered_map_test!(more_jobs_than_items, 16, 3);
+    ordered_map_test!(no_items, 4, 0);
+    ordered_map_test!(zero_jobs, 0, 5);
+}
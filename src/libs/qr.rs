@@ -0,0 +1,598 @@
+//! QR code (ISO/IEC 18004) encoder: byte mode, error correction level M,
+//! versions 1 to 40.
+
+use std::error;
+use std::fmt;
+
+const MIN_VERSION: usize = 1;
+const MAX_VERSION: usize = 40;
+const QUIET_ZONE: usize = 4;
+
+// error correction level M, indexed by version.
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION + 1] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+];
+const NUM_ECC_BLOCKS: [usize; MAX_VERSION + 1] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23,
+    25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+];
+// format bits of error correction level M.
+const ECC_FORMAT_BITS: u32 = 0;
+
+const MODE_BYTE: u32 = 0x4;
+
+const PENALTY_N1: i32 = 3;
+const PENALTY_N2: i32 = 3;
+const PENALTY_N3: i32 = 40;
+const PENALTY_N4: i32 = 10;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// data length in bytes does not fit in the largest version.
+    TooLong(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::TooLong(len) => write!(f, "{} bytes do not fit in a QR code", len),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl QrCode {
+    /// encode `data` in the smallest version which holds it.
+    pub fn encode(data: &[u8]) -> Result<QrCode, Error> {
+        Self::encode_with_mask(data, None)
+    }
+
+    fn encode_with_mask(data: &[u8], mask: Option<u8>) -> Result<QrCode, Error> {
+        let version = (MIN_VERSION..=MAX_VERSION)
+            .find(|&v| segment_bits(v, data.len()) <= data_codewords(v) * 8)
+            .ok_or(Error::TooLong(data.len()))?;
+
+        let size = version * 4 + 17;
+        let mut qr = QrCode {
+            version,
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        };
+        qr.draw_function_patterns();
+        qr.draw_codewords(&add_ecc_and_interleave(version, &codewords(version, data)));
+
+        let mask = mask.unwrap_or_else(|| {
+            (0..8)
+                .min_by_key(|&m| {
+                    qr.apply_mask(m);
+                    qr.draw_format_bits(m);
+                    let penalty = qr.penalty();
+                    qr.apply_mask(m);
+                    penalty
+                })
+                .unwrap()
+        });
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+        Ok(qr)
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// side length in modules.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// true for a dark module at column `x` and row `y`.
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// render with half block characters, two rows per line, with quiet zone.
+    /// light modules are drawn, so the code reads on dark terminal backgrounds.
+    pub fn to_terminal(&self) -> String {
+        let side = self.size + 2 * QUIET_ZONE;
+        let light = |x: usize, y: usize| {
+            x < QUIET_ZONE
+                || y < QUIET_ZONE
+                || x >= self.size + QUIET_ZONE
+                || y >= self.size + QUIET_ZONE
+                || !self.module(x - QUIET_ZONE, y - QUIET_ZONE)
+        };
+
+        let mut s = String::new();
+        for y in (0..side).step_by(2) {
+            for x in 0..side {
+                let bottom = y + 1 < side && light(x, y + 1);
+                s.push(match (light(x, y), bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        self.draw_finder(3, 3);
+        self.draw_finder(size - 4, 3);
+        self.draw_finder(3, size - 4);
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // overlaps with finder patterns.
+                if (i == 0 && (j == 0 || j == last)) || (i == last && j == 0) {
+                    continue;
+                }
+                self.draw_alignment(x, y);
+            }
+        }
+
+        // reserve format area before codewords are drawn.
+        self.draw_format_bits(0);
+        self.draw_version_bits();
+    }
+
+    // finder pattern with separator, centered at (x, y).
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                    let dist = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, dist != 2 && dist != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in 0..5 {
+            for dx in 0..5 {
+                let dist = (dx as i32 - 2).abs().max((dy as i32 - 2).abs());
+                self.set_function(x + dx - 2, y + dy - 2, dist != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u8) {
+        let data = ECC_FORMAT_BITS << 3 | mask as u32;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+        let size = self.size;
+
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        // always dark.
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version_bits(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let mut rem = self.version as u32;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = (self.version as u32) << 12 | rem;
+
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    // codewords go in 2 modules wide columns, zigzagging up and down from
+    // the bottom right corner and skipping the vertical timing pattern.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for x in [right, right - 1] {
+                    if !self.function[y * size + x] && i < codewords.len() * 8 {
+                        self.modules[y * size + x] = (codewords[i / 8] >> (7 - i % 8)) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    // masking twice restores the modules.
+    fn apply_mask(&mut self, mask: u8) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let i = y * self.size + x;
+                self.modules[i] ^= invert && !self.function[i];
+            }
+        }
+    }
+
+    fn penalty(&self) -> i32 {
+        let size = self.size;
+        let mut result = 0;
+
+        // runs of same color and finder-like patterns in rows and columns.
+        for transposed in [false, true] {
+            for a in 0..size {
+                let module = |b: usize| {
+                    if transposed {
+                        self.module(a, b)
+                    } else {
+                        self.module(b, a)
+                    }
+                };
+                let mut history = RunHistory::new(size);
+                let mut color = false;
+                let mut run = 0;
+                for b in 0..size {
+                    if module(b) == color {
+                        run += 1;
+                        if run == 5 {
+                            result += PENALTY_N1;
+                        } else if run > 5 {
+                            result += 1;
+                        }
+                    } else {
+                        history.add(run);
+                        if !color {
+                            result += history.finder_like() * PENALTY_N3;
+                        }
+                        color = module(b);
+                        run = 1;
+                    }
+                }
+                result += history.terminate(color, run) * PENALTY_N3;
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let c = self.module(x, y);
+                if c == self.module(x + 1, y)
+                    && c == self.module(x, y + 1)
+                    && c == self.module(x + 1, y + 1)
+                {
+                    result += PENALTY_N2;
+                }
+            }
+        }
+
+        let dark = self.modules.iter().filter(|&&m| m).count() as i32;
+        let total = (size * size) as i32;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        result + k * PENALTY_N4
+    }
+}
+
+// lengths of the last 7 runs in a row or column, newest first.
+// the area outside the symbol counts as light.
+struct RunHistory {
+    size: i32,
+    runs: [i32; 7],
+}
+
+impl RunHistory {
+    fn new(size: usize) -> Self {
+        RunHistory {
+            size: size as i32,
+            runs: [0; 7],
+        }
+    }
+
+    fn add(&mut self, mut run: i32) {
+        if self.runs[0] == 0 {
+            run += self.size;
+        }
+        self.runs.copy_within(0..6, 1);
+        self.runs[0] = run;
+    }
+
+    // number of 1:1:3:1:1 dark-light patterns with 4 light modules on a side.
+    fn finder_like(&self) -> i32 {
+        let r = &self.runs;
+        let n = r[1];
+        let core = n > 0 && r[2] == n && r[3] == n * 3 && r[4] == n && r[5] == n;
+        (core && r[0] >= n * 4 && r[6] >= n) as i32 + (core && r[6] >= n * 4 && r[0] >= n) as i32
+    }
+
+    fn terminate(mut self, dark: bool, mut run: i32) -> i32 {
+        if dark {
+            self.add(run);
+            run = 0;
+        }
+        self.add(run + self.size);
+        self.finder_like()
+    }
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let mut positions: Vec<usize> = (0..count - 1)
+        .map(|i| version * 4 + 10 - i * step)
+        .collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+fn raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let count = version / 7 + 2;
+        result -= (25 * count - 10) * count - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[version] * NUM_ECC_BLOCKS[version]
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version < 10 {
+        8
+    } else {
+        16
+    }
+}
+
+fn segment_bits(version: usize, len: usize) -> usize {
+    if len >> char_count_bits(version) != 0 {
+        return usize::MAX;
+    }
+    4 + char_count_bits(version) + len * 8
+}
+
+struct BitBuffer(Vec<bool>);
+
+impl BitBuffer {
+    fn append(&mut self, value: u32, len: usize) {
+        self.0.extend((0..len).rev().map(|i| (value >> i) & 1 != 0));
+    }
+}
+
+// byte mode segment with terminator and padding.
+fn codewords(version: usize, data: &[u8]) -> Vec<u8> {
+    let capacity = data_codewords(version) * 8;
+    let mut bits = BitBuffer(Vec::new());
+    bits.append(MODE_BYTE, 4);
+    bits.append(data.len() as u32, char_count_bits(version));
+    for &b in data {
+        bits.append(b as u32, 8);
+    }
+    bits.append(0, 4.min(capacity - bits.0.len()));
+    bits.append(0, (8 - bits.0.len() % 8) % 8);
+
+    let mut codewords: Vec<u8> = bits
+        .0
+        .chunks(8)
+        .map(|c| c.iter().fold(0, |b, &bit| b << 1 | bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() * 8 >= capacity {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+// split data into blocks, append Reed-Solomon codewords to every block and
+// interleave them. the first blocks are one data codeword shorter.
+fn add_ecc_and_interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks_count = NUM_ECC_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks_count - raw_codewords % blocks_count;
+    let short_len = raw_codewords / blocks_count - ecc_len;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut blocks = Vec::with_capacity(blocks_count);
+    let mut rest = data;
+    for i in 0..blocks_count {
+        let len = short_len + (i >= short_blocks) as usize;
+        let (block, tail) = rest.split_at(len);
+        rest = tail;
+        blocks.push((block, reed_solomon_remainder(block, &divisor)));
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..=short_len {
+        for (block, _) in &blocks {
+            if let Some(&b) = block.get(i) {
+                result.push(b);
+            }
+        }
+    }
+    for i in 0..ecc_len {
+        for (_, ecc) in &blocks {
+            result.push(ecc[i]);
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &b in data {
+        let factor = b ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    result
+}
+
+// multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u8 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1D);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use qrcodegen::{QrCode as Reference, QrCodeEcc, QrSegment, Version};
+
+    fn reference(data: &[u8], mask: Option<u8>) -> Reference {
+        Reference::encode_segments_advanced(
+            &[QrSegment::make_bytes(data)],
+            QrCodeEcc::Medium,
+            Version::new(MIN_VERSION as u8),
+            Version::new(MAX_VERSION as u8),
+            mask.map(qrcodegen::Mask::new),
+            false,
+        )
+        .unwrap()
+    }
+
+    fn assert_same(actual: &QrCode, expected: &Reference) {
+        assert_eq!(actual.size() as i32, expected.size());
+        for y in 0..actual.size() {
+            for x in 0..actual.size() {
+                assert_eq!(
+                    actual.module(x, y),
+                    expected.get_module(x as i32, y as i32),
+                    "module ({}, {}) of version {}",
+                    x,
+                    y,
+                    actual.version()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn versions() {
+        // the largest input of every version.
+        for version in MIN_VERSION..=MAX_VERSION {
+            let len = (data_codewords(version) * 8 - 4 - char_count_bits(version)) / 8;
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let actual = QrCode::encode(&data).unwrap();
+            assert_eq!(actual.version(), version);
+            assert_same(&actual, &reference(&data, None));
+        }
+    }
+
+    #[test]
+    fn too_long() {
+        let data = vec![0; 2332];
+        assert_eq!(QrCode::encode(&data).err(), Some(Error::TooLong(2332)));
+    }
+
+    proptest! {
+        #[test]
+        fn reference_masks(data: Vec<u8>, mask in 0..8u8) {
+            let actual = QrCode::encode_with_mask(&data, Some(mask)).unwrap();
+            assert_same(&actual, &reference(&data, Some(mask)));
+        }
+
+        #[test]
+        fn reference_auto_mask(data in proptest::collection::vec(any::<u8>(), 0..300)) {
+            let actual = QrCode::encode(&data).unwrap();
+            assert_same(&actual, &reference(&data, None));
+        }
+    }
+}
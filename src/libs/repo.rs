@@ -0,0 +1,453 @@
+//! checksum lists of package repository metadata: Debian `Release` /
+//! `InRelease` and `Packages` (deb822 stanzas) and rpm `repomd.xml`.
+//! signatures are not verified.
+
+use std::error;
+use std::fmt;
+
+use crate::libs::hash::Func;
+
+const SIGNED_BEGIN: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+const SIGNATURE_BEGIN: &str = "-----BEGIN PGP SIGNATURE-----";
+
+/// checksum fields from the strongest supported one.
+const RELEASE_FIELDS: [(&str, Func); 2] = [("SHA256", Func::SHA256), ("MD5Sum", Func::MD5)];
+const PACKAGES_FIELDS: [(&str, Func); 2] = [("SHA256", Func::SHA256), ("MD5sum", Func::MD5)];
+
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    /// path relative to the directory of the metadata (Release) or to the
+    /// repository root (Packages, repomd.xml).
+    pub path: String,
+    pub size: Option<u64>,
+    pub func: Func,
+    /// lowercase hex digest.
+    pub digest: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Line { number: usize, reason: &'static str },
+    NoChecksums,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Line { number, reason } => write!(f, "line {}: {}", number, reason),
+            Error::NoChecksums => write!(f, "no supported checksums"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn line_error(number: usize, reason: &'static str) -> Error {
+    Error::Line { number, reason }
+}
+
+/// signed text of an OpenPGP cleartext signed message (as InRelease),
+/// or `text` itself when it is not signed.
+pub fn cleartext(text: &str) -> String {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim_end) != Some(SIGNED_BEGIN) {
+        return text.to_string();
+    }
+    // armor headers up to the first empty line.
+    for line in lines.by_ref() {
+        if line.trim_end().is_empty() {
+            break;
+        }
+    }
+    let mut out = String::new();
+    for line in lines {
+        if line.trim_end() == SIGNATURE_BEGIN {
+            break;
+        }
+        out.push_str(line.strip_prefix("- ").unwrap_or(line));
+        out.push('\n');
+    }
+    out
+}
+
+/// field of a deb822 stanza; continuation lines are joined with `\n`.
+struct Field<'a> {
+    name: &'a str,
+    value: String,
+    line: usize,
+}
+
+fn stanzas(text: &str) -> Result<Vec<Vec<Field<'_>>>> {
+    let mut stanzas = Vec::new();
+    let mut stanza: Vec<Field> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        if line.trim().is_empty() {
+            if !stanza.is_empty() {
+                stanzas.push(std::mem::take(&mut stanza));
+            }
+        } else if line.starts_with('#') {
+            continue;
+        } else if line.starts_with([' ', '\t']) {
+            let field = stanza
+                .last_mut()
+                .ok_or_else(|| line_error(number, "continuation line without field"))?;
+            field.value.push('\n');
+            field.value.push_str(line.trim());
+        } else {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| line_error(number, "expected field"))?;
+            stanza.push(Field {
+                name,
+                value: value.trim().to_string(),
+                line: number,
+            });
+        }
+    }
+    if !stanza.is_empty() {
+        stanzas.push(stanza);
+    }
+    Ok(stanzas)
+}
+
+fn find<'a, 'b>(stanza: &'a [Field<'b>], name: &str) -> Option<&'a Field<'b>> {
+    stanza.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+}
+
+fn hex_digest(digest: &str, func: Func) -> Option<String> {
+    let len = match func {
+        Func::MD5 => 32,
+        Func::SHA256 => 64,
+    };
+    (digest.len() == len && digest.bytes().all(|c| c.is_ascii_hexdigit()))
+        .then(|| digest.to_ascii_lowercase())
+}
+
+/// files listed by the strongest checksum field of a Release file.
+pub fn release(text: &str) -> Result<Vec<Entry>> {
+    let text = cleartext(text);
+    let stanzas = stanzas(&text)?;
+    let stanza = stanzas.first().ok_or(Error::NoChecksums)?;
+    let (field, func) = RELEASE_FIELDS
+        .iter()
+        .find_map(|&(name, func)| find(stanza, name).map(|f| (f, func)))
+        .ok_or(Error::NoChecksums)?;
+
+    let mut entries = Vec::new();
+    for (i, line) in field.value.lines().enumerate().skip(1) {
+        let number = field.line + i;
+        let mut words = line.split_whitespace();
+        let (digest, size, path) = match (words.next(), words.next(), words.next(), words.next()) {
+            (Some(digest), Some(size), Some(path), None) => (digest, size, path),
+            _ => return Err(line_error(number, "expected digest, size and path")),
+        };
+        entries.push(Entry {
+            path: path.to_string(),
+            size: Some(
+                size.parse()
+                    .map_err(|_| line_error(number, "invalid size"))?,
+            ),
+            func,
+            digest: hex_digest(digest, func).ok_or_else(|| line_error(number, "invalid digest"))?,
+        });
+    }
+    Ok(entries)
+}
+
+/// `Filename` of every package stanza with its strongest checksum.
+pub fn packages(text: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for stanza in stanzas(text)? {
+        let filename = match find(&stanza, "Filename") {
+            Some(f) => f,
+            None => continue,
+        };
+        let (field, func) = PACKAGES_FIELDS
+            .iter()
+            .find_map(|&(name, func)| find(&stanza, name).map(|f| (f, func)))
+            .ok_or_else(|| line_error(filename.line, "package without supported checksum"))?;
+        let size = match find(&stanza, "Size") {
+            Some(f) => Some(
+                f.value
+                    .parse()
+                    .map_err(|_| line_error(f.line, "invalid size"))?,
+            ),
+            None => None,
+        };
+        entries.push(Entry {
+            path: filename.value.clone(),
+            size,
+            func,
+            digest: hex_digest(&field.value, func)
+                .ok_or_else(|| line_error(field.line, "invalid digest"))?,
+        });
+    }
+    Ok(entries)
+}
+
+/// text of the first `<name ...>...</name>` element of `xml`, and the
+/// attributes of its start tag.
+fn element<'a>(xml: &'a str, name: &str) -> Option<(&'a str, &'a str)> {
+    let open = format!("<{}", name);
+    let mut from = 0;
+    let start = loop {
+        let pos = from + xml[from..].find(&open)?;
+        let next = xml[pos + open.len()..].chars().next()?;
+        if next == '>' || next == '/' || next.is_whitespace() {
+            break pos + open.len();
+        }
+        from = pos + open.len();
+    };
+    let tag_end = start + xml[start..].find('>')?;
+    let attrs = &xml[start..tag_end];
+    if let Some(attrs) = attrs.strip_suffix('/') {
+        return Some((attrs, ""));
+    }
+    let close = format!("</{}>", name);
+    let end = tag_end + 1 + xml[tag_end + 1..].find(&close)?;
+    Some((attrs, &xml[tag_end + 1..end]))
+}
+
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attrs;
+    loop {
+        let eq = rest.find('=')?;
+        let key = rest[..eq].trim();
+        let value = rest[eq + 1..].trim_start();
+        let quote = value.chars().next().filter(|&q| q == '"' || q == '\'')?;
+        let end = value[1..].find(quote)? + 1;
+        if key == name {
+            return Some(&value[1..end]);
+        }
+        rest = &value[end + 1..];
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// files of the `<data>` elements of a repomd.xml with `md5` or `sha256`
+/// checksums; others are skipped.
+pub fn repomd(xml: &str) -> Result<Vec<Entry>> {
+    let line = |pos: usize| xml[..pos].matches('\n').count() + 1;
+    let mut entries = Vec::new();
+    let mut rest = xml;
+    while let Some((_, data)) = element(rest, "data") {
+        let pos = data.as_ptr() as usize - xml.as_ptr() as usize;
+        rest = &xml[pos + data.len()..];
+
+        let (attrs, checksum) = element(data, "checksum")
+            .ok_or_else(|| line_error(line(pos), "data without checksum"))?;
+        let func = match attribute(attrs, "type") {
+            Some("sha256") => Func::SHA256,
+            Some("md5") => Func::MD5,
+            _ => continue,
+        };
+        let path = element(data, "location")
+            .and_then(|(attrs, _)| attribute(attrs, "href"))
+            .ok_or_else(|| line_error(line(pos), "data without location"))?;
+        let size = match element(data, "size") {
+            Some((_, size)) => Some(
+                size.trim()
+                    .parse()
+                    .map_err(|_| line_error(line(pos), "invalid size"))?,
+            ),
+            None => None,
+        };
+        entries.push(Entry {
+            path: unescape(path),
+            size,
+            func,
+            digest: hex_digest(checksum.trim(), func)
+                .ok_or_else(|| line_error(line(pos), "invalid digest"))?,
+        });
+    }
+    if entries.is_empty() {
+        return Err(Error::NoChecksums);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD5_A: &str = "0cc175b9c0f1b6a831c399e269772661";
+    const SHA256_A: &str = "ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9803adba8bb3ead";
+
+    fn entry(path: &str, size: Option<u64>, func: Func, digest: &str) -> Entry {
+        Entry {
+            path: path.to_string(),
+            size,
+            func,
+            digest: digest.to_string(),
+        }
+    }
+
+    macro_rules! repo_test {
+        ($name:ident,$parse:expr,$input:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = $parse(&$input);
+                let expected = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    repo_test!(
+        release_sha256,
+        release,
+        format!(
+            "Origin: Debian\nDate: Sat, 01 Jan 2000 00:00:00 UTC\nMD5Sum:\n {md5} 1 main/a\nSHA256:\n {sha} 1 main/a\n {sha}   1 main/b\n",
+            md5 = MD5_A,
+            sha = SHA256_A.to_uppercase()
+        ),
+        Ok(vec![
+            entry("main/a", Some(1), Func::SHA256, SHA256_A),
+            entry("main/b", Some(1), Func::SHA256, SHA256_A),
+        ])
+    );
+    repo_test!(
+        release_md5,
+        release,
+        format!(
+            "Suite: old\nMD5Sum:\n {} 1 a\nSHA1:\n 86f7e437faa5a7fce15d1ddcb9eaeaea377667b8 1 a\n",
+            MD5_A
+        ),
+        Ok(vec![entry("a", Some(1), Func::MD5, MD5_A)])
+    );
+    repo_test!(
+        in_release,
+        release,
+        format!(
+            "{}\nHash: SHA512\n\nSuite: stable\nSHA256:\n {} 1 a\n{}\n\nsig\n-----END PGP SIGNATURE-----\n",
+            SIGNED_BEGIN, SHA256_A, SIGNATURE_BEGIN
+        ),
+        Ok(vec![entry("a", Some(1), Func::SHA256, SHA256_A)])
+    );
+    repo_test!(
+        release_no_checksums,
+        release,
+        "Suite: stable\nSHA1:\n 86f7e437faa5a7fce15d1ddcb9eaeaea377667b8 1 a\n",
+        Err(Error::NoChecksums)
+    );
+    repo_test!(
+        release_bad_line,
+        release,
+        format!("Suite: stable\nSHA256:\n {} 1\n", SHA256_A),
+        Err(Error::Line {
+            number: 3,
+            reason: "expected digest, size and path"
+        })
+    );
+    repo_test!(
+        release_bad_digest,
+        release,
+        "SHA256:\n abc 1 a\n",
+        Err(Error::Line {
+            number: 2,
+            reason: "invalid digest"
+        })
+    );
+    repo_test!(
+        packages_stanzas,
+        packages,
+        format!(
+            "Package: a\nFilename: pool/a.deb\nSize: 1\nMD5sum: {md5}\nSHA256: {sha}\n\nPackage: b\nDescription: multi\n line\nFilename: pool/b.deb\nMD5sum: {md5}\n\n\nPackage: no-file\n",
+            md5 = MD5_A,
+            sha = SHA256_A
+        ),
+        Ok(vec![
+            entry("pool/a.deb", Some(1), Func::SHA256, SHA256_A),
+            entry("pool/b.deb", None, Func::MD5, MD5_A),
+        ])
+    );
+    repo_test!(
+        packages_no_checksum,
+        packages,
+        "Package: a\nFilename: pool/a.deb\nSHA1: 86f7e437faa5a7fce15d1ddcb9eaeaea377667b8\n",
+        Err(Error::Line {
+            number: 2,
+            reason: "package without supported checksum"
+        })
+    );
+    repo_test!(
+        packages_continuation,
+        packages,
+        " orphan\n",
+        Err(Error::Line {
+            number: 1,
+            reason: "continuation line without field"
+        })
+    );
+    repo_test!(
+        repomd_data,
+        repomd,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<repomd xmlns="http://linux.duke.edu/metadata/repo">
+  <revision>1</revision>
+  <data type="primary">
+    <checksum type="sha256">{sha}</checksum>
+    <open-checksum type="sha256">{md5}{md5}</open-checksum>
+    <location href="repodata/a&amp;b.xml.gz"/>
+    <size>1</size>
+  </data>
+  <data type="other">
+    <checksum type='md5'>{md5}</checksum>
+    <location xml:base="x" href="repodata/other.xml.gz" />
+  </data>
+  <data type="filelists">
+    <checksum type="sha512">00</checksum>
+    <location href="repodata/filelists.xml.gz"/>
+  </data>
+</repomd>
+"#,
+            sha = SHA256_A,
+            md5 = MD5_A
+        ),
+        Ok(vec![
+            entry("repodata/a&b.xml.gz", Some(1), Func::SHA256, SHA256_A),
+            entry("repodata/other.xml.gz", None, Func::MD5, MD5_A),
+        ])
+    );
+    repo_test!(
+        repomd_no_location,
+        repomd,
+        format!(
+            "<repomd>\n<data type=\"primary\">\n<checksum type=\"md5\">{}</checksum>\n</data>\n</repomd>\n",
+            MD5_A
+        ),
+        Err(Error::Line {
+            number: 2,
+            reason: "data without location"
+        })
+    );
+    repo_test!(
+        repomd_empty,
+        repomd,
+        "<repomd></repomd>",
+        Err(Error::NoChecksums)
+    );
+
+    #[test]
+    fn cleartext_signed() {
+        let text = format!(
+            "{}\nHash: SHA512\n\nSuite: stable\n- -dash\n{}\nsig\n",
+            SIGNED_BEGIN, SIGNATURE_BEGIN
+        );
+        assert_eq!(cleartext(&text), "Suite: stable\n-dash\n");
+        assert_eq!(cleartext("Suite: x\n"), "Suite: x\n");
+    }
+}
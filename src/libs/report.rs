@@ -0,0 +1,133 @@
+//! tabular reports for spreadsheets: a header row of column names, then
+//! one row per result, as CSV (RFC 4180) or TSV.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// fields with `,`, `"`, CR or LF are quoted, with `"` doubled, and
+    /// rows end with CRLF.
+    Csv,
+    /// `\`, tab, LF and CR in fields are written as `\\`, `\t`, `\n` and `\r`,
+    /// and rows end with LF.
+    Tsv,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Csv, Format::Tsv];
+
+    /// writes one row of `fields` with its line ending.
+    pub fn row<W: Write>(self, w: &mut W, fields: &[&str]) -> io::Result<()> {
+        let mut line = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                line.push(self.separator());
+            }
+            match self {
+                Format::Csv => csv_field(&mut line, field),
+                Format::Tsv => tsv_field(&mut line, field),
+            }
+        }
+        line.push_str(match self {
+            Format::Csv => "\r\n",
+            Format::Tsv => "\n",
+        });
+        w.write_all(line.as_bytes())
+    }
+
+    fn separator(self) -> char {
+        match self {
+            Format::Csv => ',',
+            Format::Tsv => '\t',
+        }
+    }
+}
+
+fn csv_field(line: &mut String, field: &str) {
+    if !field.contains([',', '"', '\r', '\n']) {
+        line.push_str(field);
+        return;
+    }
+    line.push('"');
+    line.push_str(&field.replace('"', "\"\""));
+    line.push('"');
+}
+
+fn tsv_field(line: &mut String, field: &str) {
+    for c in field.chars() {
+        match c {
+            '\\' => line.push_str("\\\\"),
+            '\t' => line.push_str("\\t"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            c => line.push(c),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Csv => write!(f, "csv"),
+            Format::Tsv => write!(f, "tsv"),
+        }
+    }
+}
+
+impl str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|t| t.to_string() == s)
+            .ok_or(format!("unsupported report format {:?} (csv or tsv)", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! row_test {
+        ($name:ident,$format:expr,$fields:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut out = Vec::new();
+                $format.row(&mut out, $fields).unwrap();
+                let actual = String::from_utf8(out).unwrap();
+                let expected: &str = $expected;
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    row_test!(csv_plain, Format::Csv, &["a b", "", "c"], "a b,,c\r\n");
+    row_test!(
+        csv_quoted,
+        Format::Csv,
+        &["a,b", "say \"hi\"", "two\nlines"],
+        "\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n"
+    );
+    row_test!(tsv_plain, Format::Tsv, &["a b", "", "c,d"], "a b\t\tc,d\n");
+    row_test!(
+        tsv_escaped,
+        Format::Tsv,
+        &["a\tb", "c\\d", "e\r\nf"],
+        "a\\tb\tc\\\\d\te\\r\\nf\n"
+    );
+
+    #[test]
+    fn parse() {
+        for f in Format::ALL {
+            assert_eq!(Ok(f), f.to_string().parse());
+        }
+        assert!("json".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//! progress of checksum file verification, saved so an interrupted run can
+//! skip lines it already verified. the state is JSON:
+//! `{"version":1,"manifests":[{"path":"SUMS","verified":[[1,1000],[1002,1500]]}]}`
+//! with inclusive ranges of 1-based line numbers.
+
+use std::error;
+use std::fmt;
+
+use crate::libs::json::{self, Value};
+
+const VERSION: u64 = 1;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct State {
+    manifests: Vec<Manifest>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Manifest {
+    path: String,
+    /// sorted, disjoint and not adjacent ranges.
+    verified: Vec<(u64, u64)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Json(json::Error),
+    Format(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Json(err) => write!(f, "resume state: {}", err),
+            Error::Format(reason) => write!(f, "resume state: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Json(ref e) => Some(e),
+            Error::Format(_) => None,
+        }
+    }
+}
+
+impl From<json::Error> for Error {
+    fn from(err: json::Error) -> Error {
+        Error::Json(err)
+    }
+}
+
+impl State {
+    pub fn new() -> State {
+        State::default()
+    }
+
+    pub fn parse(text: &str) -> Result<State, Error> {
+        let v = json::parse(text)?;
+        if v.get("version").and_then(Value::as_u64) != Some(VERSION) {
+            return Err(Error::Format("unsupported version"));
+        }
+        let manifests = v
+            .get("manifests")
+            .and_then(Value::as_array)
+            .ok_or(Error::Format("no manifests"))?;
+
+        let mut state = State::new();
+        for m in manifests {
+            let path = m
+                .get("path")
+                .and_then(Value::as_str)
+                .ok_or(Error::Format("manifest without path"))?;
+            let ranges = m
+                .get("verified")
+                .and_then(Value::as_array)
+                .ok_or(Error::Format("manifest without verified lines"))?;
+            for range in ranges {
+                let (start, end) = match range.as_array() {
+                    Some([start, end]) => (start.as_u64(), end.as_u64()),
+                    _ => (None, None),
+                };
+                match (start, end) {
+                    (Some(start), Some(end)) if start <= end => {
+                        for line in start..=end {
+                            state.mark_verified(path, line);
+                        }
+                    }
+                    _ => return Err(Error::Format("invalid line range")),
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    fn manifest(&self, path: &str) -> Option<&Manifest> {
+        self.manifests.iter().find(|m| m.path == path)
+    }
+
+    pub fn is_verified(&self, path: &str, line: u64) -> bool {
+        self.manifest(path).is_some_and(|m| {
+            let i = m.verified.partition_point(|&(_, end)| end < line);
+            m.verified.get(i).is_some_and(|&(start, _)| start <= line)
+        })
+    }
+
+    /// number of verified lines of manifest `path`.
+    pub fn verified_count(&self, path: &str) -> u64 {
+        self.manifest(path).map_or(0, |m| {
+            m.verified.iter().map(|&(start, end)| end - start + 1).sum()
+        })
+    }
+
+    pub fn mark_verified(&mut self, path: &str, line: u64) {
+        let m = match self.manifests.iter().position(|m| m.path == path) {
+            Some(i) => &mut self.manifests[i],
+            None => {
+                self.manifests.push(Manifest {
+                    path: path.to_string(),
+                    verified: Vec::new(),
+                });
+                self.manifests.last_mut().unwrap()
+            }
+        };
+        let ranges = &mut m.verified;
+        // first range which ends at least right before the line.
+        let i = ranges.partition_point(|&(_, end)| end + 1 < line);
+        match ranges.get(i).copied() {
+            Some((start, end)) if start <= line && line <= end => (),
+            Some((start, end)) if end + 1 == line => {
+                ranges[i] = (start, line);
+                if ranges.get(i + 1).is_some_and(|&(next, _)| next == line + 1) {
+                    ranges[i] = (start, ranges[i + 1].1);
+                    ranges.remove(i + 1);
+                }
+            }
+            Some((start, end)) if start == line + 1 => ranges[i] = (line, end),
+            _ => ranges.insert(i, (line, line)),
+        }
+    }
+}
+
+/// compact JSON text of the state.
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let manifests = self
+            .manifests
+            .iter()
+            .map(|m| {
+                let verified = m
+                    .verified
+                    .iter()
+                    .map(|&(start, end)| Value::Array(vec![start.into(), end.into()]))
+                    .collect();
+                Value::Object(vec![
+                    ("path".to_string(), m.path.as_str().into()),
+                    ("verified".to_string(), Value::Array(verified)),
+                ])
+            })
+            .collect();
+        let v = Value::Object(vec![
+            ("version".to_string(), VERSION.into()),
+            ("manifests".to_string(), Value::Array(manifests)),
+        ]);
+        write!(f, "{}", v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(lines: &[u64]) -> State {
+        let mut state = State::new();
+        for &line in lines {
+            state.mark_verified("SUMS", line);
+        }
+        state
+    }
+
+    fn ranges(state: &State) -> Vec<(u64, u64)> {
+        state.manifest("SUMS").unwrap().verified.clone()
+    }
+
+    macro_rules! mark_test {
+        ($name:ident,$lines:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = ranges(&state(&$lines));
+                let expected: Vec<(u64, u64)> = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    mark_test!(in_order, [1, 2, 3, 5, 6], vec![(1, 3), (5, 6)]);
+    mark_test!(fill_gap, [1, 2, 4, 5, 3], vec![(1, 5)]);
+    mark_test!(before, [5, 4, 1], vec![(1, 1), (4, 5)]);
+    mark_test!(repeated, [2, 2, 1, 2], vec![(1, 2)]);
+    mark_test!(between, [1, 9, 5], vec![(1, 1), (5, 5), (9, 9)]);
+
+    #[test]
+    fn is_verified() {
+        let state = state(&[1, 2, 3, 7]);
+        let verified: Vec<u64> = (0..10).filter(|&l| state.is_verified("SUMS", l)).collect();
+        assert_eq!(vec![1, 2, 3, 7], verified);
+        assert!(!state.is_verified("OTHER", 1));
+        assert_eq!(4, state.verified_count("SUMS"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut state = state(&[1, 2, 4]);
+        state.mark_verified("a \"b\"", 10);
+        let text = state.to_string();
+        assert_eq!(
+            r#"{"version":1,"manifests":[{"path":"SUMS","verified":[[1,2],[4,4]]},{"path":"a \"b\"","verified":[[10,10]]}]}"#,
+            text
+        );
+        assert_eq!(Ok(state), State::parse(&text));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Err(Error::Format("unsupported version")),
+            State::parse(r#"{"version":2,"manifests":[]}"#)
+        );
+        assert_eq!(
+            Err(Error::Format("invalid line range")),
+            State::parse(r#"{"version":1,"manifests":[{"path":"a","verified":[[3,1]]}]}"#)
+        );
+        assert!(matches!(State::parse("{"), Err(Error::Json(_))));
+    }
+}
@@ -0,0 +1,71 @@
+use std::io;
+
+use crate::libs::chacha20::{ChaCha20, KEY_BYTE_SIZE, NONCE_BYTE_SIZE};
+use crate::libs::hash;
+
+/// cryptographically secure random bytes generator:
+/// ChaCha20 keystream with a key taken from the operating system.
+pub struct Rng(ChaCha20);
+
+impl Rng {
+    pub fn from_os() -> io::Result<Rng> {
+        let mut key = [0u8; KEY_BYTE_SIZE];
+        getrandom::getrandom(&mut key)?;
+
+        Ok(Rng(ChaCha20::new(&key, &[0; NONCE_BYTE_SIZE], 0)))
+    }
+}
+
+impl io::Read for Rng {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.keystream(buf);
+        Ok(buf.len())
+    }
+}
+
+/// deterministic generator for reproducible test data. NOT for secrets.
+/// the stream is ChaCha20 keystream with key = SHA-256(seed),
+/// zero nonce and block counter starting from zero.
+pub struct SeededRng(ChaCha20);
+
+impl SeededRng {
+    pub fn from_seed(seed: &[u8]) -> SeededRng {
+        let key: [u8; KEY_BYTE_SIZE] = hash::sha256(seed)
+            .expect("reading from slice never fails")
+            .into();
+
+        SeededRng(ChaCha20::new(&key, &[0; NONCE_BYTE_SIZE], 0))
+    }
+}
+
+impl io::Read for SeededRng {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.keystream(buf);
+        Ok(buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::chacha20::cipher::{KeyIvInit, StreamCipher};
+    use ::sha2::Digest as _;
+    use io::Read;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn seeded_reference(seed in vec(any::<u8>(), 0..64), len in 0usize..256) {
+            let mut actual = vec![0u8; len];
+            SeededRng::from_seed(&seed).read_exact(&mut actual).unwrap();
+
+            let key = ::sha2::Sha256::digest(&seed);
+            let mut expected = vec![0u8; len];
+            ::chacha20::ChaCha20::new(&key, &[0; NONCE_BYTE_SIZE].into())
+                .apply_keystream(&mut expected);
+
+            prop_assert_eq!(expected, actual);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::hint;
+use std::io::{self, Write};
+
+use crate::base64::decoder::Decoder;
+use crate::libs::hash;
+use crate::libs::hex;
+
+/// parse keyed material (keys, seeds, salts, nonces) given as
+/// `hex:HEX`, `base64:BASE64`, `raw:STRING` (bytes of the string itself)
+/// or `file:PATH` (content of the file). value without prefix is hex.
+pub fn parse(s: &str) -> Result<Vec<u8>, String> {
+    let (format, value) = s.split_once(':').unwrap_or(("hex", s));
+
+    match format {
+        "hex" => hex::decode(value).map_err(|err| format!("hex: {}", err)),
+        "base64" => {
+            let mut data = Vec::new();
+            let mut decoder = Decoder::new(&mut data);
+            decoder
+                .write_all(value.as_bytes())
+                .and_then(|_| decoder.finish())
+                .map_err(|err| format!("base64: {}", err))?;
+            drop(decoder);
+            Ok(data)
+        }
+        "raw" => Ok(value.as_bytes().to_vec()),
+        "file" => fs::read(value).map_err(|err| format!("file {}: {}", value, err)),
+        _ => Err(format!(
+            "unknown format {:?}, expected hex:, base64:, raw: or file:",
+            format
+        )),
+    }
+}
+
+/// compares secrets in time which depends on neither their content nor
+/// where they differ. lengths are hidden too: SHA-256 digests of both are
+/// compared instead of the secrets themselves.
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    // reading slices never fails.
+    let a = hash::sha256(a).unwrap();
+    let b = hash::sha256(b).unwrap();
+    let diff = a
+        .as_bytes()
+        .iter()
+        .zip(b.as_bytes())
+        .fold(0u8, |diff, (x, y)| diff | hint::black_box(x ^ y));
+    hint::black_box(diff) == 0
+}
+
+/// removes one trailing line break (`\n` or `\r\n`), as left by `echo`
+/// or editors at the end of secret files.
+pub fn trim_newline(mut secret: Vec<u8>) -> Vec<u8> {
+    if secret.ends_with(b"\n") {
+        secret.pop();
+        if secret.ends_with(b"\r") {
+            secret.pop();
+        }
+    }
+    secret
+}
+
+/// reads a line from the terminal with echo turned off by `stty`,
+/// after writing `prompt` to it.
+#[cfg(unix)]
+pub fn prompt(prompt: &str) -> io::Result<Vec<u8>> {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    const TTY: &str = "/dev/tty";
+    let stty = |arg: &str| -> io::Result<()> {
+        let status = Command::new("stty")
+            .arg(arg)
+            .stdin(Stdio::from(fs::File::open(TTY)?))
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("stty {}: {}", arg, status)));
+        }
+        Ok(())
+    };
+
+    let mut tty = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(TTY)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", TTY, err)))?;
+    tty.write_all(prompt.as_bytes())?;
+    stty("-echo")?;
+    let mut line = Vec::new();
+    let read = BufReader::new(&tty).read_until(b'\n', &mut line);
+    // echo is restored even when reading failed.
+    let restored = stty("echo");
+    tty.write_all(b"\n")?;
+    read?;
+    restored?;
+    Ok(trim_newline(line))
+}
+
+#[cfg(not(unix))]
+pub fn prompt(_prompt: &str) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "prompt is supported only on unix",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! parse_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse($data).ok();
+                let expected: Option<Vec<u8>> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    parse_test!(no_prefix, Some(vec![0x00, 0xff]), "00ff");
+    parse_test!(hex, Some(vec![0x00, 0xff]), "hex:00ff");
+    parse_test!(base64, Some(b"hello".to_vec()), "base64:aGVsbG8=");
+    parse_test!(raw, Some(b"a:b".to_vec()), "raw:a:b");
+    parse_test!(empty_raw, Some(vec![]), "raw:");
+    parse_test!(bad_hex, None, "hex:0");
+    parse_test!(unknown, None, "rot13:uryyb");
+    parse_test!(missing_file, None, "file:/nonexistent/secret");
+
+    #[test]
+    fn eq_secrets() {
+        assert!(eq(b"", b""));
+        assert!(eq(b"correct horse", b"correct horse"));
+        assert!(!eq(b"correct horse", b"correct horsE"));
+        assert!(!eq(b"correct horse", b"correct horse "));
+        assert!(!eq(b"", b"\0"));
+    }
+
+    #[test]
+    fn trim_one_newline() {
+        assert_eq!(trim_newline(b"pw\n".to_vec()), b"pw");
+        assert_eq!(trim_newline(b"pw\r\n".to_vec()), b"pw");
+        assert_eq!(trim_newline(b"pw\n\n".to_vec()), b"pw\n");
+        assert_eq!(trim_newline(b"pw\r".to_vec()), b"pw\r");
+        assert_eq!(trim_newline(b"".to_vec()), b"");
+    }
+}
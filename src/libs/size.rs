@@ -0,0 +1,53 @@
+/// parse size in bytes with optional suffix:
+/// K, M, G, T are powers of 1024 and KB, MB, GB, TB are powers of 1000.
+pub fn parse(s: &str) -> Result<u64, String> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, suffix) = s.split_at(digits);
+
+    let number: u64 = number.parse().map_err(|_| format!("invalid size: {}", s))?;
+    let multiplier: u64 = match suffix {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return Err(format!("invalid size suffix: {}", suffix)),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size is too large: {}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! parse_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse($data).ok();
+                let expected: Option<u64> = $expected;
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    parse_test!(bytes, Some(100), "100");
+    parse_test!(kibi, Some(4096), "4K");
+    parse_test!(gibi, Some(1 << 30), "1G");
+    parse_test!(mega, Some(50_000_000), "50MB");
+    parse_test!(empty, None, "");
+    parse_test!(no_number, None, "M");
+    parse_test!(bad_suffix, None, "1Q");
+    parse_test!(overflow, None, "99999999999T");
+}
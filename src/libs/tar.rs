@@ -0,0 +1,371 @@
+//! streaming tar reader for ustar archives with PAX and GNU long name extensions.
+
+use std::io::{self, Read};
+use std::str;
+
+const BLOCK_SIZE: u64 = 512;
+// PAX and GNU long names are read into memory; bigger ones are rejected.
+const MAX_META_SIZE: u64 = 1024 * 1024;
+
+const TYPE_REGULAR: u8 = b'0';
+const TYPE_REGULAR_OLD: u8 = 0;
+const TYPE_CONTIGUOUS: u8 = b'7';
+const TYPE_PAX_LOCAL: u8 = b'x';
+const TYPE_PAX_GLOBAL: u8 = b'g';
+const TYPE_GNU_LONG_NAME: u8 = b'L';
+const TYPE_GNU_LONG_LINK: u8 = b'K';
+
+const USTAR_MAGIC: &[u8] = b"ustar\0";
+
+#[derive(Debug, PartialEq)]
+pub struct Header {
+    pub path: String,
+    pub size: u64,
+    pub typeflag: u8,
+}
+
+impl Header {
+    /// true for entries with file data (not directories, links or devices).
+    pub fn is_file(&self) -> bool {
+        matches!(
+            self.typeflag,
+            TYPE_REGULAR | TYPE_REGULAR_OLD | TYPE_CONTIGUOUS
+        )
+    }
+}
+
+/// tar archive reader. `next_entry` moves to the next member,
+/// then `Read` gives data of that member.
+pub struct Reader<R: Read> {
+    r: R,
+    remaining: u64,
+    padding: u64,
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(r: R) -> Self {
+        Reader {
+            r,
+            remaining: 0,
+            padding: 0,
+        }
+    }
+
+    /// header of the next member, or `None` at the end of the archive.
+    pub fn next_entry(&mut self) -> io::Result<Option<Header>> {
+        let mut path = None;
+        let mut size = None;
+        loop {
+            self.skip()?;
+            let block = match self.block()? {
+                Some(block) => block,
+                None => return Ok(None),
+            };
+            let mut header = header(&block)?;
+            self.remaining = header.size;
+            self.padding = header.size.next_multiple_of(BLOCK_SIZE) - header.size;
+
+            match header.typeflag {
+                TYPE_PAX_LOCAL => {
+                    for (key, value) in pax_records(&self.meta()?)? {
+                        match key.as_str() {
+                            "path" => path = Some(value),
+                            "size" => {
+                                size = Some(value.parse().map_err(|_| {
+                                    invalid(format!("invalid PAX size {:?}", value))
+                                })?)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                TYPE_GNU_LONG_NAME => {
+                    let name = self.meta()?;
+                    let name = name.split(|&b| b == 0).next().unwrap_or_default();
+                    path = Some(String::from_utf8_lossy(name).into_owned());
+                }
+                // global attributes and long link names don't change path and size.
+                TYPE_PAX_GLOBAL | TYPE_GNU_LONG_LINK => {}
+                _ => {
+                    if let Some(path) = path {
+                        header.path = path;
+                    }
+                    if let Some(size) = size {
+                        header.size = size;
+                        self.remaining = size;
+                        self.padding = size.next_multiple_of(BLOCK_SIZE) - size;
+                    }
+                    return Ok(Some(header));
+                }
+            }
+        }
+    }
+
+    // data of the current meta entry.
+    fn meta(&mut self) -> io::Result<Vec<u8>> {
+        if self.remaining > MAX_META_SIZE {
+            return Err(invalid(format!(
+                "extended header of {} bytes is too big",
+                self.remaining
+            )));
+        }
+        let mut data = Vec::new();
+        self.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    // skip unread data of the current entry and its padding.
+    fn skip(&mut self) -> io::Result<()> {
+        let n = self.remaining + self.padding;
+        let skipped = io::copy(&mut (&mut self.r).take(n), &mut io::sink())?;
+        if skipped < n {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining = 0;
+        self.padding = 0;
+        Ok(())
+    }
+
+    // next header block. a zero block or end of input ends the archive.
+    fn block(&mut self) -> io::Result<Option<[u8; BLOCK_SIZE as usize]>> {
+        let mut block = [0u8; BLOCK_SIZE as usize];
+        let mut filled = 0;
+        while filled < block.len() {
+            match self.r.read(&mut block[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if block.iter().all(|&b| b == 0) {
+            return Ok(None);
+        }
+        Ok(Some(block))
+    }
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let max = buf.len().min(self.remaining as usize);
+        let n = self.r.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+fn header(block: &[u8; BLOCK_SIZE as usize]) -> io::Result<Header> {
+    let stored = number(&block[148..156])?;
+    // checksum is taken with its own field filled with spaces.
+    let computed: u64 = block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+        .sum();
+    if stored != computed {
+        return Err(invalid(format!(
+            "header checksum {} does not match computed {}",
+            stored, computed
+        )));
+    }
+
+    let mut path = text(&block[0..100]);
+    // GNU tar uses prefix field for other data and has a different magic.
+    let prefix = text(&block[345..500]);
+    if &block[257..263] == USTAR_MAGIC && !prefix.is_empty() {
+        path = format!("{}/{}", prefix, path);
+    }
+    Ok(Header {
+        path,
+        size: number(&block[124..136])?,
+        typeflag: block[156],
+    })
+}
+
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+// octal number terminated by NUL or space, or GNU base-256 if the high bit is set.
+fn number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut n: u64 = (field[0] & 0x7F) as u64;
+        for &b in &field[1..] {
+            n = n
+                .checked_mul(256)
+                .ok_or_else(|| invalid("base-256 number overflows".to_string()))?
+                | b as u64;
+        }
+        return Ok(n);
+    }
+    let digits = str::from_utf8(field)
+        .map_err(|_| invalid(format!("invalid octal number {:?}", field)))?
+        .trim_matches(|c| c == ' ' || c == '\0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8)
+        .map_err(|_| invalid(format!("invalid octal number {:?}", digits)))
+}
+
+// PAX records: "<length> <key>=<value>\n", where length counts the whole record.
+fn pax_records(data: &[u8]) -> io::Result<Vec<(String, String)>> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let bad = || invalid("invalid PAX record".to_string());
+        let space = rest.iter().position(|&b| b == b' ').ok_or_else(bad)?;
+        let len: usize = str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&len| len > space + 1 && len <= rest.len())
+            .ok_or_else(bad)?;
+        let record = rest[space + 1..len].strip_suffix(b"\n").ok_or_else(bad)?;
+        let eq = record.iter().position(|&b| b == b'=').ok_or_else(bad)?;
+        records.push((
+            String::from_utf8_lossy(&record[..eq]).into_owned(),
+            String::from_utf8_lossy(&record[eq + 1..]).into_owned(),
+        ));
+        rest = &rest[len..];
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ustar header block with valid checksum.
+    fn block(name: &str, size: usize, typeflag: u8, prefix: &str) -> Vec<u8> {
+        let mut b = vec![0u8; BLOCK_SIZE as usize];
+        b[..name.len()].copy_from_slice(name.as_bytes());
+        b[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        b[156] = typeflag;
+        b[257..263].copy_from_slice(USTAR_MAGIC);
+        b[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        b[148..156].fill(b' ');
+        let sum: u64 = b.iter().map(|&x| x as u64).sum();
+        b[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        b
+    }
+
+    fn entry(name: &str, typeflag: u8, prefix: &str, data: &[u8]) -> Vec<u8> {
+        let mut e = block(name, data.len(), typeflag, prefix);
+        e.extend_from_slice(data);
+        e.resize(e.len().next_multiple_of(BLOCK_SIZE as usize), 0);
+        e
+    }
+
+    fn read_all(archive: &[u8]) -> io::Result<Vec<(Header, Vec<u8>)>> {
+        let mut r = Reader::new(archive);
+        let mut entries = Vec::new();
+        while let Some(h) = r.next_entry()? {
+            let mut data = Vec::new();
+            // leave some data unread to check skipping.
+            if h.path != "skipped" {
+                r.read_to_end(&mut data)?;
+            }
+            entries.push((h, data));
+        }
+        Ok(entries)
+    }
+
+    macro_rules! tar_test {
+        ($name:ident,$expected:expr,$archive:expr) => {
+            #[test]
+            fn $name() {
+                let archive: Vec<u8> = $archive;
+                let actual = read_all(&archive).map_err(|err| err.kind());
+                let expected: Result<Vec<(&str, u8, u64, &[u8])>, io::ErrorKind> = $expected;
+                let expected = expected.map(|entries| {
+                    entries
+                        .into_iter()
+                        .map(|(path, typeflag, size, data)| {
+                            (
+                                Header {
+                                    path: path.to_string(),
+                                    size,
+                                    typeflag,
+                                },
+                                data.to_vec(),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                });
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    tar_test!(empty, Ok(vec![]), vec![0; 1024]);
+    tar_test!(
+        files,
+        Ok(vec![
+            ("dir/", b'5', 0, b""),
+            ("dir/a", b'0', 5, b"hello"),
+            ("skipped", b'0', 700, b""),
+            ("b", b'0', 600, &[7; 600]),
+        ]),
+        [
+            entry("dir/", b'5', "", b""),
+            entry("dir/a", b'0', "", b"hello"),
+            entry("skipped", b'0', "", &[1; 700]),
+            entry("b", b'0', "", &[7; 600]),
+            vec![0; 1024],
+        ]
+        .concat()
+    );
+    tar_test!(
+        ustar_prefix,
+        Ok(vec![("long/prefix/name", b'0', 1, b"x")]),
+        entry("name", b'0', "long/prefix", b"x")
+    );
+    tar_test!(
+        pax_path,
+        Ok(vec![
+            ("pax/path/name", b'0', 1, b"x"),
+            ("next", b'0', 0, b"")
+        ]),
+        [
+            entry("PaxHeaders/name", b'x', "", b"22 path=pax/path/name\n"),
+            entry("name", b'0', "", b"x"),
+            entry("next", b'0', "", b""),
+        ]
+        .concat()
+    );
+    tar_test!(
+        gnu_long_name,
+        Ok(vec![("gnu/long/name", b'0', 1, b"x")]),
+        [
+            entry("././@LongLink", b'L', "", b"gnu/long/name\0"),
+            entry("gnu/long/na", b'0', "", b"x"),
+        ]
+        .concat()
+    );
+    tar_test!(bad_checksum, Err(io::ErrorKind::InvalidData), {
+        let mut e = entry("a", b'0', "", b"x");
+        e[0] = b'b';
+        e
+    });
+    tar_test!(
+        truncated_data,
+        Err(io::ErrorKind::UnexpectedEof),
+        block("a", 10, b'0', "")
+    );
+}
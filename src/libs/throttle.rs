@@ -0,0 +1,112 @@
+//! token bucket bandwidth limit, shared by readers on any thread.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// burst allowance as a fraction of a second of the rate.
+const BURST_SECS: f64 = 0.1;
+
+pub struct Limiter {
+    /// bytes per second.
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// available bytes; negative when reads ran ahead of the rate.
+    tokens: f64,
+    last: Instant,
+}
+
+impl Limiter {
+    /// limiter of `rate` bytes per second, allowing bursts of 100ms worth.
+    pub fn new(rate: u64) -> Limiter {
+        let rate = rate.max(1) as f64;
+        let burst = rate * BURST_SECS;
+        Limiter {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    /// takes `n` bytes from the bucket at `now` and returns how long to wait
+    /// until they are covered by the rate.
+    fn take(&self, n: usize, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst) - n as f64;
+        bucket.last = now;
+        if bucket.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(-bucket.tokens / self.rate)
+    }
+
+    /// accounts `n` read bytes, sleeping while reads are ahead of the rate.
+    pub fn consume(&self, n: usize) {
+        let wait = self.take(n, Instant::now());
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn burst_is_free() {
+        let limiter = Limiter::new(1000);
+        let start = limiter.bucket.lock().unwrap().last;
+        assert_eq!(Duration::ZERO, limiter.take(100, start));
+    }
+
+    #[test]
+    fn debt_is_waited() {
+        let limiter = Limiter::new(1000);
+        let start = limiter.bucket.lock().unwrap().last;
+        // 100 bytes of burst, 400 bytes over the rate.
+        assert_eq!(ms(400), limiter.take(500, start));
+        // after waiting, the bucket is empty.
+        assert_eq!(ms(100), limiter.take(100, start + ms(400)));
+    }
+
+    #[test]
+    fn refill_is_capped_by_burst() {
+        let limiter = Limiter::new(1000);
+        let start = limiter.bucket.lock().unwrap().last;
+        limiter.take(100, start);
+        // an idle minute refills only the burst.
+        assert_eq!(ms(100), limiter.take(200, start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn shared_rate() {
+        let limiter = Limiter::new(1000);
+        let start = limiter.bucket.lock().unwrap().last;
+        let waits: Vec<Duration> = (0..3).map(|_| limiter.take(100, start)).collect();
+        assert_eq!(vec![ms(0), ms(100), ms(200)], waits);
+    }
+
+    #[test]
+    fn consume_sleeps() {
+        let limiter = Limiter::new(10_000);
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter.consume(300);
+        }
+        // 3000 bytes with 1000 of burst at 10000 bytes per second.
+        assert!(start.elapsed() >= ms(190), "{:?}", start.elapsed());
+    }
+}
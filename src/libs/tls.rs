@@ -0,0 +1,419 @@
+use std::error;
+use std::fmt;
+
+pub const CONTENT_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_ALERT: u8 = 21;
+pub const CONTENT_HANDSHAKE: u8 = 22;
+pub const CONTENT_APPLICATION_DATA: u8 = 23;
+
+pub const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+pub const HANDSHAKE_SERVER_HELLO: u8 = 2;
+
+const HANDSHAKE_HEADER_LEN: usize = 4;
+const RANDOM_LEN: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// input ended inside the named field.
+    Truncated(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated(field) => write!(f, "truncated {}", field),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// big-endian cursor over TLS wire data.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn rest(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    pub fn bytes(&mut self, n: usize, field: &'static str) -> Result<&'a [u8]> {
+        if self.buf.len() < n {
+            return Err(Error::Truncated(field));
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    pub fn u8(&mut self, field: &'static str) -> Result<u8> {
+        Ok(self.bytes(1, field)?[0])
+    }
+
+    pub fn u16(&mut self, field: &'static str) -> Result<u16> {
+        let b = self.bytes(2, field)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn u24(&mut self, field: &'static str) -> Result<usize> {
+        let b = self.bytes(3, field)?;
+        Ok(u32::from_be_bytes([0, b[0], b[1], b[2]]) as usize)
+    }
+
+    /// vector with 1 byte length prefix.
+    pub fn vec8(&mut self, field: &'static str) -> Result<&'a [u8]> {
+        let n = self.u8(field)? as usize;
+        self.bytes(n, field)
+    }
+
+    /// vector with 2 bytes length prefix.
+    pub fn vec16(&mut self, field: &'static str) -> Result<&'a [u8]> {
+        let n = self.u16(field)? as usize;
+        self.bytes(n, field)
+    }
+}
+
+pub struct Record<'a> {
+    pub content_type: u8,
+    pub version: u16,
+    pub fragment: &'a [u8],
+}
+
+pub fn record<'a>(r: &mut Reader<'a>) -> Result<Record<'a>> {
+    Ok(Record {
+        content_type: r.u8("record header")?,
+        version: r.u16("record header")?,
+        fragment: r.vec16("record fragment")?,
+    })
+}
+
+pub struct Handshake<'a> {
+    pub msg_type: u8,
+    pub body: &'a [u8],
+}
+
+/// handshake message length with header, if `buf` holds at least the header.
+/// messages may be split over several records, so callers collect fragments
+/// until the whole message is there.
+pub fn handshake_len(buf: &[u8]) -> Option<usize> {
+    if buf.len() < HANDSHAKE_HEADER_LEN {
+        return None;
+    }
+    let body = u32::from_be_bytes([0, buf[1], buf[2], buf[3]]) as usize;
+    Some(HANDSHAKE_HEADER_LEN + body)
+}
+
+pub fn handshake<'a>(r: &mut Reader<'a>) -> Result<Handshake<'a>> {
+    let msg_type = r.u8("handshake header")?;
+    let n = r.u24("handshake header")?;
+    Ok(Handshake {
+        msg_type,
+        body: r.bytes(n, "handshake body")?,
+    })
+}
+
+pub struct Extension<'a> {
+    pub typ: u16,
+    pub data: &'a [u8],
+}
+
+/// ClientHello or ServerHello. ServerHello has exactly one cipher suite
+/// and one compression method.
+pub struct Hello<'a> {
+    pub version: u16,
+    pub random: &'a [u8],
+    pub session_id: &'a [u8],
+    pub cipher_suites: Vec<u16>,
+    pub compression_methods: &'a [u8],
+    pub extensions: Vec<Extension<'a>>,
+}
+
+pub fn client_hello(body: &[u8]) -> Result<Hello<'_>> {
+    let mut r = Reader::new(body);
+    let version = r.u16("version")?;
+    let random = r.bytes(RANDOM_LEN, "random")?;
+    let session_id = r.vec8("session id")?;
+    let mut suites = Reader::new(r.vec16("cipher suites")?);
+    let mut cipher_suites = Vec::new();
+    while !suites.is_empty() {
+        cipher_suites.push(suites.u16("cipher suites")?);
+    }
+    let compression_methods = r.vec8("compression methods")?;
+    Ok(Hello {
+        version,
+        random,
+        session_id,
+        cipher_suites,
+        compression_methods,
+        extensions: extensions(&mut r)?,
+    })
+}
+
+pub fn server_hello(body: &[u8]) -> Result<Hello<'_>> {
+    let mut r = Reader::new(body);
+    let version = r.u16("version")?;
+    let random = r.bytes(RANDOM_LEN, "random")?;
+    let session_id = r.vec8("session id")?;
+    let cipher_suites = vec![r.u16("cipher suite")?];
+    let compression_methods = r.bytes(1, "compression method")?;
+    Ok(Hello {
+        version,
+        random,
+        session_id,
+        cipher_suites,
+        compression_methods,
+        extensions: extensions(&mut r)?,
+    })
+}
+
+// extensions are optional at the end of hello messages.
+fn extensions<'a>(r: &mut Reader<'a>) -> Result<Vec<Extension<'a>>> {
+    let mut extensions = Vec::new();
+    if r.is_empty() {
+        return Ok(extensions);
+    }
+    let mut r = Reader::new(r.vec16("extensions")?);
+    while !r.is_empty() {
+        extensions.push(Extension {
+            typ: r.u16("extension type")?,
+            data: r.vec16("extension data")?,
+        });
+    }
+    Ok(extensions)
+}
+
+pub struct Alert {
+    pub level: u8,
+    pub description: u8,
+}
+
+pub fn alert(r: &mut Reader) -> Result<Alert> {
+    Ok(Alert {
+        level: r.u8("alert")?,
+        description: r.u8("alert")?,
+    })
+}
+
+pub fn version_name(v: u16) -> Option<&'static str> {
+    match v {
+        0x0300 => Some("SSL 3.0"),
+        0x0301 => Some("TLS 1.0"),
+        0x0302 => Some("TLS 1.1"),
+        0x0303 => Some("TLS 1.2"),
+        0x0304 => Some("TLS 1.3"),
+        _ => None,
+    }
+}
+
+pub fn content_type_name(t: u8) -> Option<&'static str> {
+    match t {
+        CONTENT_CHANGE_CIPHER_SPEC => Some("change_cipher_spec"),
+        CONTENT_ALERT => Some("alert"),
+        CONTENT_HANDSHAKE => Some("handshake"),
+        CONTENT_APPLICATION_DATA => Some("application_data"),
+        24 => Some("heartbeat"),
+        _ => None,
+    }
+}
+
+pub fn handshake_type_name(t: u8) -> Option<&'static str> {
+    match t {
+        0 => Some("hello_request"),
+        HANDSHAKE_CLIENT_HELLO => Some("client_hello"),
+        HANDSHAKE_SERVER_HELLO => Some("server_hello"),
+        4 => Some("new_session_ticket"),
+        5 => Some("end_of_early_data"),
+        8 => Some("encrypted_extensions"),
+        11 => Some("certificate"),
+        12 => Some("server_key_exchange"),
+        13 => Some("certificate_request"),
+        14 => Some("server_hello_done"),
+        15 => Some("certificate_verify"),
+        16 => Some("client_key_exchange"),
+        20 => Some("finished"),
+        24 => Some("key_update"),
+        254 => Some("message_hash"),
+        _ => None,
+    }
+}
+
+pub fn alert_level_name(l: u8) -> Option<&'static str> {
+    match l {
+        1 => Some("warning"),
+        2 => Some("fatal"),
+        _ => None,
+    }
+}
+
+pub fn alert_description_name(d: u8) -> Option<&'static str> {
+    match d {
+        0 => Some("close_notify"),
+        10 => Some("unexpected_message"),
+        20 => Some("bad_record_mac"),
+        22 => Some("record_overflow"),
+        40 => Some("handshake_failure"),
+        42 => Some("bad_certificate"),
+        43 => Some("unsupported_certificate"),
+        44 => Some("certificate_revoked"),
+        45 => Some("certificate_expired"),
+        46 => Some("certificate_unknown"),
+        47 => Some("illegal_parameter"),
+        48 => Some("unknown_ca"),
+        49 => Some("access_denied"),
+        50 => Some("decode_error"),
+        51 => Some("decrypt_error"),
+        70 => Some("protocol_version"),
+        71 => Some("insufficient_security"),
+        80 => Some("internal_error"),
+        86 => Some("inappropriate_fallback"),
+        90 => Some("user_canceled"),
+        100 => Some("no_renegotiation"),
+        109 => Some("missing_extension"),
+        110 => Some("unsupported_extension"),
+        112 => Some("unrecognized_name"),
+        113 => Some("bad_certificate_status_response"),
+        115 => Some("unknown_psk_identity"),
+        116 => Some("certificate_required"),
+        120 => Some("no_application_protocol"),
+        _ => None,
+    }
+}
+
+pub fn extension_name(t: u16) -> Option<&'static str> {
+    match t {
+        0 => Some("server_name"),
+        1 => Some("max_fragment_length"),
+        5 => Some("status_request"),
+        10 => Some("supported_groups"),
+        11 => Some("ec_point_formats"),
+        13 => Some("signature_algorithms"),
+        14 => Some("use_srtp"),
+        15 => Some("heartbeat"),
+        16 => Some("application_layer_protocol_negotiation"),
+        18 => Some("signed_certificate_timestamp"),
+        21 => Some("padding"),
+        22 => Some("encrypt_then_mac"),
+        23 => Some("extended_master_secret"),
+        27 => Some("compress_certificate"),
+        28 => Some("record_size_limit"),
+        35 => Some("session_ticket"),
+        41 => Some("pre_shared_key"),
+        42 => Some("early_data"),
+        43 => Some("supported_versions"),
+        44 => Some("cookie"),
+        45 => Some("psk_key_exchange_modes"),
+        47 => Some("certificate_authorities"),
+        48 => Some("oid_filters"),
+        49 => Some("post_handshake_auth"),
+        50 => Some("signature_algorithms_cert"),
+        51 => Some("key_share"),
+        0xff01 => Some("renegotiation_info"),
+        _ => None,
+    }
+}
+
+pub fn cipher_suite_name(s: u16) -> Option<&'static str> {
+    match s {
+        0x00ff => Some("TLS_EMPTY_RENEGOTIATION_INFO_SCSV"),
+        0x002f => Some("TLS_RSA_WITH_AES_128_CBC_SHA"),
+        0x0035 => Some("TLS_RSA_WITH_AES_256_CBC_SHA"),
+        0x009c => Some("TLS_RSA_WITH_AES_128_GCM_SHA256"),
+        0x009d => Some("TLS_RSA_WITH_AES_256_GCM_SHA384"),
+        0x1301 => Some("TLS_AES_128_GCM_SHA256"),
+        0x1302 => Some("TLS_AES_256_GCM_SHA384"),
+        0x1303 => Some("TLS_CHACHA20_POLY1305_SHA256"),
+        0x5600 => Some("TLS_FALLBACK_SCSV"),
+        0xc009 => Some("TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA"),
+        0xc00a => Some("TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA"),
+        0xc013 => Some("TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA"),
+        0xc014 => Some("TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA"),
+        0xc02b => Some("TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256"),
+        0xc02c => Some("TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384"),
+        0xc02f => Some("TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"),
+        0xc030 => Some("TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384"),
+        0xcca8 => Some("TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256"),
+        0xcca9 => Some("TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! record_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = &$data;
+                let actual = record(&mut Reader::new(data))
+                    .map(|r| (r.content_type, r.version, r.fragment.to_vec()));
+                let expected: Result<(u8, u16, Vec<u8>)> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    record_test!(
+        alert_record,
+        Ok((CONTENT_ALERT, 0x0303, vec![2, 40])),
+        [21, 3, 3, 0, 2, 2, 40]
+    );
+    record_test!(empty_fragment, Ok((23, 0x0303, vec![])), [23, 3, 3, 0, 0]);
+    record_test!(
+        short_header,
+        Err(Error::Truncated("record header")),
+        [22, 3]
+    );
+    record_test!(
+        short_fragment,
+        Err(Error::Truncated("record fragment")),
+        [22, 3, 1, 0, 4, 1, 0]
+    );
+
+    #[test]
+    fn hello() {
+        let mut body = vec![3, 3];
+        body.extend_from_slice(&[0xAA; 32]);
+        body.extend_from_slice(&[1, 0x55]); // session id
+        body.extend_from_slice(&[0, 4, 0x13, 0x01, 0xc0, 0x2f]); // cipher suites
+        body.extend_from_slice(&[1, 0]); // compression methods
+        body.extend_from_slice(&[0, 9, 0, 0, 0, 5, 0, 3, 0, 0, 1]); // extensions
+
+        let mut data = vec![HANDSHAKE_CLIENT_HELLO, 0, 0, body.len() as u8];
+        data.extend_from_slice(&body);
+        assert_eq!(handshake_len(&data), Some(data.len()));
+
+        let h = handshake(&mut Reader::new(&data)).unwrap();
+        assert_eq!(h.msg_type, HANDSHAKE_CLIENT_HELLO);
+
+        let hello = client_hello(h.body).unwrap();
+        assert_eq!(hello.version, 0x0303);
+        assert_eq!(hello.session_id, &[0x55]);
+        assert_eq!(hello.cipher_suites, vec![0x1301, 0xc02f]);
+        assert_eq!(hello.compression_methods, &[0]);
+        assert_eq!(hello.extensions.len(), 1);
+        assert_eq!(hello.extensions[0].typ, 0);
+        assert_eq!(hello.extensions[0].data, &[0, 3, 0, 0, 1]);
+
+        assert_eq!(
+            client_hello(&body[..40]).err(),
+            Some(Error::Truncated("cipher suites"))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! parser of the TOML subset used by configuration files:
+//! `key = value` pairs, `[table]` and `[[array of tables]]` headers with bare
+//! names, basic and literal strings, integers, booleans and single-line arrays.
+//! documents are returned as `json::Value` objects.
+
+use std::fmt;
+
+use crate::libs::json::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct Error {
+    /// 1-based line number.
+    pub line: usize,
+    pub msg: &'static str,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "toml: line {}: {}", self.line, self.msg)
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn is_bare(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// members of the object holding `key`: appends a new empty one if absent.
+fn member<'a>(members: &'a mut Vec<(String, Value)>, key: &str, v: Value) -> &'a mut Value {
+    let i = match members.iter().position(|(k, _)| k == key) {
+        Some(i) => i,
+        None => {
+            members.push((key.to_string(), v));
+            members.len() - 1
+        }
+    };
+    &mut members[i].1
+}
+
+pub fn parse(text: &str) -> Result<Value, Error> {
+    let mut root = Vec::new();
+    // header of the current table: name and whether it is an array element.
+    let mut current: Option<(String, bool)> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let error = |msg| Error { line: i + 1, msg };
+        let mut p = Parser {
+            s: line.as_bytes(),
+            pos: 0,
+        };
+        p.whitespace();
+        if p.end() {
+            continue;
+        }
+
+        if p.peek() == Some(b'[') {
+            let array = line.trim_start().starts_with("[[");
+            let (open, close) = if array { ("[[", "]]") } else { ("[", "]") };
+            let rest = line.trim_start()[open.len()..].trim_end();
+            let rest = match rest.find('#') {
+                Some(c) => rest[..c].trim_end(),
+                None => rest,
+            };
+            let name = rest
+                .strip_suffix(close)
+                .map(str::trim)
+                .filter(|name| is_bare(name))
+                .ok_or(error("invalid table header"))?;
+            let table = member(&mut root, name, Value::Array(Vec::new()));
+            match (table, array) {
+                (Value::Array(tables), true) => tables.push(Value::Object(Vec::new())),
+                (table @ Value::Array(_), false) if table.as_array() == Some(&[]) => {
+                    *table = Value::Object(Vec::new())
+                }
+                _ => return Err(error("table defined twice")),
+            }
+            current = Some((name.to_string(), array));
+            continue;
+        }
+
+        let key = p.key().map_err(error)?;
+        p.whitespace();
+        if p.peek() != Some(b'=') {
+            return Err(error("expected ="));
+        }
+        p.pos += 1;
+        let v = p.value().map_err(error)?;
+        p.whitespace();
+        if !p.end() {
+            return Err(error("trailing characters"));
+        }
+
+        let members = match current {
+            None => &mut root,
+            Some((ref name, array)) => match member(&mut root, name, Value::Null) {
+                Value::Array(tables) if array => match tables.last_mut() {
+                    Some(Value::Object(members)) => members,
+                    _ => unreachable!("array of tables holds tables"),
+                },
+                Value::Object(members) => members,
+                _ => unreachable!("table header creates the table"),
+            },
+        };
+        if members.iter().any(|(k, _)| *k == key) {
+            return Err(error("duplicate key"));
+        }
+        members.push((key, v));
+    }
+    Ok(Value::Object(root))
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn whitespace(&mut self) {
+        while let Some(b' ' | b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    /// end of line, or start of a comment.
+    fn end(&self) -> bool {
+        matches!(self.peek(), None | Some(b'#'))
+    }
+
+    fn key(&mut self) -> Result<String, &'static str> {
+        if let Some(b'"' | b'\'') = self.peek() {
+            return self.string();
+        }
+        let start = self.pos;
+        while let Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b'-') = self.peek() {
+            self.pos += 1;
+        }
+        match self.pos {
+            pos if pos == start => Err("expected key"),
+            pos => Ok(String::from_utf8_lossy(&self.s[start..pos]).into_owned()),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, &'static str> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'"' | b'\'') => Ok(Value::String(self.string()?)),
+            Some(b'[') => self.array(),
+            Some(b't' | b'f') => {
+                for (word, b) in [("true", true), ("false", false)] {
+                    if self.s[self.pos..].starts_with(word.as_bytes()) {
+                        self.pos += word.len();
+                        return Ok(Value::Bool(b));
+                    }
+                }
+                Err("invalid value")
+            }
+            Some(b'+' | b'-' | b'0'..=b'9') => self.integer(),
+            _ => Err("invalid value"),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, &'static str> {
+        let start = self.pos;
+        if let Some(b'+' | b'-') = self.peek() {
+            self.pos += 1;
+        }
+        while let Some(b'0'..=b'9' | b'_') = self.peek() {
+            self.pos += 1;
+        }
+        let text: String = String::from_utf8_lossy(&self.s[start..self.pos])
+            .chars()
+            .filter(|&c| c != '_' && c != '+')
+            .collect();
+        match text.parse::<i64>() {
+            Ok(n) => Ok(Value::Number(n.to_string())),
+            Err(_) => Err("invalid integer"),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, &'static str> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.whitespace();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => (),
+                _ => return Err("expected , or ] in array"),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        let quote = self.s[self.pos];
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            let c = self.peek().ok_or("unterminated string")?;
+            self.pos += 1;
+            match c {
+                c if c == quote => break,
+                b'\\' if quote == b'"' => {
+                    let e = self.peek().ok_or("unterminated string")?;
+                    self.pos += 1;
+                    match e {
+                        b'"' | b'\\' => out.push(e),
+                        b'n' => out.push(b'\n'),
+                        b't' => out.push(b'\t'),
+                        b'r' => out.push(b'\r'),
+                        b'u' => {
+                            let hex = self.s.get(self.pos..self.pos + 4).ok_or("invalid escape")?;
+                            let code = std::str::from_utf8(hex)
+                                .ok()
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .and_then(char::from_u32)
+                                .ok_or("invalid escape")?;
+                            self.pos += 4;
+                            out.extend_from_slice(code.to_string().as_bytes());
+                        }
+                        _ => return Err("invalid escape"),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        String::from_utf8(out).map_err(|_| "invalid utf-8 in string")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libs::json;
+
+    macro_rules! parse_test {
+        ($name:ident,$text:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse($text).map(|v| v.to_string());
+                let expected: Result<String, Error> = $expected.map(String::from);
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    fn err(line: usize, msg: &'static str) -> Result<&'static str, Error> {
+        Err(Error { line, msg })
+    }
+
+    parse_test!(
+        pairs,
+        "# config\nname = \"a \\\"b\\\"\" # comment\npath = 'C:\\dir'\nn = -1_000\nok = true\n",
+        Ok(r#"{"name":"a \"b\"","path":"C:\\dir","n":-1000,"ok":true}"#)
+    );
+    parse_test!(
+        tables,
+        "top = 1\n[server]\nport = 80\n[[item]]\nx = [1, 'two', [3]]\n[[item]]\n\"quoted key\" = false\n",
+        Ok(r#"{"top":1,"server":{"port":80},"item":[{"x":[1,"two",[3]]},{"quoted key":false}]}"#)
+    );
+    parse_test!(empty, "\n  # nothing\n", Ok("{}"));
+    parse_test!(missing_equals, "a 1", err(1, "expected ="));
+    parse_test!(duplicate_key, "a = 1\na = 2", err(2, "duplicate key"));
+    parse_test!(table_twice, "[a]\n[a]", err(2, "table defined twice"));
+    parse_test!(bad_header, "[a.b]", err(1, "invalid table header"));
+    parse_test!(unterminated, "a = \"x", err(1, "unterminated string"));
+    parse_test!(trailing, "a = 1 2", err(1, "trailing characters"));
+    parse_test!(bad_value, "a = yes", err(1, "invalid value"));
+
+    #[test]
+    fn get() {
+        let v = parse("[[m]]\npath = \"SUMS\"\n").unwrap();
+        let m = &v.get("m").and_then(json::Value::as_array).unwrap()[0];
+        assert_eq!(Some("SUMS"), m.get("path").and_then(json::Value::as_str));
+    }
+}
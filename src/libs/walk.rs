@@ -0,0 +1,50 @@
+//! recursive listing of the regular files of a directory tree.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// regular files under `root`, sorted by path.
+/// symbolic links are not followed and not listed.
+pub fn files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|err| io::Error::new(err.kind(), format!("{:?}: {}", dir, err)))?;
+        for entry in entries {
+            let entry = entry?;
+            let kind = entry.file_type()?;
+            if kind.is_dir() {
+                dirs.push(entry.path());
+            } else if kind.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files() {
+        let root = std::env::temp_dir().join(format!("ssl-walk-{}", std::process::id()));
+        fs::create_dir_all(root.join("b/c")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        for f in ["a", "b/x", "b/c/y"] {
+            fs::write(root.join(f), f).unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("a"), root.join("link")).unwrap();
+
+        let actual = super::files(&root);
+        let _ = fs::remove_dir_all(&root);
+        let expected: Vec<PathBuf> = ["a", "b/c/y", "b/x"].iter().map(|f| root.join(f)).collect();
+        assert_eq!(expected, actual.unwrap());
+        assert!(super::files(&root).is_err());
+    }
+}
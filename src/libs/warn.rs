@@ -0,0 +1,70 @@
+//! warnings on stderr with stable codes, like
+//! `WARNING[WEAK_DIGEST]: MD5 is open to collisions, prefer SHA256`.
+//! scripts match the code, and users silence a code with `--no-warn CODE`.
+//! each code is printed at most once per run.
+
+use std::fmt;
+use std::str;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::libs::hash::Func;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Code {
+    /// digests generated with a function open to collisions.
+    WeakDigest,
+}
+
+impl Code {
+    pub const ALL: [Code; 1] = [Code::WeakDigest];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Code::WeakDigest => write!(f, "WEAK_DIGEST"),
+        }
+    }
+}
+
+impl str::FromStr for Code {
+    type Err = String;
+
+    /// case-insensitive code, like `WEAK_DIGEST`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Code::ALL
+            .into_iter()
+            .find(|c| c.to_string().eq_ignore_ascii_case(s))
+            .ok_or(format!("unknown warning code {:?} (WEAK_DIGEST)", s))
+    }
+}
+
+/// codes which are suppressed or were already printed.
+static DONE: AtomicU32 = AtomicU32::new(0);
+
+/// `code` is not printed from now on.
+pub fn suppress(code: Code) {
+    DONE.fetch_or(code.bit(), Ordering::Relaxed);
+}
+
+/// prints `msg` with `code` unless the code is suppressed or was printed.
+pub fn emit(code: Code, msg: fmt::Arguments) {
+    if DONE.fetch_or(code.bit(), Ordering::Relaxed) & code.bit() == 0 {
+        eprintln!("WARNING[{}]: {}", code, msg);
+    }
+}
+
+/// warns about digests generated with `f` when it is weak.
+/// checking existing digests is not warned about.
+pub fn generating(f: Func) {
+    if !f.approved() {
+        emit(
+            Code::WeakDigest,
+            format_args!("{} is open to collisions, prefer SHA256", f),
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! notification of changes to files: inotify on linux, kqueue on macOS and the BSDs,
+//! and polling of size and modification time elsewhere.
+//! files are watched by path, so a file replaced by rename or removed and
+//! created again is still reported.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// name of the mechanism used on this platform.
+pub const BACKEND: &str = sys::BACKEND;
+
+pub struct Watcher {
+    inner: sys::Watcher,
+}
+
+impl Watcher {
+    pub fn new() -> io::Result<Watcher> {
+        Ok(Watcher {
+            inner: sys::Watcher::new()?,
+        })
+    }
+
+    /// watch `path`, which does not need to exist yet.
+    pub fn add(&mut self, path: &Path) -> io::Result<()> {
+        self.inner.add(path)
+    }
+
+    /// blocks until some watched files change or `timeout` passes.
+    /// returns paths of changed files (as passed to `add`), without duplicates.
+    pub fn wait(&mut self, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+        let mut changed = self.inner.wait(timeout)?;
+        changed.sort();
+        changed.dedup();
+        Ok(changed)
+    }
+}
+
+/// directory of `path` as it is joined with entry names, and as it is opened.
+fn parent(path: &Path) -> (PathBuf, PathBuf) {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => (dir.into(), dir.into()),
+        _ => (PathBuf::new(), PathBuf::from(".")),
+    }
+}
+
+#[cfg(unix)]
+fn cstring(path: &Path) -> io::Result<std::ffi::CString> {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains NUL byte"))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod sys {
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::OsStr;
+    use std::fs::File;
+    use std::io::{self, Read};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    pub const BACKEND: &str = "inotify";
+
+    /// directory events which change or replace an entry.
+    const MASK: u32 = libc::IN_CLOSE_WRITE
+        | libc::IN_ATTRIB
+        | libc::IN_MOVED_TO
+        | libc::IN_MOVED_FROM
+        | libc::IN_DELETE;
+    /// size of `struct inotify_event` without the name.
+    const EVENT_SIZE: usize = 16;
+
+    pub struct Watcher {
+        inotify: File,
+        /// watched directory of each watch descriptor.
+        dirs: HashMap<i32, PathBuf>,
+        files: HashSet<PathBuf>,
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Watcher> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Watcher {
+                inotify: File::from(unsafe { OwnedFd::from_raw_fd(fd) }),
+                dirs: HashMap::new(),
+                files: HashSet::new(),
+            })
+        }
+
+        /// watches the directory of `path`, so replacing the file is noticed too.
+        pub fn add(&mut self, path: &Path) -> io::Result<()> {
+            let (dir, open) = super::parent(path);
+            let dir_path = super::cstring(&open)?;
+            let wd = unsafe {
+                libc::inotify_add_watch(self.inotify.as_raw_fd(), dir_path.as_ptr(), MASK)
+            };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            self.dirs.insert(wd, dir);
+            self.files.insert(path.into());
+            Ok(())
+        }
+
+        pub fn wait(&mut self, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+            let mut pfd = libc::pollfd {
+                fd: self.inotify.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let millis = timeout.as_millis().min(i32::MAX as u128) as i32;
+            match unsafe { libc::poll(&mut pfd, 1, millis) } {
+                n if n < 0 => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        return Ok(Vec::new());
+                    }
+                    return Err(err);
+                }
+                0 => return Ok(Vec::new()),
+                _ => (),
+            }
+
+            let mut buf = [0u8; 64 * 1024];
+            let n = self.inotify.read(&mut buf)?;
+            let mut changed = Vec::new();
+            let mut rest = &buf[..n];
+            while rest.len() >= EVENT_SIZE {
+                let field = |i: usize| u32::from_ne_bytes(rest[i..i + 4].try_into().unwrap());
+                let wd = field(0) as i32;
+                let len = field(12) as usize;
+                let name = &rest[EVENT_SIZE..EVENT_SIZE + len];
+                // the name is padded with NUL bytes.
+                let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(len)];
+                if let Some(dir) = self.dirs.get(&wd) {
+                    let path = dir.join(OsStr::from_bytes(name));
+                    if self.files.contains(&path) {
+                        changed.push(path);
+                    }
+                }
+                rest = &rest[EVENT_SIZE + len..];
+            }
+            Ok(changed)
+        }
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::path::{Path, PathBuf};
+    use std::ptr;
+    use std::time::Duration;
+
+    pub const BACKEND: &str = "kqueue";
+
+    struct Watched {
+        path: PathBuf,
+        dir: PathBuf,
+        /// open while the file exists; closing it removes its event.
+        file: Option<File>,
+    }
+
+    pub struct Watcher {
+        kqueue: OwnedFd,
+        files: Vec<Watched>,
+        /// opened directories of the files, which report created entries.
+        dirs: Vec<(PathBuf, File)>,
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Watcher> {
+            let fd = unsafe { libc::kqueue() };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Watcher {
+                kqueue: unsafe { OwnedFd::from_raw_fd(fd) },
+                files: Vec::new(),
+                dirs: Vec::new(),
+            })
+        }
+
+        fn register(&self, fd: RawFd, fflags: u32) -> io::Result<()> {
+            let mut ev: libc::kevent = unsafe { mem::zeroed() };
+            ev.ident = fd as _;
+            ev.filter = libc::EVFILT_VNODE;
+            ev.flags = libc::EV_ADD | libc::EV_CLEAR;
+            ev.fflags = fflags as _;
+            let res = unsafe {
+                libc::kevent(
+                    self.kqueue.as_raw_fd(),
+                    &ev,
+                    1,
+                    ptr::null_mut(),
+                    0,
+                    ptr::null(),
+                )
+            };
+            if res < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        /// opens the file at index `i` and registers it, if it exists.
+        fn open(&mut self, i: usize) -> io::Result<bool> {
+            let file = match File::open(&self.files[i].path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+                Err(err) => return Err(err),
+            };
+            let fflags = libc::NOTE_WRITE
+                | libc::NOTE_EXTEND
+                | libc::NOTE_ATTRIB
+                | libc::NOTE_DELETE
+                | libc::NOTE_RENAME;
+            self.register(file.as_raw_fd(), fflags as u32)?;
+            self.files[i].file = Some(file);
+            Ok(true)
+        }
+
+        pub fn add(&mut self, path: &Path) -> io::Result<()> {
+            let (dir, open) = super::parent(path);
+            if !self.dirs.iter().any(|(d, _)| *d == dir) {
+                let file = File::open(&open)?;
+                self.register(file.as_raw_fd(), libc::NOTE_WRITE as u32)?;
+                self.dirs.push((dir.clone(), file));
+            }
+            self.files.push(Watched {
+                path: path.into(),
+                dir,
+                file: None,
+            });
+            self.open(self.files.len() - 1)?;
+            Ok(())
+        }
+
+        pub fn wait(&mut self, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+            let mut ts: libc::timespec = unsafe { mem::zeroed() };
+            ts.tv_sec = timeout.as_secs() as _;
+            ts.tv_nsec = timeout.subsec_nanos() as _;
+            let mut events: [libc::kevent; 32] = unsafe { mem::zeroed() };
+            let n = unsafe {
+                libc::kevent(
+                    self.kqueue.as_raw_fd(),
+                    ptr::null(),
+                    0,
+                    events.as_mut_ptr(),
+                    events.len() as _,
+                    &ts,
+                )
+            };
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    return Ok(Vec::new());
+                }
+                return Err(err);
+            }
+
+            let mut changed = Vec::new();
+            for ev in &events[..n as usize] {
+                let fd = ev.ident as RawFd;
+                let gone = (ev.fflags as u32) & (libc::NOTE_DELETE | libc::NOTE_RENAME) as u32;
+                if let Some(w) = self
+                    .files
+                    .iter_mut()
+                    .find(|w| w.file.as_ref().is_some_and(|f| f.as_raw_fd() == fd))
+                {
+                    if gone != 0 {
+                        w.file = None;
+                    }
+                    changed.push(w.path.clone());
+                } else if let Some((dir, _)) = self.dirs.iter().find(|(_, f)| f.as_raw_fd() == fd) {
+                    // an entry was created or renamed into the directory.
+                    let dir = dir.clone();
+                    for i in 0..self.files.len() {
+                        if self.files[i].dir == dir
+                            && self.files[i].file.is_none()
+                            && self.open(i)?
+                        {
+                            changed.push(self.files[i].path.clone());
+                        }
+                    }
+                }
+            }
+            Ok(changed)
+        }
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+)))]
+mod sys {
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    pub const BACKEND: &str = "poll";
+
+    type Stamp = Option<(u64, Option<SystemTime>)>;
+
+    fn stamp(path: &Path) -> Stamp {
+        fs::metadata(path)
+            .ok()
+            .map(|m| (m.len(), m.modified().ok()))
+    }
+
+    /// compares size and modification time of the files every `wait`.
+    pub struct Watcher {
+        files: Vec<(PathBuf, Stamp)>,
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Watcher> {
+            Ok(Watcher { files: Vec::new() })
+        }
+
+        pub fn add(&mut self, path: &Path) -> io::Result<()> {
+            self.files.push((path.into(), stamp(path)));
+            Ok(())
+        }
+
+        pub fn wait(&mut self, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+            thread::sleep(timeout);
+            let mut changed = Vec::new();
+            for (path, old) in self.files.iter_mut() {
+                let new = stamp(path);
+                if new != *old {
+                    *old = new;
+                    changed.push(path.clone());
+                }
+            }
+            Ok(changed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn changes(w: &mut Watcher) -> Vec<PathBuf> {
+        w.wait(Duration::from_secs(2)).unwrap()
+    }
+
+    #[test]
+    fn watch() {
+        let dir = std::env::temp_dir().join(format!("ssl-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::write(&a, "a").unwrap();
+
+        let mut w = Watcher::new().unwrap();
+        w.add(&a).unwrap();
+        w.add(&b).unwrap();
+
+        fs::write(&a, "changed").unwrap();
+        assert_eq!(vec![a.clone()], changes(&mut w));
+
+        // created file, and replacing the other one.
+        fs::write(&b, "b").unwrap();
+        assert_eq!(vec![b.clone()], changes(&mut w));
+        fs::write(dir.join("c"), "c").unwrap();
+        fs::rename(dir.join("c"), &a).unwrap();
+        assert_eq!(vec![a.clone()], changes(&mut w));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+//! weak algorithms and parameters in certificates, keys and DH parameters:
+//! MD5 and SHA-1 signatures, short RSA moduli and DH primes, small curves
+//! and validity periods which do not include the current time.
+
+use std::fmt;
+use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::libs::audit;
+use crate::libs::der::{self, Reader};
+use crate::libs::oid;
+
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+
+/// RSA moduli and DH primes shorter than this are weak.
+const MIN_BITS: usize = 2048;
+/// RSA moduli and DH primes shorter than this are broken.
+const BROKEN_BITS: usize = 1024;
+/// curves of fewer bits than SECG secp224r1.
+const SMALL_CURVES: [&str; 1] = ["prime192v1"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Info,
+        Severity::Low,
+        Severity::Medium,
+        Severity::High,
+    ];
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+        }
+    }
+}
+
+impl str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|l| l.to_string() == s)
+            .ok_or(format!(
+                "unsupported severity {:?} (info, low, medium or high)",
+                s
+            ))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+fn finding(severity: Severity, message: String) -> Finding {
+    Finding { severity, message }
+}
+
+/// findings of the DER object of PEM block `label` at time `now`, or
+/// `None` for labels which are not inspected.
+pub fn block(label: &str, der: &[u8], now: SystemTime) -> Option<der::Result<Vec<Finding>>> {
+    let findings = match label {
+        "CERTIFICATE" | "X509 CERTIFICATE" | "TRUSTED CERTIFICATE" => certificate(der, now),
+        "PUBLIC KEY" => public_key(&mut Reader::new(der)),
+        "PRIVATE KEY" => private_key(der),
+        "RSA PUBLIC KEY" => rsa_key(der, false),
+        "RSA PRIVATE KEY" => rsa_key(der, true),
+        "EC PRIVATE KEY" => ec_private_key(der),
+        "DH PARAMETERS" => dh_parameters(der),
+        _ => return None,
+    };
+    Some(findings)
+}
+
+/// findings of an X.509 certificate: its signature algorithm, public key
+/// and validity at `now`.
+pub fn certificate(der: &[u8], now: SystemTime) -> der::Result<Vec<Finding>> {
+    let mut cert = Reader::new(Reader::new(der).expect(der::TAG_SEQUENCE)?.content);
+    let mut tbs = Reader::new(cert.expect(der::TAG_SEQUENCE)?.content);
+    if tbs.peek_tag() == Some(der::context(0)) {
+        tbs.read()?;
+    }
+    tbs.expect(der::TAG_INTEGER)?;
+    tbs.expect(der::TAG_SEQUENCE)?;
+    tbs.expect(der::TAG_SEQUENCE)?;
+    let mut validity = Reader::new(tbs.expect(der::TAG_SEQUENCE)?.content);
+    let not_before = time(&mut validity)?;
+    let not_after = time(&mut validity)?;
+    tbs.expect(der::TAG_SEQUENCE)?;
+
+    let mut findings = Vec::new();
+    let mut signature = Reader::new(cert.expect(der::TAG_SEQUENCE)?.content);
+    let name = describe(signature.expect(der::TAG_OID)?.content)?;
+    let lower = name.to_ascii_lowercase();
+    if lower.contains("md5") {
+        findings.push(finding(Severity::High, format!("signed with {}", name)));
+    } else if lower.contains("sha1") {
+        findings.push(finding(Severity::Medium, format!("signed with {}", name)));
+    }
+    findings.extend(public_key(&mut tbs)?);
+    if now > not_after {
+        let msg = format!("expired at {}", audit::timestamp(not_after));
+        findings.push(finding(Severity::High, msg));
+    } else if now < not_before {
+        let msg = format!("not valid before {}", audit::timestamp(not_before));
+        findings.push(finding(Severity::Low, msg));
+    }
+    Ok(findings)
+}
+
+fn describe(oid: &[u8]) -> der::Result<String> {
+    oid::describe(oid).map_err(|_| der::Error::Unsupported)
+}
+
+/// findings of the next SubjectPublicKeyInfo of `r`.
+fn public_key(r: &mut Reader) -> der::Result<Vec<Finding>> {
+    let mut spki = Reader::new(r.expect(der::TAG_SEQUENCE)?.content);
+    let mut algorithm = Reader::new(spki.expect(der::TAG_SEQUENCE)?.content);
+    let key = spki.expect(TAG_BIT_STRING)?.content;
+    match describe(algorithm.expect(der::TAG_OID)?.content)?.as_str() {
+        "rsaEncryption" => rsa_key(key.get(1..).ok_or(der::Error::Truncated)?, false),
+        "id-ecPublicKey" => curve(&mut algorithm),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// findings of a PKCS#8 PrivateKeyInfo.
+fn private_key(der: &[u8]) -> der::Result<Vec<Finding>> {
+    let mut info = Reader::new(Reader::new(der).expect(der::TAG_SEQUENCE)?.content);
+    info.expect(der::TAG_INTEGER)?;
+    let mut algorithm = Reader::new(info.expect(der::TAG_SEQUENCE)?.content);
+    let key = info.expect(TAG_OCTET_STRING)?.content;
+    match describe(algorithm.expect(der::TAG_OID)?.content)?.as_str() {
+        "rsaEncryption" => rsa_key(key, true),
+        "id-ecPublicKey" => curve(&mut algorithm),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// findings of a PKCS#1 RSAPublicKey, or RSAPrivateKey with `private`.
+fn rsa_key(der: &[u8], private: bool) -> der::Result<Vec<Finding>> {
+    let mut key = Reader::new(Reader::new(der).expect(der::TAG_SEQUENCE)?.content);
+    if private {
+        key.expect(der::TAG_INTEGER)?;
+    }
+    let bits = integer_bits(key.expect(der::TAG_INTEGER)?.content);
+    Ok(short("RSA modulus", bits).into_iter().collect())
+}
+
+/// findings of an RFC 5915 ECPrivateKey.
+fn ec_private_key(der: &[u8]) -> der::Result<Vec<Finding>> {
+    let mut key = Reader::new(Reader::new(der).expect(der::TAG_SEQUENCE)?.content);
+    key.expect(der::TAG_INTEGER)?;
+    key.expect(TAG_OCTET_STRING)?;
+    if key.peek_tag() != Some(der::context(0)) {
+        return Ok(Vec::new());
+    }
+    curve(&mut Reader::new(key.read()?.content))
+}
+
+/// findings of PKCS#3 DHParameter.
+fn dh_parameters(der: &[u8]) -> der::Result<Vec<Finding>> {
+    let mut params = Reader::new(Reader::new(der).expect(der::TAG_SEQUENCE)?.content);
+    let bits = integer_bits(params.expect(der::TAG_INTEGER)?.content);
+    Ok(short("DH prime", bits).into_iter().collect())
+}
+
+/// finding of the named curve OID at `r`; explicit parameters are not
+/// inspected.
+fn curve(r: &mut Reader) -> der::Result<Vec<Finding>> {
+    if r.peek_tag() != Some(der::TAG_OID) {
+        return Ok(Vec::new());
+    }
+    let name = describe(r.read()?.content)?;
+    let finding = match oid::dotted(&name) {
+        None => finding(Severity::Info, format!("unknown curve {}", name)),
+        Some(_) if SMALL_CURVES.contains(&name.as_str()) => finding(
+            Severity::Medium,
+            format!("curve {} has fewer than 224 bits", name),
+        ),
+        Some(_) => return Ok(Vec::new()),
+    };
+    Ok(vec![finding])
+}
+
+fn short(what: &str, bits: usize) -> Option<Finding> {
+    let severity = match bits {
+        b if b < BROKEN_BITS => Severity::High,
+        b if b < MIN_BITS => Severity::Medium,
+        _ => return None,
+    };
+    Some(finding(severity, format!("{} of {} bits", what, bits)))
+}
+
+/// bit length of the unsigned big-endian integer `n`.
+fn integer_bits(n: &[u8]) -> usize {
+    match n.iter().position(|&b| b != 0) {
+        Some(i) => (n.len() - i) * 8 - n[i].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// next UTCTime or GeneralizedTime of `r`, which must be in UTC to the second.
+fn time(r: &mut Reader) -> der::Result<SystemTime> {
+    let tlv = r.read()?;
+    let s = str::from_utf8(tlv.content).map_err(|_| der::Error::Unsupported)?;
+    let s = s.strip_suffix('Z').ok_or(der::Error::Unsupported)?;
+    let digits = |s: &str| -> der::Result<u64> {
+        match s.bytes().all(|b| b.is_ascii_digit()) {
+            true => s.parse().map_err(|_| der::Error::Unsupported),
+            false => Err(der::Error::Unsupported),
+        }
+    };
+    let (year, rest) = match (tlv.tag, s.len()) {
+        (TAG_UTC_TIME, 12) => match digits(&s[..2])? {
+            y if y < 50 => (2000 + y, &s[2..]),
+            y => (1900 + y, &s[2..]),
+        },
+        (TAG_GENERALIZED_TIME, 14) => (digits(&s[..4])?, &s[4..]),
+        _ => return Err(der::Error::Unsupported),
+    };
+    let field = |i: usize| digits(&rest[i..i + 2]);
+    let (month, day) = (field(0)?, field(2)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(der::Error::Unsupported);
+    }
+    let secs =
+        days_from_civil(year, month, day) * 86400 + field(4)? * 3600 + field(6)? * 60 + field(8)?;
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// days since 1970-01-01 of a date from that day on (Howard Hinnant's
+/// algorithm, the inverse of the one in `audit::timestamp`).
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year % 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    (era * 146097 + doe).saturating_sub(719468)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seq(parts: &[Vec<u8>]) -> Vec<u8> {
+        der::encode(der::TAG_SEQUENCE, &parts.concat())
+    }
+
+    fn oid(name: &str) -> Vec<u8> {
+        der::encode(der::TAG_OID, &oid::encode(name).unwrap())
+    }
+
+    fn int(bytes: &[u8]) -> Vec<u8> {
+        der::encode(der::TAG_INTEGER, bytes)
+    }
+
+    /// RSA SubjectPublicKeyInfo with a modulus of `bits` bits.
+    fn rsa_spki(bits: usize) -> Vec<u8> {
+        let mut n = vec![0xFF; bits / 8];
+        n.insert(0, 0);
+        let key = seq(&[int(&n), int(&[1, 0, 1])]);
+        seq(&[
+            seq(&[oid("rsaEncryption"), der::encode(0x05, &[])]),
+            der::encode(TAG_BIT_STRING, &[&[0][..], &key].concat()),
+        ])
+    }
+
+    fn cert(signature: &str, spki: Vec<u8>, not_before: &str, not_after: &str) -> Vec<u8> {
+        let algorithm = seq(&[oid(signature)]);
+        let tbs = seq(&[
+            der::encode(der::context(0), &int(&[2])),
+            int(&[1]),
+            algorithm.clone(),
+            seq(&[]),
+            seq(&[
+                der::encode(TAG_UTC_TIME, not_before.as_bytes()),
+                der::encode(TAG_GENERALIZED_TIME, not_after.as_bytes()),
+            ]),
+            seq(&[]),
+            spki,
+        ]);
+        seq(&[tbs, algorithm, der::encode(TAG_BIT_STRING, &[0])])
+    }
+
+    fn now() -> SystemTime {
+        // 2024-05-01T12:00:00Z
+        UNIX_EPOCH + Duration::from_secs(1714564800)
+    }
+
+    macro_rules! block_test {
+        ($name:ident,$label:expr,$der:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = block($label, &$der, now())
+                    .unwrap()
+                    .unwrap()
+                    .into_iter()
+                    .map(|f| (f.severity, f.message))
+                    .collect::<Vec<_>>();
+                let expected: Vec<(Severity, &str)> = $expected;
+                let expected = expected
+                    .into_iter()
+                    .map(|(s, m)| (s, m.to_string()))
+                    .collect::<Vec<_>>();
+
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    block_test!(
+        strong_certificate,
+        "CERTIFICATE",
+        cert(
+            "sha256WithRSAEncryption",
+            rsa_spki(2048),
+            "240101000000Z",
+            "20340101000000Z"
+        ),
+        vec![]
+    );
+    block_test!(
+        weak_certificate,
+        "CERTIFICATE",
+        cert(
+            "md5WithRSAEncryption",
+            rsa_spki(1024),
+            "990101000000Z",
+            "20000101000000Z"
+        ),
+        vec![
+            (Severity::High, "signed with md5WithRSAEncryption"),
+            (Severity::Medium, "RSA modulus of 1024 bits"),
+            (Severity::High, "expired at 2000-01-01T00:00:00Z"),
+        ]
+    );
+    block_test!(
+        future_certificate,
+        "CERTIFICATE",
+        cert(
+            "sha1WithRSAEncryption",
+            rsa_spki(4096),
+            "250101000000Z",
+            "20340101000000Z"
+        ),
+        vec![
+            (Severity::Medium, "signed with sha1WithRSAEncryption"),
+            (Severity::Low, "not valid before 2025-01-01T00:00:00Z"),
+        ]
+    );
+    block_test!(
+        small_curve,
+        "PUBLIC KEY",
+        seq(&[
+            seq(&[oid("id-ecPublicKey"), oid("prime192v1")]),
+            der::encode(TAG_BIT_STRING, &[0, 4]),
+        ]),
+        vec![(Severity::Medium, "curve prime192v1 has fewer than 224 bits")]
+    );
+    block_test!(
+        unknown_curve,
+        "EC PRIVATE KEY",
+        seq(&[
+            int(&[1]),
+            der::encode(TAG_OCTET_STRING, &[1]),
+            der::encode(der::context(0), &oid("1.2.3.4")),
+        ]),
+        vec![(Severity::Info, "unknown curve 1.2.3.4")]
+    );
+    block_test!(
+        broken_dh,
+        "DH PARAMETERS",
+        seq(&[int(&[0x7F; 64]), int(&[2])]),
+        vec![(Severity::High, "DH prime of 511 bits")]
+    );
+
+    #[test]
+    fn not_inspected() {
+        assert!(block("CERTIFICATE REQUEST", &[], now()).is_none());
+    }
+
+    #[test]
+    fn severity_order() {
+        assert!(Severity::High > Severity::Medium);
+        assert_eq!("low".parse(), Ok(Severity::Low));
+        assert!("critical".parse::<Severity>().is_err());
+    }
+
+    #[test]
+    fn civil() {
+        for secs in [0, 951782400, 1704067199, 4102444800] {
+            let t = UNIX_EPOCH + Duration::from_secs(secs);
+            let s = audit::timestamp(t);
+            let (y, m, d) = (&s[..4], &s[5..7], &s[8..10]);
+            let days = days_from_civil(y.parse().unwrap(), m.parse().unwrap(), d.parse().unwrap());
+            assert_eq!(days, secs / 86400, "{}", s);
+        }
+    }
+}
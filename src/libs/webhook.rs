@@ -0,0 +1,163 @@
+//! delivery of JSON notifications by HTTP POST.
+//! only plain `http://` URLs are supported; put a local relay in front
+//! of endpoints which need TLS.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// limit for connecting and for each read or write.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, PartialEq)]
+struct Url<'a> {
+    host: &'a str,
+    port: u16,
+    path: &'a str,
+}
+
+fn parse_url(url: &str) -> Result<Url<'_>, String> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        format!(
+            "unsupported webhook url {:?}: only http:// is supported",
+            url
+        )
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        // an IPv6 address without port, like [::1].
+        Some((_, port)) if port.ends_with(']') => (authority, 80),
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in webhook url {:?}", url))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("no host in webhook url {:?}", url));
+    }
+    Ok(Url { host, port, path })
+}
+
+/// posts `body` as `application/json` to `url`; fails unless the server
+/// answers with a 2xx status.
+pub fn post(url: &str, body: &str) -> io::Result<()> {
+    let url = parse_url(url).map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+    let host = url.host.trim_start_matches('[').trim_end_matches(']');
+    let addr = (host, url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "webhook host has no address"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: ssl/{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        url.path,
+        url.host,
+        env!("CARGO_PKG_VERSION"),
+        body.len(),
+        body
+    )?;
+
+    let mut status = String::new();
+    BufReader::new(stream).read_line(&mut status)?;
+    match status.split(' ').nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "webhook answered {:?}",
+            status.trim_end()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    macro_rules! parse_url_test {
+        ($name:ident,$url:expr,$expected:expr) => {
+            #[test]
+            fn $name() {
+                let actual = parse_url($url).ok();
+                let expected = $expected;
+                println!("  actual: {:?}", actual);
+                println!("expected: {:?}", expected);
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    parse_url_test!(
+        host_only,
+        "http://example.com",
+        Some(Url {
+            host: "example.com",
+            port: 80,
+            path: "/"
+        })
+    );
+    parse_url_test!(
+        port_and_path,
+        "http://127.0.0.1:8080/hooks/ssl?x=1",
+        Some(Url {
+            host: "127.0.0.1",
+            port: 8080,
+            path: "/hooks/ssl?x=1"
+        })
+    );
+    parse_url_test!(
+        ipv6,
+        "http://[::1]/h",
+        Some(Url {
+            host: "[::1]",
+            port: 80,
+            path: "/h"
+        })
+    );
+    parse_url_test!(https, "https://example.com/", None);
+    parse_url_test!(bad_port, "http://a:port/", None);
+    parse_url_test!(no_host, "http:///x", None);
+
+    fn server(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let mut request = Vec::new();
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn post_ok() {
+        let (url, handle) = server("204 No Content");
+        post(&url, r#"{"a":1}"#).unwrap();
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.contains("\r\nContent-Length: 7\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"a\":1}"));
+    }
+
+    #[test]
+    fn post_rejected() {
+        let (url, handle) = server("500 Internal Server Error");
+        assert!(post(&url, "{}").is_err());
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,458 @@
+//! zip archive reader: central directory (with ZIP64) and stored or
+//! deflated members, which are streamed, not read into memory.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::libs::checksum::Crc32;
+use crate::libs::inflate;
+
+const EOCD_SIGNATURE: u32 = 0x06054B50;
+const EOCD_LEN: usize = 22;
+const MAX_COMMENT_LEN: usize = 0xFFFF;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064B50;
+const ZIP64_LOCATOR_LEN: u64 = 20;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x06064B50;
+const CENTRAL_SIGNATURE: u32 = 0x02014B50;
+const CENTRAL_LEN: usize = 46;
+const LOCAL_SIGNATURE: u32 = 0x04034B50;
+const LOCAL_LEN: usize = 30;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+pub const METHOD_STORED: u16 = 0;
+pub const METHOD_DEFLATE: u16 = 8;
+
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub flags: u16,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub size: u64,
+    local_header_offset: u64,
+}
+
+impl Entry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn u16_at(b: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([b[pos], b[pos + 1]])
+}
+
+fn u32_at(b: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(b[pos..pos + 4].try_into().unwrap())
+}
+
+fn u64_at(b: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(b[pos..pos + 8].try_into().unwrap())
+}
+
+fn read_at<R: Read + Seek>(r: &mut R, pos: u64, len: usize) -> io::Result<Vec<u8>> {
+    r.seek(SeekFrom::Start(pos))?;
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// entries of the central directory in their order.
+pub fn central_directory<R: Read + Seek>(r: &mut R) -> io::Result<Vec<Entry>> {
+    let len = r.seek(SeekFrom::End(0))?;
+    let tail_len = len.min((EOCD_LEN + MAX_COMMENT_LEN) as u64);
+    let tail = read_at(r, len - tail_len, tail_len as usize)?;
+    if tail.len() < EOCD_LEN {
+        return Err(invalid("end of central directory not found"));
+    }
+    let eocd = (0..=tail.len() - EOCD_LEN)
+        .rev()
+        .find(|&i| u32_at(&tail, i) == EOCD_SIGNATURE)
+        .ok_or_else(|| invalid("end of central directory not found"))?;
+    let eocd_pos = len - tail_len + eocd as u64;
+    let eocd = &tail[eocd..];
+
+    let mut count = u16_at(eocd, 10) as u64;
+    let mut cd_size = u32_at(eocd, 12) as u64;
+    let mut cd_offset = u32_at(eocd, 16) as u64;
+    if eocd_pos >= ZIP64_LOCATOR_LEN {
+        let locator = read_at(r, eocd_pos - ZIP64_LOCATOR_LEN, ZIP64_LOCATOR_LEN as usize)?;
+        if u32_at(&locator, 0) == ZIP64_LOCATOR_SIGNATURE {
+            let eocd64 = read_at(r, u64_at(&locator, 8), 56)?;
+            if u32_at(&eocd64, 0) != ZIP64_EOCD_SIGNATURE {
+                return Err(invalid("invalid ZIP64 end of central directory"));
+            }
+            count = u64_at(&eocd64, 32);
+            cd_size = u64_at(&eocd64, 40);
+            cd_offset = u64_at(&eocd64, 48);
+        }
+    }
+
+    if cd_offset.checked_add(cd_size).is_none_or(|end| end > len) {
+        return Err(invalid("central directory is out of the archive"));
+    }
+    let cd = read_at(r, cd_offset, cd_size as usize)?;
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    for _ in 0..count {
+        if pos + CENTRAL_LEN > cd.len() || u32_at(&cd, pos) != CENTRAL_SIGNATURE {
+            return Err(invalid("invalid central directory entry"));
+        }
+        let h = &cd[pos..];
+        let name_len = u16_at(h, 28) as usize;
+        let extra_len = u16_at(h, 30) as usize;
+        let comment_len = u16_at(h, 32) as usize;
+        let end = CENTRAL_LEN + name_len + extra_len;
+        if pos + end + comment_len > cd.len() {
+            return Err(invalid("truncated central directory entry"));
+        }
+
+        let mut entry = Entry {
+            name: String::from_utf8_lossy(&h[CENTRAL_LEN..CENTRAL_LEN + name_len]).into_owned(),
+            flags: u16_at(h, 8),
+            method: u16_at(h, 10),
+            crc32: u32_at(h, 16),
+            compressed_size: u32_at(h, 20) as u64,
+            size: u32_at(h, 24) as u64,
+            local_header_offset: u32_at(h, 42) as u64,
+        };
+        zip64_extra(&mut entry, &h[CENTRAL_LEN + name_len..end])?;
+        // members are stored before the central directory.
+        if entry
+            .local_header_offset
+            .checked_add(entry.compressed_size)
+            .is_none_or(|end| end > cd_offset)
+        {
+            return Err(invalid("member is out of the archive"));
+        }
+        entries.push(entry);
+        pos += end + comment_len;
+    }
+    Ok(entries)
+}
+
+// 32 bit fields set to 0xFFFFFFFF are stored in the ZIP64 extra field, in order.
+fn zip64_extra(entry: &mut Entry, mut extra: &[u8]) -> io::Result<()> {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let len = u16_at(extra, 2) as usize;
+        let data = extra
+            .get(4..4 + len)
+            .ok_or_else(|| invalid("truncated extra field"))?;
+        if id == ZIP64_EXTRA_ID {
+            let mut values = data.chunks_exact(8).map(|v| u64_at(v, 0));
+            for field in [
+                &mut entry.size,
+                &mut entry.compressed_size,
+                &mut entry.local_header_offset,
+            ] {
+                if *field == u32::MAX as u64 {
+                    *field = values
+                        .next()
+                        .ok_or_else(|| invalid("truncated ZIP64 extra field"))?;
+                }
+            }
+        }
+        extra = &extra[4 + len..];
+    }
+    Ok(())
+}
+
+/// reader of the uncompressed data of a member, which is inflated while
+/// it is read. fails as soon as more than the size recorded in the central
+/// directory is read, and at the end when less was read or the CRC-32
+/// does not match.
+pub struct Member<'a> {
+    data: Box<dyn Read + 'a>,
+    size: u64,
+    crc32: u32,
+    read: u64,
+    /// CRC-32 of the data read, until it is checked at the end.
+    crc: Option<Crc32>,
+}
+
+impl Read for Member<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // checked at the end already.
+        let Some(ref mut crc) = self.crc else {
+            return Ok(0);
+        };
+        let n = self.data.read(buf)?;
+        self.read += n as u64;
+        if self.read > self.size {
+            return Err(invalid(
+                "member is larger than its size in central directory",
+            ));
+        }
+        crc.write_all(&buf[..n])?;
+        if n > 0 || buf.is_empty() {
+            return Ok(n);
+        }
+
+        if self.read != self.size {
+            return Err(invalid(
+                "uncompressed size does not match central directory",
+            ));
+        }
+        let crc = self.crc.take().unwrap_or_default().finish();
+        if crc != self.crc32 {
+            return Err(invalid(&format!(
+                "CRC-32 mismatch: stored {:08x}, computed {:08x}",
+                self.crc32, crc
+            )));
+        }
+        Ok(0)
+    }
+}
+
+/// reader of the data of `entry`, stored or deflated.
+pub fn read_entry<'a, R: Read + Seek>(r: &'a mut R, entry: &Entry) -> io::Result<Member<'a>> {
+    if entry.is_encrypted() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "encrypted members are not supported",
+        ));
+    }
+    let len = r.seek(SeekFrom::End(0))?;
+    let local = read_at(r, entry.local_header_offset, LOCAL_LEN)?;
+    if u32_at(&local, 0) != LOCAL_SIGNATURE {
+        return Err(invalid("invalid local file header"));
+    }
+    let data_pos = entry.local_header_offset
+        + (LOCAL_LEN + u16_at(&local, 26) as usize + u16_at(&local, 28) as usize) as u64;
+    if data_pos
+        .checked_add(entry.compressed_size)
+        .is_none_or(|end| end > len)
+    {
+        return Err(invalid("member is out of the archive"));
+    }
+    r.seek(SeekFrom::Start(data_pos))?;
+    let compressed = io::BufReader::new(r.take(entry.compressed_size));
+
+    let data: Box<dyn Read> = match entry.method {
+        METHOD_STORED => Box::new(compressed),
+        METHOD_DEFLATE => Box::new(inflate::Inflater::new(compressed)),
+        m => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("compression method {} is not supported", m),
+            ))
+        }
+    };
+    Ok(Member {
+        data,
+        size: entry.size,
+        crc32: entry.crc32,
+        read: 0,
+        crc: Some(Crc32::new()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Cursor;
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = Crc32::new();
+        crc.write_all(data).unwrap();
+        crc.finish()
+    }
+
+    // archive with members (name, method, data) and an archive comment.
+    fn archive(members: &[(&str, u16, &[u8])], zip64: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut cd = Vec::new();
+        for &(name, method, data) in members {
+            let compressed = match method {
+                METHOD_DEFLATE => {
+                    let mut e = DeflateEncoder::new(Vec::new(), Compression::default());
+                    e.write_all(data).unwrap();
+                    e.finish().unwrap()
+                }
+                _ => data.to_vec(),
+            };
+            let offset = out.len() as u32;
+            let mut common = Vec::new();
+            common.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            common.extend_from_slice(&0u16.to_le_bytes()); // flags
+            common.extend_from_slice(&method.to_le_bytes());
+            common.extend_from_slice(&[0; 4]); // time, date
+            common.extend_from_slice(&crc32(data).to_le_bytes());
+            common.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+
+            out.extend_from_slice(&LOCAL_SIGNATURE.to_le_bytes());
+            out.extend_from_slice(&common);
+            out.extend_from_slice(&0u16.to_le_bytes()); // extra
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&compressed);
+
+            cd.extend_from_slice(&CENTRAL_SIGNATURE.to_le_bytes());
+            cd.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            cd.extend_from_slice(&common);
+            if zip64 {
+                // offset is moved to ZIP64 extra field.
+                cd.extend_from_slice(&12u16.to_le_bytes());
+                cd.extend_from_slice(&[0; 6]); // comment, disk, attributes
+                cd.extend_from_slice(&[0; 4]);
+                cd.extend_from_slice(&u32::MAX.to_le_bytes());
+                cd.extend_from_slice(name.as_bytes());
+                cd.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+                cd.extend_from_slice(&8u16.to_le_bytes());
+                cd.extend_from_slice(&(offset as u64).to_le_bytes());
+            } else {
+                cd.extend_from_slice(&0u16.to_le_bytes());
+                cd.extend_from_slice(&[0; 6]);
+                cd.extend_from_slice(&[0; 4]);
+                cd.extend_from_slice(&offset.to_le_bytes());
+                cd.extend_from_slice(name.as_bytes());
+            }
+        }
+        let cd_offset = out.len();
+        out.extend_from_slice(&cd);
+        if zip64 {
+            let eocd64 = out.len() as u64;
+            out.extend_from_slice(&ZIP64_EOCD_SIGNATURE.to_le_bytes());
+            out.extend_from_slice(&44u64.to_le_bytes());
+            out.extend_from_slice(&[0; 12]); // versions, disks
+            out.extend_from_slice(&(members.len() as u64).to_le_bytes());
+            out.extend_from_slice(&(members.len() as u64).to_le_bytes());
+            out.extend_from_slice(&(cd.len() as u64).to_le_bytes());
+            out.extend_from_slice(&(cd_offset as u64).to_le_bytes());
+            out.extend_from_slice(&ZIP64_LOCATOR_SIGNATURE.to_le_bytes());
+            out.extend_from_slice(&0u32.to_le_bytes());
+            out.extend_from_slice(&eocd64.to_le_bytes());
+            out.extend_from_slice(&1u32.to_le_bytes());
+        }
+        out.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&[0; 4]); // disks
+        let count: u16 = if zip64 { 0xFFFF } else { members.len() as u16 };
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&(cd.len() as u32).to_le_bytes());
+        let cd_offset: u32 = if zip64 { u32::MAX } else { cd_offset as u32 };
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&7u16.to_le_bytes());
+        out.extend_from_slice(b"comment");
+        out
+    }
+
+    fn read_all(archive: Vec<u8>) -> io::Result<Vec<(String, Vec<u8>)>> {
+        let mut r = Cursor::new(archive);
+        central_directory(&mut r)?
+            .into_iter()
+            .map(|e| {
+                let mut data = Vec::new();
+                read_entry(&mut r, &e)?.read_to_end(&mut data)?;
+                Ok((e.name.clone(), data))
+            })
+            .collect()
+    }
+
+    macro_rules! zip_test {
+        ($name:ident,$zip64:expr) => {
+            #[test]
+            fn $name() {
+                let big: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+                let members: [(&str, u16, &[u8]); 4] = [
+                    ("dir/", METHOD_STORED, b""),
+                    ("dir/stored", METHOD_STORED, b"hello"),
+                    ("deflated", METHOD_DEFLATE, &big),
+                    ("empty", METHOD_DEFLATE, b""),
+                ];
+                let actual = read_all(archive(&members, $zip64)).unwrap();
+                let expected: Vec<(String, Vec<u8>)> = members
+                    .iter()
+                    .map(|&(name, _, data)| (name.to_string(), data.to_vec()))
+                    .collect();
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    zip_test!(zip32, false);
+    zip_test!(zip64, true);
+
+    #[test]
+    fn not_zip() {
+        let err = read_all(vec![0; 100]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn shorter_than_eocd() {
+        for len in [0, EOCD_LEN - 1] {
+            let err = read_all(vec![0; len]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn truncated_eocd() {
+        // the signature alone, 4 bytes of the 22 of the record.
+        let err = read_all(EOCD_SIGNATURE.to_le_bytes().to_vec()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn size_mismatch() {
+        let mut data = archive(&[("a", METHOD_STORED, b"abc")], false);
+        // uncompressed size of central directory entry.
+        let pos = data.len() - EOCD_LEN - 7 - 1 - CENTRAL_LEN + 24;
+        data[pos] = 4;
+        let err = read_all(data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    // field at `offset` of the central directory entry of the only member `a`.
+    fn patch(data: &mut [u8], offset: usize, value: u32) {
+        let pos = data.len() - EOCD_LEN - 7 - 1 - CENTRAL_LEN + offset;
+        data[pos..pos + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn out_of_archive() {
+        let mut data = archive(&[("a", METHOD_STORED, b"abc")], false);
+        // compressed size, which would be allocated if read at once.
+        patch(&mut data, 20, 0xFFFF_FFF0);
+        let err = read_all(data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn larger_than_size() {
+        let data = vec![0; 1 << 20];
+        let mut data = archive(&[("a", METHOD_DEFLATE, &data)], false);
+        patch(&mut data, 24, 1000);
+        let err = read_all(data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "member is larger than its size in central directory"
+        );
+    }
+
+    #[test]
+    fn crc_mismatch() {
+        let mut data = archive(&[("a", METHOD_DEFLATE, b"abc")], false);
+        patch(&mut data, 16, 0);
+        let err = read_all(data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "CRC-32 mismatch: stored 00000000, computed 352441c2"
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! zlib stream format (RFC 1950): header, deflate data, Adler-32 trailer.
+
+use std::error;
+use std::fmt;
+
+pub const HEADER_LEN: usize = 2;
+pub const TRAILER_LEN: usize = 4;
+
+const METHOD_DEFLATE: u8 = 8;
+const MAX_WINDOW_BITS: u8 = 15;
+const FLAG_DICT: u8 = 0x20;
+const DICT_ID_LEN: usize = 4;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Truncated,
+    HeaderCheck,
+    Method(u8),
+    WindowSize(u8),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "truncated zlib header"),
+            Error::HeaderCheck => write!(f, "zlib header check failed"),
+            Error::Method(m) => write!(f, "unsupported zlib compression method {}", m),
+            Error::WindowSize(bits) => write!(f, "invalid zlib window size 2^{}", bits),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// FLEVEL, the compressor's hint of how hard it tried. it is not the
+/// compression level: zlib writes `Maximum` for levels 7 to 9.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flevel {
+    Fastest,
+    Fast,
+    Default,
+    Maximum,
+}
+
+impl Flevel {
+    /// FLEVEL of the two high bits of the FLG byte.
+    fn of(flg: u8) -> Flevel {
+        match flg >> 6 {
+            0 => Flevel::Fastest,
+            1 => Flevel::Fast,
+            2 => Flevel::Default,
+            _ => Flevel::Maximum,
+        }
+    }
+}
+
+impl fmt::Display for Flevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Flevel::Fastest => write!(f, "fastest"),
+            Flevel::Fast => write!(f, "fast"),
+            Flevel::Default => write!(f, "default"),
+            Flevel::Maximum => write!(f, "maximum"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Header {
+    pub window_size: usize,
+    pub flevel: Flevel,
+    /// Adler-32 of the preset dictionary the data was compressed with.
+    pub dict_id: Option<u32>,
+    /// header length in bytes.
+    pub len: usize,
+}
+
+pub fn header(data: &[u8]) -> Result<Header, Error> {
+    let (cmf, flg) = match data {
+        [cmf, flg, ..] => (*cmf, *flg),
+        _ => return Err(Error::Truncated),
+    };
+    if !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(Error::HeaderCheck);
+    }
+    if cmf & 0x0F != METHOD_DEFLATE {
+        return Err(Error::Method(cmf & 0x0F));
+    }
+    let window_bits = (cmf >> 4) + 8;
+    if window_bits > MAX_WINDOW_BITS {
+        return Err(Error::WindowSize(window_bits));
+    }
+
+    let dict_id = if flg & FLAG_DICT != 0 {
+        let id = data
+            .get(HEADER_LEN..HEADER_LEN + DICT_ID_LEN)
+            .ok_or(Error::Truncated)?;
+        Some(u32::from_be_bytes(id.try_into().unwrap()))
+    } else {
+        None
+    };
+    Ok(Header {
+        window_size: 1 << window_bits,
+        flevel: Flevel::of(flg),
+        len: HEADER_LEN + dict_id.map_or(0, |_| DICT_ID_LEN),
+        dict_id,
+    })
+}
+
+/// Adler-32 of the uncompressed data.
+pub fn trailer(data: &[u8]) -> Result<u32, Error> {
+    data.get(..TRAILER_LEN)
+        .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+        .ok_or(Error::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! header_test {
+        ($name:ident,$expected:expr,$data:expr) => {
+            #[test]
+            fn $name() {
+                let data: &[u8] = &$data;
+                let actual = header(data);
+                let expected: Result<Header, Error> = $expected;
+
+                println!("  actual: {:X?}", actual);
+                println!("expected: {:X?}", expected);
+
+                assert_eq!(expected, actual);
+            }
+        };
+    }
+
+    header_test!(
+        default_level,
+        Ok(Header {
+            window_size: 32768,
+            flevel: Flevel::Default,
+            dict_id: None,
+            len: 2,
+        }),
+        [0x78, 0x9C]
+    );
+    header_test!(
+        fastest,
+        Ok(Header {
+            window_size: 32768,
+            flevel: Flevel::Fastest,
+            dict_id: None,
+            len: 2,
+        }),
+        [0x78, 0x01]
+    );
+    header_test!(
+        dictionary,
+        Ok(Header {
+            window_size: 32768,
+            flevel: Flevel::Maximum,
+            dict_id: Some(0x01020304),
+            len: 6,
+        }),
+        [0x78, 0xF9, 1, 2, 3, 4]
+    );
+    header_test!(header_check, Err(Error::HeaderCheck), [0x78, 0x9D]);
+    header_test!(method, Err(Error::Method(7)), [0x77, 0x85]);
+    header_test!(window_size, Err(Error::WindowSize(16)), [0x88, 0x98]);
+    header_test!(truncated, Err(Error::Truncated), [0x78]);
+}
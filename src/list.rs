@@ -0,0 +1,30 @@
+use clap::{ArgGroup, Args};
+use std::error;
+
+use crate::libs::hash::{self, Func};
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("kind").required(true).multiple(true)))]
+pub struct List {
+    /// list message digest algorithms.
+    #[arg(long, group = "kind")]
+    digest_algorithms: bool,
+    /// list cipher algorithms.
+    #[arg(long, group = "kind")]
+    cipher_algorithms: bool,
+    /// list public key algorithms.
+    #[arg(long, group = "kind")]
+    public_key_algorithms: bool,
+}
+
+impl List {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        if self.digest_algorithms {
+            for f in Func::ALL.into_iter().filter(|&f| hash::allowed(f).is_ok()) {
+                println!("{}", f);
+            }
+        }
+        // there are no ciphers and public key algorithms yet.
+        Ok(())
+    }
+}
@@ -1,10 +1,10 @@
-use ssl::Cli;
+use ssl::{exit_code, Cli};
 
 fn main() {
     let cli = Cli::new();
 
     if let Err(err) = cli.run() {
         eprintln!("{}", err);
-        std::process::exit(1)
+        std::process::exit(exit_code(err.as_ref()))
     }
 }
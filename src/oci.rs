@@ -0,0 +1,238 @@
+use clap::{Args, Subcommand};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::libs::hash;
+use crate::libs::json;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+const LAYOUT_FILE: &str = "oci-layout";
+const INDEX_FILE: &str = "index.json";
+const BLOBS_DIR: &str = "blobs";
+const SHA256: &str = "sha256";
+
+/// media types of blobs which hold descriptors of other blobs.
+const MANIFEST_TYPES: [&str; 4] = [
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+#[derive(Args)]
+pub struct Oci {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// check every blob of an OCI image layout against its digest and every
+    /// descriptor reachable from index.json against its blob
+    VerifyLayout(VerifyLayout),
+}
+
+#[derive(Args)]
+struct VerifyLayout {
+    /// image layout directory (holding oci-layout, index.json and blobs/).
+    dir: PathBuf,
+}
+
+impl Oci {
+    pub fn exec(self) -> Result<()> {
+        match self.command {
+            Commands::VerifyLayout(cmd) => cmd.exec(),
+        }
+    }
+}
+
+impl VerifyLayout {
+    fn exec(self) -> Result<()> {
+        let layout = read_json(&self.dir.join(LAYOUT_FILE))?;
+        if layout
+            .get("imageLayoutVersion")
+            .and_then(json::Value::as_str)
+            .is_none()
+        {
+            return Err(format!("{}: no imageLayoutVersion", LAYOUT_FILE).into());
+        }
+
+        let mut failed = 0;
+        let verified = verify_blobs(&self.dir, &mut failed)?;
+        let index = read_json(&self.dir.join(INDEX_FILE))?;
+        let descriptors = verify_descriptors(&self.dir, index, &verified, &mut failed);
+
+        println!(
+            "{} blobs verified, {} descriptors checked",
+            verified.len(),
+            descriptors
+        );
+        if failed > 0 {
+            return Err(format!("WARNING: {} FAILS", failed).into());
+        }
+        Ok(())
+    }
+}
+
+fn read_json(path: &Path) -> Result<json::Value> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{:?}: {}", path, err))?;
+    json::parse(&text).map_err(|err| format!("{:?}: {}", path, err).into())
+}
+
+/// hashes every sha256 blob and compares it with its file name.
+/// returns digests of the matching blobs.
+fn verify_blobs(dir: &Path, failed: &mut usize) -> Result<HashSet<String>> {
+    let mut verified = HashSet::new();
+    let mut algorithms = BTreeSet::new();
+    for entry in fs::read_dir(dir.join(BLOBS_DIR))? {
+        algorithms.insert(entry?.file_name().to_string_lossy().into_owned());
+    }
+    for alg in algorithms {
+        let alg_dir = dir.join(BLOBS_DIR).join(&alg);
+        if alg != SHA256 {
+            eprintln!("{}: digest algorithm is not supported, skipped", alg);
+            continue;
+        }
+        let mut names = BTreeSet::new();
+        for entry in fs::read_dir(&alg_dir)? {
+            names.insert(entry?.file_name().to_string_lossy().into_owned());
+        }
+        for name in names {
+            let digest = format!("{}:{}", alg, name);
+            let actual = fs::File::open(alg_dir.join(&name)).and_then(hash::sha256);
+            match actual {
+                Ok(actual) if actual.to_string() == name => {
+                    println!("{}: OK", digest);
+                    verified.insert(digest);
+                }
+                Ok(_) => {
+                    println!("{}: FAILED", digest);
+                    *failed += 1;
+                }
+                Err(err) => {
+                    eprintln!("{}: {}", digest, err);
+                    *failed += 1;
+                }
+            }
+        }
+    }
+    Ok(verified)
+}
+
+/// blob path of an `algorithm:encoded` digest.
+fn blob_path(dir: &Path, digest: &str) -> Option<PathBuf> {
+    let (alg, encoded) = digest.split_once(':')?;
+    let alg_ok = !alg.is_empty()
+        && alg
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || b"+._-".contains(&c));
+    let encoded_ok = !encoded.is_empty()
+        && encoded
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"=_-".contains(&c));
+    if !alg_ok || !encoded_ok || alg.starts_with('.') {
+        return None;
+    }
+    Some(dir.join(BLOBS_DIR).join(alg).join(encoded))
+}
+
+/// walks descriptors breadth first from the image index, checking that each
+/// blob exists with the declared size and descending into manifests and indexes.
+/// returns number of checked descriptors.
+fn verify_descriptors(
+    dir: &Path,
+    index: json::Value,
+    verified: &HashSet<String>,
+    failed: &mut usize,
+) -> usize {
+    let mut checked = 0;
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    push_children(&mut queue, INDEX_FILE, index);
+
+    while let Some((parent, descriptor)) = queue.pop_front() {
+        checked += 1;
+        let digest = match descriptor.get("digest").and_then(json::Value::as_str) {
+            Some(digest) => digest.to_string(),
+            None => {
+                eprintln!("{}: descriptor without digest", parent);
+                *failed += 1;
+                continue;
+            }
+        };
+        let path = match blob_path(dir, &digest) {
+            Some(path) => path,
+            None => {
+                eprintln!("{}: {}: invalid digest", parent, digest);
+                *failed += 1;
+                continue;
+            }
+        };
+        let size = match fs::metadata(&path) {
+            Ok(meta) => meta.len(),
+            Err(err) => {
+                eprintln!("{}: {}: missing blob: {}", parent, digest, err);
+                *failed += 1;
+                continue;
+            }
+        };
+        match descriptor.get("size").and_then(json::Value::as_u64) {
+            Some(expected) if expected == size => (),
+            Some(expected) => {
+                eprintln!(
+                    "{}: {}: size {} does not match blob size {}",
+                    parent, digest, expected, size
+                );
+                *failed += 1;
+                continue;
+            }
+            None => {
+                eprintln!("{}: {}: descriptor without size", parent, digest);
+                *failed += 1;
+                continue;
+            }
+        }
+        // mismatching sha256 blobs are already counted.
+        if !verified.contains(&digest) {
+            continue;
+        }
+
+        let media_type = descriptor.get("mediaType").and_then(json::Value::as_str);
+        if !media_type.is_some_and(|t| MANIFEST_TYPES.contains(&t))
+            || !visited.insert(digest.clone())
+        {
+            continue;
+        }
+        match read_json(&path) {
+            Ok(manifest) => push_children(&mut queue, &digest, manifest),
+            Err(err) => {
+                eprintln!("{}", err);
+                *failed += 1;
+            }
+        }
+    }
+    checked
+}
+
+/// queues descriptors of an index or manifest.
+fn push_children(queue: &mut VecDeque<(String, json::Value)>, parent: &str, manifest: json::Value) {
+    let json::Value::Object(members) = manifest else {
+        return;
+    };
+    for (key, value) in members {
+        match (key.as_str(), value) {
+            ("config" | "subject", value @ json::Value::Object(_)) => {
+                queue.push_back((parent.to_string(), value))
+            }
+            ("layers" | "manifests", json::Value::Array(items)) => {
+                for item in items {
+                    queue.push_back((parent.to_string(), item));
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use clap::{Args, Subcommand};
+use std::error;
+use std::fs;
+use std::io::{self, Write};
+use std::path;
+
+use crate::libs::input;
+use crate::libs::pem;
+
+#[derive(Args)]
+pub struct Pem {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// write every PEM block of a bundle to its own file in DIR,
+    /// named by block number and label (like 00-certificate.pem)
+    Split(Split),
+}
+
+#[derive(Args)]
+struct Split {
+    /// PEM bundle (optional; default is stdin).
+    file: Option<path::PathBuf>,
+
+    /// directory to write blocks to. created if missing.
+    #[arg(long, value_name = "DIR")]
+    out_dir: path::PathBuf,
+}
+
+impl Pem {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        match self.command {
+            Commands::Split(cmd) => cmd.exec(),
+        }
+    }
+}
+
+impl Split {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let f = self.file.unwrap_or(path::PathBuf::from(input::STDIN_PATH));
+        let blocks = pem::parse(io::BufReader::new(input::Input::new(&f)?))?;
+
+        fs::create_dir_all(&self.out_dir)?;
+        for (i, block) in blocks.iter().enumerate() {
+            let name = format!("{:02}-{}.pem", i, file_label(&block.label));
+            let path = self.out_dir.join(name);
+
+            // existing files are never replaced.
+            let mut out = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            out.write_all(block.text.as_bytes())?;
+            out.sync_all()?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// `label` in lower case with every character but letters, digits and `-`
+/// replaced by `-`, so a hostile label can not name a path outside DIR.
+fn file_label(label: &str) -> String {
+    label
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' => c,
+            _ => '-',
+        })
+        .collect()
+}
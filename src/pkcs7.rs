@@ -0,0 +1,129 @@
+use clap::{Args, Subcommand};
+use std::error;
+use std::io::{self, Read, Write};
+use std::path;
+
+use crate::libs::der;
+use crate::libs::input;
+use crate::libs::output;
+use crate::libs::pem;
+use crate::libs::pkcs7;
+
+const CERTIFICATE_LABEL: &str = "CERTIFICATE";
+const CRL_LABEL: &str = "X509 CRL";
+
+#[derive(Args)]
+pub struct Pkcs7 {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// package certificates and CRLs of PEM files into a PKCS#7 certs-only
+    /// structure (like `openssl crl2pkcs7`)
+    Pack(Pack),
+    /// print certificates and CRLs of a PKCS#7 structure as PEM
+    Unpack(Unpack),
+}
+
+#[derive(Args)]
+struct Pack {
+    /// PEM files with CERTIFICATE and X509 CRL blocks; other blocks are
+    /// skipped. With no FILE, or when FILE is -, read standard input.
+    files: Vec<path::PathBuf>,
+
+    /// skip CRLs.
+    #[arg(long)]
+    no_crl: bool,
+
+    /// write DER instead of PEM.
+    #[arg(long)]
+    der: bool,
+
+    /// write output to FILE instead of standard output.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<path::PathBuf>,
+}
+
+#[derive(Args)]
+struct Unpack {
+    /// PKCS#7 in PEM or DER (optional; default is stdin).
+    file: Option<path::PathBuf>,
+
+    /// write output to FILE instead of standard output.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<path::PathBuf>,
+}
+
+impl Pkcs7 {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        match self.command {
+            Commands::Pack(cmd) => cmd.exec(),
+            Commands::Unpack(cmd) => cmd.exec(),
+        }
+    }
+}
+
+impl Pack {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let files = if self.files.is_empty() {
+            vec![path::PathBuf::from(input::STDIN_PATH)]
+        } else {
+            self.files
+        };
+
+        let mut blocks = Vec::new();
+        for f in &files {
+            blocks.extend(pem::parse(io::BufReader::new(input::Input::new(f)?))?);
+        }
+        let mut bundle = pkcs7::Bundle::default();
+        for block in &blocks {
+            match block.label.as_str() {
+                CERTIFICATE_LABEL => bundle.certificates.push(&block.data),
+                CRL_LABEL if !self.no_crl => bundle.crls.push(&block.data),
+                _ => {}
+            }
+        }
+        let data = pkcs7::encode(&bundle);
+
+        let out = self.out.unwrap_or(path::PathBuf::from(output::STDOUT_PATH));
+        let mut output = output::Output::new(&out)?;
+        if self.der {
+            output.write_all(&data)?;
+        } else {
+            output.write_all(pem::encode(pkcs7::PEM_LABEL, &data).as_bytes())?;
+        }
+        output.commit()?;
+        Ok(())
+    }
+}
+
+impl Unpack {
+    fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let f = self.file.unwrap_or(path::PathBuf::from(input::STDIN_PATH));
+        let mut data = Vec::new();
+        input::Input::new(&f)?.read_to_end(&mut data)?;
+
+        // DER starts with SEQUENCE tag, which is never valid PEM text.
+        if data.first() != Some(&der::TAG_SEQUENCE) {
+            let block = pem::parse(data.as_slice())?
+                .into_iter()
+                .find(|b| b.label == pkcs7::PEM_LABEL)
+                .ok_or("no PKCS7 PEM block")?;
+            data = block.data;
+        }
+        let bundle = pkcs7::decode(&data)?;
+
+        let out = self.out.unwrap_or(path::PathBuf::from(output::STDOUT_PATH));
+        let mut output = output::Output::new(&out)?;
+        for cert in bundle.certificates {
+            output.write_all(pem::encode(CERTIFICATE_LABEL, cert).as_bytes())?;
+        }
+        for crl in bundle.crls {
+            output.write_all(pem::encode(CRL_LABEL, crl).as_bytes())?;
+        }
+        output.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use clap::Args;
+use std::error;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path;
+
+use crate::libs::output;
+use crate::libs::rng;
+use crate::libs::secret;
+use crate::libs::size;
+
+#[derive(Args)]
+pub struct Rand {
+    /// number of bytes to generate. accepts K, M, G, T (powers of 1024)
+    /// and KB, MB, GB, TB (powers of 1000) suffixes.
+    #[arg(short, long, value_parser = size::parse)]
+    size: u64,
+    /// generate and write data by chunks of CHUNK bytes (same suffixes as --size).
+    #[arg(long, value_parser = size::parse, default_value = "64K")]
+    chunk: u64,
+    /// write output to FILE instead of standard output.
+    /// the file is replaced atomically when all data is written.
+    #[arg(short, long, value_name = "FILE")]
+    out: Option<path::PathBuf>,
+    /// octal permissions of the --out file (before umask).
+    #[arg(long, value_name = "MODE", value_parser = output::parse_mode, default_value = "600")]
+    mode: u32,
+    /// generate reproducible (NOT secure) data from SEED:
+    /// ChaCha20 keystream with key = SHA-256(seed), zero nonce and counter.
+    /// SEED is hex, or prefixed with hex:, base64:, raw: or file:.
+    #[arg(long, value_name = "SEED", value_parser = secret::parse)]
+    seed: Option<::std::vec::Vec<u8>>,
+}
+
+impl Rand {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        if self.chunk == 0 {
+            return Err("chunk size must be positive".into());
+        }
+        let chunk = usize::try_from(self.chunk)?;
+
+        let out = self.out.unwrap_or(path::PathBuf::from(output::STDOUT_PATH));
+        let mut output = output::Output::with_mode(&out, self.mode)?;
+        // progress is shown only when it does not mix with the data.
+        let progress = matches!(output, output::Output::File(_)) && io::stderr().is_terminal();
+
+        let mut rng: Box<dyn Read> = match self.seed {
+            Some(seed) => Box::new(rng::SeededRng::from_seed(&seed)),
+            None => Box::new(rng::Rng::from_os()?),
+        };
+        let mut buf = vec![0u8; chunk];
+        let mut written: u64 = 0;
+        while written < self.size {
+            let n = (chunk as u64).min(self.size - written) as usize;
+            rng.read_exact(&mut buf[..n])?;
+            output.write_all(&buf[..n])?;
+            written += n as u64;
+
+            if progress {
+                eprint!("\r{} / {} bytes", written, self.size);
+            }
+        }
+        if progress {
+            eprintln!();
+        }
+
+        output.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use clap::Args;
+use std::collections::HashSet;
+use std::error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::libs::hash::{self, Func};
+use crate::libs::warn;
+
+const TAR_EXTENSION: &str = "tar";
+
+#[derive(Args)]
+pub struct RenameByHash {
+    /// files to rename in place, within their directories.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// hash function naming the files.
+    #[arg(short, long, value_name = "FUNC", default_value = "sha256")]
+    algo: Func,
+    /// keep only the first N hex digits of the digest (default: all).
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    length: Option<u16>,
+    /// print the rename plan without renaming.
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+}
+
+impl RenameByHash {
+    /// renames every FILE to its digest, keeping its last extension
+    /// (or two for compressed tarballs like `.tar.gz`),
+    /// and prints `FILE -> NEW` for each. existing files are never replaced.
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        hash::allowed(self.algo)?;
+        warn::generating(self.algo);
+        let mut failed = 0;
+        // targets of earlier files, which may not exist yet with --dry-run.
+        let mut taken = HashSet::new();
+        for file in self.files.iter() {
+            let target = match self.target(file) {
+                Ok(target) => target,
+                Err(err) => {
+                    eprintln!("{:?}: {}", file, err);
+                    failed += 1;
+                    continue;
+                }
+            };
+            if target == *file {
+                continue;
+            }
+            if !taken.insert(target.clone()) || target.symlink_metadata().is_ok() {
+                eprintln!("{:?}: {:?} already exists, skipped", file, target);
+                failed += 1;
+                continue;
+            }
+            if !self.dry_run {
+                if let Err(err) = fs::rename(file, &target) {
+                    eprintln!("{:?}: rename: {}", file, err);
+                    failed += 1;
+                    continue;
+                }
+            }
+            println!("{} -> {}", file.display(), target.display());
+        }
+
+        if failed > 0 {
+            return Err(format!("WARNING: {} FAILS", failed).into());
+        }
+        Ok(())
+    }
+
+    /// digest-derived path of `file` in its directory.
+    fn target(&self, file: &Path) -> Result<PathBuf, Box<dyn error::Error>> {
+        if !fs::metadata(file)?.is_file() {
+            return Err("not a regular file".into());
+        }
+        let mut name = hash::digest(fs::File::open(file)?, self.algo)?.to_string();
+        if let Some(length) = self.length {
+            name.truncate(length.into());
+        }
+        if let Some(ext) = file.extension() {
+            let stem = Path::new(file.file_stem().unwrap_or_default());
+            let tar = stem
+                .extension()
+                .filter(|e| e.eq_ignore_ascii_case(TAR_EXTENSION));
+            if let Some(tar) = tar {
+                name.push('.');
+                name.push_str(&tar.to_string_lossy());
+            }
+            name.push('.');
+            name.push_str(&ext.to_string_lossy());
+        }
+        Ok(file.with_file_name(name))
+    }
+}
@@ -0,0 +1,103 @@
+use clap::{ArgGroup, Args};
+use std::error;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::libs::hash;
+use crate::libs::repo;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("metadata").required(true)))]
+pub struct RepoCheck {
+    /// Debian Release or InRelease file; files it lists but which are
+    /// absent from the mirror are skipped.
+    #[arg(long, value_name = "FILE", group = "metadata")]
+    release: Option<PathBuf>,
+    /// Debian Packages file (uncompressed).
+    #[arg(long, value_name = "FILE", group = "metadata")]
+    packages: Option<PathBuf>,
+    /// rpm repodata/repomd.xml file.
+    #[arg(long, value_name = "FILE", group = "metadata")]
+    repomd: Option<PathBuf>,
+    /// directory the listed paths are relative to: the directory of the
+    /// Release file, or the repository root for Packages and repomd.xml.
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    root: PathBuf,
+}
+
+impl RepoCheck {
+    pub fn exec(self) -> Result<()> {
+        let (file, entries, skip_missing) = if let Some(file) = self.release {
+            let entries = repo::release(&read(&file)?);
+            (file, entries, true)
+        } else if let Some(file) = self.packages {
+            let entries = repo::packages(&read(&file)?);
+            (file, entries, false)
+        } else if let Some(file) = self.repomd {
+            let entries = repo::repomd(&read(&file)?);
+            (file, entries, false)
+        } else {
+            unreachable!("clap requires one metadata file")
+        };
+        let entries = entries.map_err(|err| format!("{:?}: {}", file, err))?;
+
+        let (mut failed, mut missing) = (0, 0);
+        for entry in entries.iter() {
+            let path = match relative(&self.root, &entry.path) {
+                Some(path) => path,
+                None => {
+                    eprintln!("{}: path outside of root", entry.path);
+                    failed += 1;
+                    continue;
+                }
+            };
+            match verify(&path, entry) {
+                Ok(true) => println!("{}: OK", entry.path),
+                Ok(false) => {
+                    println!("{}: FAILED", entry.path);
+                    failed += 1;
+                }
+                Err(err) if skip_missing && err.kind() == io::ErrorKind::NotFound => missing += 1,
+                Err(err) => {
+                    eprintln!("{}: {}", entry.path, err);
+                    println!("{}: FAILED open or read", entry.path);
+                    failed += 1;
+                }
+            }
+        }
+        if missing > 0 {
+            eprintln!("{} listed files are not present, skipped", missing);
+        }
+
+        if failed > 0 {
+            return Err(format!("WARNING: {} FAILS", failed).into());
+        }
+        Ok(())
+    }
+}
+
+fn read(file: &Path) -> Result<String> {
+    fs::read_to_string(file).map_err(|err| format!("{:?}: {}", file, err).into())
+}
+
+/// `path` under `root`, or None when it could escape it.
+fn relative(root: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        .then(|| root.join(path))
+}
+
+/// whether size and digest of the file match the entry.
+fn verify(path: &Path, entry: &repo::Entry) -> io::Result<bool> {
+    let f = fs::File::open(path)?;
+    if let Some(size) = entry.size {
+        if f.metadata()?.len() != size {
+            return Ok(false);
+        }
+    }
+    Ok(hash::digest(f, entry.func)?.to_string() == entry.digest)
+}
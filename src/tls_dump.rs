@@ -0,0 +1,168 @@
+use clap::Args;
+use std::error;
+use std::fmt;
+use std::io::Read;
+use std::path;
+
+use crate::libs::input;
+use crate::libs::tls;
+
+#[derive(Args)]
+pub struct TlsDump {
+    /// captured TLS byte stream of one direction, starting at a record
+    /// boundary (optional; default is stdin).
+    file: Option<path::PathBuf>,
+}
+
+impl TlsDump {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        let f = self.file.unwrap_or(path::PathBuf::from(input::STDIN_PATH));
+        let mut data = Vec::new();
+        input::Input::new(&f)?.read_to_end(&mut data)?;
+
+        let mut r = tls::Reader::new(&data);
+        let mut handshake = Vec::new();
+        let mut encrypted = false;
+        while !r.is_empty() {
+            let offset = data.len() - r.rest().len();
+            let record = tls::record(&mut r).map_err(|err| Error { offset, err })?;
+            println!(
+                "record {} {} length {}",
+                Name(
+                    tls::content_type_name(record.content_type),
+                    record.content_type
+                ),
+                Name(tls::version_name(record.version), record.version),
+                record.fragment.len()
+            );
+
+            let res = match record.content_type {
+                _ if encrypted => {
+                    println!("  encrypted");
+                    Ok(())
+                }
+                tls::CONTENT_HANDSHAKE => {
+                    handshake.extend_from_slice(record.fragment);
+                    dump_handshakes(&mut handshake)
+                }
+                tls::CONTENT_ALERT => dump_alert(record.fragment),
+                tls::CONTENT_CHANGE_CIPHER_SPEC => {
+                    // everything after it is protected with the new keys.
+                    encrypted = true;
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            res.map_err(|err| Error { offset, err })?;
+        }
+        if !handshake.is_empty() {
+            println!("incomplete handshake message: {} bytes", handshake.len());
+        }
+        Ok(())
+    }
+}
+
+/// parsing error at the record starting at `offset`.
+#[derive(Debug)]
+struct Error {
+    offset: usize,
+    err: tls::Error,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "record at offset {}: {}", self.offset, self.err)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.err)
+    }
+}
+
+/// known name of a protocol value, or its number.
+struct Name<T>(Option<&'static str>, T);
+
+impl<T: fmt::LowerHex> fmt::Display for Name<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "unknown(0x{:x})", self.1),
+        }
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// prints complete messages and leaves a partial one in `buf`.
+fn dump_handshakes(buf: &mut Vec<u8>) -> tls::Result<()> {
+    let mut done = 0;
+    while let Some(n) = tls::handshake_len(&buf[done..]) {
+        if buf.len() - done < n {
+            break;
+        }
+        let msg = tls::handshake(&mut tls::Reader::new(&buf[done..done + n]))?;
+        dump_handshake(&msg)?;
+        done += n;
+    }
+    buf.drain(..done);
+    Ok(())
+}
+
+fn dump_handshake(msg: &tls::Handshake) -> tls::Result<()> {
+    println!(
+        "  handshake {} length {}",
+        Name(tls::handshake_type_name(msg.msg_type), msg.msg_type),
+        msg.body.len()
+    );
+    let hello = match msg.msg_type {
+        tls::HANDSHAKE_CLIENT_HELLO => tls::client_hello(msg.body)?,
+        tls::HANDSHAKE_SERVER_HELLO => tls::server_hello(msg.body)?,
+        _ => return Ok(()),
+    };
+
+    println!(
+        "    version: {}",
+        Name(tls::version_name(hello.version), hello.version)
+    );
+    println!("    random: {}", hex(hello.random));
+    println!("    session id: {}", hex(hello.session_id));
+    println!("    cipher suites:");
+    for s in hello.cipher_suites {
+        println!(
+            "      0x{:04x} {}",
+            s,
+            tls::cipher_suite_name(s).unwrap_or("unknown")
+        );
+    }
+    println!(
+        "    compression methods: {}",
+        hex(hello.compression_methods)
+    );
+    println!("    extensions:");
+    for e in hello.extensions {
+        println!(
+            "      {} ({}) length {}",
+            tls::extension_name(e.typ).unwrap_or("unknown"),
+            e.typ,
+            e.data.len()
+        );
+    }
+    Ok(())
+}
+
+fn dump_alert(fragment: &[u8]) -> tls::Result<()> {
+    let alert = tls::alert(&mut tls::Reader::new(fragment))?;
+    println!(
+        "  alert {} {}",
+        Name(tls::alert_level_name(alert.level), alert.level),
+        Name(
+            tls::alert_description_name(alert.description),
+            alert.description
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use clap::{Args, CommandFactory};
+use std::env;
+use std::error;
+
+use crate::libs::cpu;
+use crate::libs::hash::{self, Func};
+use crate::libs::json::Value;
+use crate::libs::log;
+use crate::libs::report;
+use crate::libs::watch;
+
+/// version of the layout of the `--json` document, raised on incompatible changes.
+const JSON_SCHEMA: u64 = 1;
+
+#[derive(Args)]
+pub struct Version {
+    /// print all information: algorithms, build and detected cpu extensions.
+    #[arg(short, long)]
+    all: bool,
+    /// print a JSON document of the subcommands, algorithms, formats and
+    /// features supported by this build.
+    #[arg(long, conflicts_with = "all")]
+    json: bool,
+}
+
+impl Version {
+    pub fn exec(self) -> Result<(), Box<dyn error::Error>> {
+        if self.json {
+            println!("{}", capabilities());
+            return Ok(());
+        }
+        println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        if !self.all {
+            return Ok(());
+        }
+
+        let digests: Vec<String> = Func::ALL.iter().map(|f| f.to_string()).collect();
+        let extensions = cpu::extensions();
+
+        println!("digests: {}", digests.join(" "));
+        println!("encodings: base16, base32, base32hex, base64, bech32, bech32m");
+        println!(
+            "build: {} {}-{}",
+            profile(),
+            env::consts::ARCH,
+            env::consts::OS
+        );
+        let implementations: Vec<String> = Func::ALL
+            .iter()
+            .map(|&f| format!("{}={}", f, hash::implementation(f)))
+            .collect();
+        println!("implementations: {}", implementations.join(" "));
+        if extensions.is_empty() {
+            println!("cpu extensions: none");
+        } else {
+            println!("cpu extensions: {}", extensions.join(" "));
+        }
+        Ok(())
+    }
+}
+
+fn profile() -> &'static str {
+    if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+fn strings<I: IntoIterator<Item = S>, S: ToString>(items: I) -> Value {
+    Value::Array(
+        items
+            .into_iter()
+            .map(|s| Value::String(s.to_string()))
+            .collect(),
+    )
+}
+
+/// what this build supports, for tools that must not parse help text.
+fn capabilities() -> Value {
+    let subcommands = crate::Cli::command()
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect::<Vec<_>>();
+    let implementations = Func::ALL
+        .iter()
+        .map(|&f| {
+            (
+                f.to_string(),
+                Value::String(hash::implementation(f).to_string()),
+            )
+        })
+        .collect();
+    let log_targets = log::Target::ALL
+        .into_iter()
+        .filter(|&t| cfg!(unix) || t == log::Target::Stderr);
+
+    Value::Object(vec![
+        ("schema".into(), JSON_SCHEMA.into()),
+        ("name".into(), env!("CARGO_PKG_NAME").into()),
+        ("version".into(), env!("CARGO_PKG_VERSION").into()),
+        (
+            "build".into(),
+            Value::Object(vec![
+                ("profile".into(), profile().into()),
+                ("arch".into(), env::consts::ARCH.into()),
+                ("os".into(), env::consts::OS.into()),
+            ]),
+        ),
+        ("subcommands".into(), strings(subcommands)),
+        ("digests".into(), strings(Func::ALL)),
+        ("implementations".into(), Value::Object(implementations)),
+        ("cpu_extensions".into(), strings(cpu::extensions())),
+        (
+            "encodings".into(),
+            strings([
+                "base16",
+                "base32",
+                "base32hex",
+                "base64",
+                "bech32",
+                "bech32m",
+            ]),
+        ),
+        (
+            "formats".into(),
+            Value::Object(vec![
+                ("checksum".into(), strings(["gnu", "bsd"])),
+                ("archives".into(), strings(["tar", "zip"])),
+                ("reports".into(), strings(report::Format::ALL)),
+            ]),
+        ),
+        (
+            "features".into(),
+            Value::Object(vec![
+                ("text_mode".into(), Value::Bool(true)),
+                ("escaped_names".into(), Value::Bool(true)),
+                ("fd_input".into(), Value::Bool(cfg!(unix))),
+                ("resume".into(), Value::Bool(true)),
+                ("watch".into(), watch::BACKEND.into()),
+                ("metrics".into(), Value::Bool(true)),
+                ("audit_log".into(), Value::Bool(true)),
+                ("log_targets".into(), strings(log_targets)),
+                ("webhook".into(), strings(["http"])),
+                ("fips".into(), Value::Bool(hash::fips())),
+            ]),
+        ),
+    ])
+}
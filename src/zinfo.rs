@@ -0,0 +1,152 @@
+use clap::Args;
+use std::error;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use crate::libs::checksum::{Adler32, Crc32};
+use crate::libs::gzip;
+use crate::libs::inflate;
+use crate::libs::input;
+use crate::libs::zlib;
+
+type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
+
+#[derive(Args)]
+pub struct Zinfo {
+    /// gzip or zlib file (optional; default is stdin).
+    file: Option<PathBuf>,
+}
+
+impl Zinfo {
+    pub fn exec(self) -> Result<()> {
+        let f = self.file.unwrap_or(PathBuf::from(input::STDIN_PATH));
+        let mut data = Vec::new();
+        input::Input::new(&f)?.read_to_end(&mut data)?;
+
+        let ok = if data.starts_with(&gzip::MAGIC) {
+            println!("format: gzip");
+            gzip_members(&data)?
+        } else if zlib::header(&data).is_ok() {
+            println!("format: zlib");
+            zlib_stream(&data)?
+        } else {
+            return Err("not gzip or zlib data".into());
+        };
+
+        if !ok {
+            return Err("checksum mismatch".into());
+        }
+        Ok(())
+    }
+}
+
+// prints every member and returns whether all checks passed.
+fn gzip_members(data: &[u8]) -> Result<bool> {
+    let mut ok = true;
+    let mut pos = 0;
+    for member in 1.. {
+        let h = gzip::header(&data[pos..])?;
+        println!("member {}:", member);
+        println!("  text: {}", h.text);
+        println!("  mtime: {}", h.mtime);
+        println!(
+            "  os: {} ({})",
+            gzip::os_name(h.os).unwrap_or("unrecognized"),
+            h.os
+        );
+        if let Some(extra) = &h.extra {
+            println!("  extra: {} bytes", extra.len());
+        }
+        if let Some(name) = &h.name {
+            println!("  name: {}", latin1(name));
+        }
+        if let Some(comment) = &h.comment {
+            println!("  comment: {}", latin1(comment));
+        }
+        if let Some(stored) = h.crc16 {
+            let mut crc = Crc32::new();
+            crc.write_all(&data[pos..pos + h.len - 2])?;
+            ok &= check(
+                "header crc16",
+                hex(stored as u32),
+                hex(crc.finish() & 0xFFFF),
+            );
+        }
+        pos += h.len;
+
+        let (out, taken) = inflate::inflate(&data[pos..])?;
+        pos += taken;
+        let t = gzip::trailer(&data[pos..])?;
+        pos += gzip::TRAILER_LEN;
+
+        println!("  compressed size: {}", taken);
+        println!("  uncompressed size: {}", out.len());
+        let mut crc = Crc32::new();
+        crc.write_all(&out)?;
+        ok &= check("crc32", hex(t.crc32), hex(crc.finish()));
+        ok &= check(
+            "size mod 2^32",
+            t.size.to_string(),
+            (out.len() as u32).to_string(),
+        );
+
+        // gzip ignores zero padding after the last member.
+        if !data[pos..].starts_with(&gzip::MAGIC) {
+            if data.len() > pos {
+                println!("trailing data: {} bytes", data.len() - pos);
+            }
+            break;
+        }
+    }
+    Ok(ok)
+}
+
+fn zlib_stream(data: &[u8]) -> Result<bool> {
+    let h = zlib::header(data)?;
+    println!("stream:");
+    println!("  window size: {}", h.window_size);
+    println!("  flevel: {}", h.flevel);
+    if let Some(id) = h.dict_id {
+        println!("  dictionary id: {}", hex(id));
+        return Err("data compressed with a preset dictionary can't be checked".into());
+    }
+
+    let (out, taken) = inflate::inflate(&data[h.len..])?;
+    let pos = h.len + taken;
+    let stored = zlib::trailer(&data[pos..])?;
+
+    println!("  compressed size: {}", taken);
+    println!("  uncompressed size: {}", out.len());
+    let mut adler = Adler32::new();
+    adler.write_all(&out)?;
+    let ok = check("adler32", hex(stored), hex(adler.finish()));
+    if data.len() > pos + zlib::TRAILER_LEN {
+        println!(
+            "trailing data: {} bytes",
+            data.len() - pos - zlib::TRAILER_LEN
+        );
+    }
+    Ok(ok)
+}
+
+fn check(name: &str, stored: String, computed: String) -> bool {
+    if stored == computed {
+        println!("  {}: {} OK", name, stored);
+        true
+    } else {
+        println!(
+            "  {}: stored {}, computed {} MISMATCH",
+            name, stored, computed
+        );
+        false
+    }
+}
+
+fn hex(v: u32) -> String {
+    format!("0x{:08x}", v)
+}
+
+// gzip names and comments are ISO 8859-1, which maps to the first 256 code points.
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
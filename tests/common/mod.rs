@@ -0,0 +1,104 @@
+//! helpers shared by the integration tests: running ssl, taking its output
+//! and temporary directories which are removed when dropped.
+
+// every test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+pub const HELLO_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+pub const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+pub fn ssl(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ssl"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// runs ssl with `args`, which need not be UTF-8, in the directory `dir`.
+pub fn ssl_in<S: AsRef<OsStr>>(dir: &Path, args: &[S]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ssl"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// runs ssl with `args` and the environment variables `env`, writing
+/// `stdin` to its standard input through a pipe.
+pub fn ssl_with(args: &[&str], env: &[(&str, &str)], stdin: &[u8]) -> Output {
+    ssl_in_with(Path::new("."), args, env, stdin)
+}
+
+/// like `ssl_with`, but runs ssl in the directory `dir`.
+pub fn ssl_in_with(dir: &Path, args: &[&str], env: &[(&str, &str)], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ssl"))
+        .current_dir(dir)
+        .args(args)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut pipe = child.stdin.take().unwrap();
+    let data = stdin.to_vec();
+    // the child may exit without reading everything.
+    let writer = thread::spawn(move || {
+        let _ = pipe.write_all(&data);
+    });
+    let out = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    out
+}
+
+/// standard output of a successful run.
+pub fn stdout(out: &Output) -> String {
+    assert!(
+        out.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout.clone()).unwrap()
+}
+
+/// standard error of a successful run.
+pub fn stderr(out: &Output) -> String {
+    assert!(out.status.success());
+    String::from_utf8(out.stderr.clone()).unwrap()
+}
+
+/// fresh temporary directory, removed with its content on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// directory of the test `name` of the test crate `suite`.
+    pub fn new(suite: &str, name: &str) -> TempDir {
+        let dir =
+            std::env::temp_dir().join(format!("ssl-{}-{}-{}", suite, std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
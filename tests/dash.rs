@@ -0,0 +1,31 @@
+//! `-` reads standard input even when a file named `-` exists, which is
+//! read as `./-`.
+
+mod common;
+
+use common::{ssl_in_with, stdout, TempDir, EMPTY_SHA256, HELLO_SHA256};
+use std::fs;
+
+#[test]
+fn stdin_and_file() {
+    let dir = TempDir::new("dash", "stdin_and_file");
+    fs::write(dir.join("-"), b"").unwrap();
+
+    let out = ssl_in_with(&dir, &["sha256", "-"], &[], b"hello\n");
+    assert_eq!(stdout(&out), format!("{}  -\n", HELLO_SHA256));
+
+    let out = ssl_in_with(&dir, &["sha256", "./-"], &[], b"hello\n");
+    assert_eq!(stdout(&out), format!("{}  ./-\n", EMPTY_SHA256));
+}
+
+#[test]
+fn base64() {
+    let dir = TempDir::new("dash", "base64");
+    fs::write(dir.join("-"), b"file").unwrap();
+
+    let out = ssl_in_with(&dir, &["base64", "-"], &[], b"stdin");
+    assert_eq!(stdout(&out), "c3RkaW4=\n");
+
+    let out = ssl_in_with(&dir, &["base64", "./-"], &[], b"stdin");
+    assert_eq!(stdout(&out), "ZmlsZQ==\n");
+}
@@ -0,0 +1,34 @@
+//! BSD lines of double digests are labelled like `SHA256D`, so --check
+//! digests their files twice.
+
+mod common;
+
+use common::{ssl_in as ssl, stdout, TempDir};
+use std::fs;
+
+/// SHA256 of SHA256 of "hello\n".
+const HELLO_SHA256D: &str = "ecb65bb98f9d905b70458986c39fcbad7715e5f2fcc3b1f07767d7c83e2438cc";
+
+#[test]
+fn double_tag() {
+    let dir = TempDir::new("double", "tag");
+    fs::write(dir.join("a"), b"hello\n").unwrap();
+    let line = stdout(&ssl(&dir, &["sha256", "--double", "--tag", "a"]));
+    assert_eq!(line, format!("SHA256D (a) = {}\n", HELLO_SHA256D));
+    fs::write(dir.join("manifest"), line).unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
+
+#[test]
+fn hash256_tag() {
+    let dir = TempDir::new("double", "hash256");
+    fs::write(dir.join("a"), b"hello\n").unwrap();
+    let line = stdout(&ssl(&dir, &["hash256", "--tag", "a"]));
+    assert_eq!(line, format!("SHA256D (a) = {}\n", HELLO_SHA256D));
+    fs::write(dir.join("manifest"), line).unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+    let out = ssl(&dir, &["hash256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
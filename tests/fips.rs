@@ -0,0 +1,39 @@
+//! --fips and SSL_FIPS=1 refuse hash functions which are not approved.
+
+mod common;
+
+use common::ssl_with;
+use std::process::Output;
+
+/// runs ssl with "hello\n" on standard input.
+fn ssl(args: &[&str], env: &[(&str, &str)]) -> Output {
+    ssl_with(args, env, b"hello\n")
+}
+
+#[test]
+fn md5_refused() {
+    for (args, env) in [
+        (&["--fips", "md5"][..], &[][..]),
+        (&["md5"][..], &[("SSL_FIPS", "1")][..]),
+        (&["sha256", "--fips", "--pipe", "sha256|md5"][..], &[][..]),
+        (&["hashdeep", "--fips"][..], &[][..]),
+    ] {
+        let out = ssl(args, env);
+        assert!(!out.status.success(), "{:?}", args);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(
+            stderr.contains("MD5 is not approved"),
+            "{:?}: {}",
+            args,
+            stderr
+        );
+    }
+}
+
+#[test]
+fn sha256_allowed() {
+    let out = ssl(&["--fips", "sha256"], &[]);
+    assert!(out.status.success());
+    let out = ssl(&["list", "--fips", "--digest-algorithms"], &[]);
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "SHA256\n");
+}
@@ -0,0 +1,23 @@
+//! `ssl md5 -c` verifies GNU and BSD lines of md5 digests.
+
+mod common;
+
+use common::{ssl_in, stdout, TempDir};
+use std::fs;
+
+const HELLO_MD5: &str = "b1946ac92492d2347c6235b4d2611184";
+
+#[test]
+fn md5_lines() {
+    let dir = TempDir::new("md5_check", "md5_lines");
+    fs::write(dir.join("a"), b"hello\n").unwrap();
+
+    for line in [
+        format!("{}  a\n", HELLO_MD5),
+        format!("MD5 (a) = {}\n", HELLO_MD5),
+    ] {
+        fs::write(dir.join("manifest"), &line).unwrap();
+        let out = ssl_in(&dir, &["md5", "-c", "manifest"]);
+        assert!(stdout(&out).ends_with("OK\n"), "line {:?}", line);
+    }
+}
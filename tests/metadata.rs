@@ -0,0 +1,43 @@
+//! size and modification time recorded by --metadata and compared by
+//! --check --verify-metadata.
+
+mod common;
+
+use common::{ssl_in as ssl, stdout, TempDir};
+use std::fs;
+
+fn dir(name: &str) -> TempDir {
+    TempDir::new("metadata", name)
+}
+
+#[test]
+fn verify_metadata() {
+    let dir = dir("verify");
+    fs::write(dir.join("a"), b"hello\n").unwrap();
+    let line = stdout(&ssl(&dir, &["sha256", "--metadata", "a"]));
+    assert!(line.starts_with("[size=6 mtime="), "{}", line);
+    fs::write(dir.join("manifest"), line).unwrap();
+
+    let out = ssl(&dir, &["sha256", "-c", "--verify-metadata", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+
+    fs::write(dir.join("a"), b"hello!\n").unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "--verify-metadata", "manifest"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("size incorrect"));
+}
+
+#[test]
+fn metadata_ignored_without_flag() {
+    let dir = dir("ignored");
+    fs::write(dir.join("a"), b"hello\n").unwrap();
+    fs::write(
+        dir.join("manifest"),
+        "[size=1 mtime=0.000000000] 5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  a\n",
+    )
+    .unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+    let out = ssl(&dir, &["sha256", "-c", "--verify-metadata", "manifest"]);
+    assert!(!out.status.success());
+}
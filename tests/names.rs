@@ -0,0 +1,63 @@
+//! file names with `\`, LF or CR are escaped in checksum lines as by GNU
+//! coreutils, and bytes which are not UTF-8 as `\xHH`. Such names are not
+//! allowed on Windows.
+#![cfg(unix)]
+
+mod common;
+
+use common::{ssl_in as ssl, stdout, TempDir, HELLO_SHA256};
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+
+/// fresh temporary directory with "hello\n" in a file named `name`.
+fn dir(test: &str, name: &str) -> TempDir {
+    let dir = TempDir::new("names", test);
+    fs::write(dir.join(name), b"hello\n").unwrap();
+    dir
+}
+
+#[test]
+fn escaped_gnu() {
+    let dir = dir("gnu", "a\nb\\c\rd");
+    let out = ssl(&dir, &["sha256", "a\nb\\c\rd"]);
+    let line = stdout(&out);
+    assert_eq!(line, format!("\\{}  a\\nb\\\\c\\rd\n", HELLO_SHA256));
+
+    fs::write(dir.join("manifest"), line).unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
+
+#[test]
+fn escaped_bsd() {
+    let dir = dir("bsd", "a\nb");
+    let out = ssl(&dir, &["sha256", "--tag", "a\nb"]);
+    let line = stdout(&out);
+    assert_eq!(line, format!("\\SHA256 (a\\nb) = {}\n", HELLO_SHA256));
+
+    fs::write(dir.join("manifest"), line).unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
+
+#[test]
+fn invalid_escape() {
+    let dir = dir("invalid", "a");
+    fs::write(dir.join("manifest"), format!("\\{}  a\\q\n", HELLO_SHA256)).unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(!out.status.success());
+}
+
+#[test]
+fn not_utf8() {
+    let name = OsStr::from_bytes(b"a\xff");
+    let dir = TempDir::new("names", "utf8");
+    fs::write(dir.join(name), b"hello\n").unwrap();
+    let out = ssl(&dir, &[OsStr::new("sha256"), name]);
+    let line = stdout(&out);
+    assert_eq!(line, format!("\\{}  a\\xff\n", HELLO_SHA256));
+    fs::write(dir.join("manifest"), line).unwrap();
+    let out = ssl(&dir, &["sha256", "-c", "manifest"]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
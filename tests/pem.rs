@@ -0,0 +1,34 @@
+//! `ssl pem split` writes blocks only into --out-dir and never replaces files.
+
+mod common;
+
+use common::{ssl, stdout, TempDir};
+use std::fs;
+
+const HOSTILE: &str = "-----BEGIN A/../../ESCAPED-----\nAAAA\n-----END A/../../ESCAPED-----\n";
+
+#[test]
+fn hostile_label() {
+    let dir = TempDir::new("pem", "hostile");
+    let bundle = dir.join("bundle.pem");
+    fs::write(&bundle, HOSTILE).unwrap();
+    let out_dir = dir.join("a").join("b");
+
+    let args = [
+        "pem",
+        "split",
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+        bundle.to_str().unwrap(),
+    ];
+    let out = ssl(&args);
+    let written = out_dir.join("00-a-------escaped.pem");
+    assert_eq!(stdout(&out), format!("{}\n", written.display()));
+    assert_eq!(fs::read_to_string(&written).unwrap(), HOSTILE);
+    assert!(!dir.join("escaped.pem").exists());
+
+    fs::write(&written, "keep").unwrap();
+    let out = ssl(&args);
+    assert!(!out.status.success());
+    assert_eq!(fs::read_to_string(&written).unwrap(), "keep");
+}
@@ -0,0 +1,122 @@
+//! hashing of pipes and FIFOs, which can be read only once and can not seek.
+
+mod common;
+
+use common::{stdout, EMPTY_SHA256, HELLO_SHA256};
+use std::process::Output;
+#[cfg(unix)]
+use {
+    common::TempDir,
+    std::{fs, io::Write, path::PathBuf, process::Command, thread},
+};
+
+/// zip archive with one stored member `a` holding "hello\n".
+const HELLO_ZIP: &[u8] = b"PK\x03\x04\x14\x00\x00\x00\x00\x00\x00\x00\x21\x00\x20\x30\x3a\x36\x06\x00\x00\x00\x06\x00\x00\x00\x01\x00\x00\x00ahello\nPK\x01\x02\x14\x03\x14\x00\x00\x00\x00\x00\x00\x00\x21\x00\x20\x30\x3a\x36\x06\x00\x00\x00\x06\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x01\x00\x00\x00\x00aPK\x05\x06\x00\x00\x00\x00\x01\x00\x01\x00\x2f\x00\x00\x00\x25\x00\x00\x00\x00\x00";
+
+/// runs ssl with `args`, writing `stdin` to its standard input through a pipe.
+fn ssl(args: &[&str], stdin: &[u8]) -> Output {
+    common::ssl_with(args, &[], stdin)
+}
+
+/// FIFO in a fresh temporary directory, filled with `data` by a thread
+/// once a reader opens it.
+#[cfg(unix)]
+fn fifo(name: &str, data: &'static [u8]) -> (TempDir, PathBuf, thread::JoinHandle<()>) {
+    let dir = TempDir::new("pipes", name);
+    let path = dir.join("fifo");
+    let status = Command::new("mkfifo").arg(&path).status().unwrap();
+    assert!(status.success());
+    let writer_path = path.clone();
+    let writer = thread::spawn(move || {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(writer_path)
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    });
+    (dir, path, writer)
+}
+
+#[test]
+fn stdin_pipe() {
+    let out = ssl(&["sha256"], b"hello\n");
+    assert_eq!(stdout(&out), format!("{}  -\n", HELLO_SHA256));
+}
+
+#[test]
+fn stdin_null() {
+    let out = ssl(&["sha256", "--stdin-null"], b"hello\n");
+    assert_eq!(stdout(&out), format!("{}  -\n", EMPTY_SHA256));
+}
+
+#[test]
+fn concat_with_stdin() {
+    let out = ssl(&["sha256", "--concat", "-"], b"hello\n");
+    assert_eq!(stdout(&out), format!("{}  -\n", HELLO_SHA256));
+}
+
+#[test]
+fn binary_out_chain() {
+    let out = ssl(&["sha256", "--binary-out"], b"hello\n");
+    assert!(out.status.success());
+    assert_eq!(out.stdout.len(), 32);
+    let chained = ssl(&["sha256"], &out.stdout);
+    let double = ssl(&["sha256", "--double"], b"hello\n");
+    assert_eq!(stdout(&chained), stdout(&double));
+}
+
+#[test]
+fn hash256_stdin() {
+    let out = ssl(&["hash256"], b"hello");
+    assert_eq!(
+        stdout(&out),
+        "9595c9df90075148eb06860365df33584b75bff782a510c6cd4883a419833d50  -\n"
+    );
+}
+
+#[test]
+fn salted_stdin() {
+    use sha2::Digest;
+
+    let out = ssl(&["sha256", "--salt"], b"hello\n");
+    let line = stdout(&out);
+    let (salt, rest) = line.split_once(':').unwrap();
+    let salt: Vec<u8> = (0..salt.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&salt[i..i + 2], 16).unwrap())
+        .collect();
+    assert_eq!(salt.len(), 16);
+    let expected = sha2::Sha256::new()
+        .chain_update(&salt)
+        .chain_update(b"hello\n")
+        .finalize();
+    assert_eq!(rest, format!("{:x}  -\n", expected));
+}
+
+#[test]
+fn zip_stdin() {
+    let out = ssl(&["sha256", "--zip"], HELLO_ZIP);
+    assert_eq!(stdout(&out), format!("{}  a\n", HELLO_SHA256));
+}
+
+#[cfg(unix)]
+#[test]
+fn fifo_file() {
+    let (_dir, path, writer) = fifo("file", b"hello\n");
+    let out = ssl(&["sha256", path.to_str().unwrap()], b"");
+    writer.join().unwrap();
+    assert_eq!(
+        stdout(&out),
+        format!("{}  {}\n", HELLO_SHA256, path.display())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn fifo_zip() {
+    let (_dir, path, writer) = fifo("zip", HELLO_ZIP);
+    let out = ssl(&["sha256", "--zip", path.to_str().unwrap()], b"");
+    writer.join().unwrap();
+    assert_eq!(stdout(&out), format!("{}  a\n", HELLO_SHA256));
+}
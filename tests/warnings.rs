@@ -0,0 +1,26 @@
+//! warnings with stable codes for weak digests, silenced by --no-warn.
+
+mod common;
+
+use common::{ssl, stderr, TempDir};
+
+const WARNING: &str = "WARNING[WEAK_DIGEST]";
+
+#[test]
+fn weak_digest() {
+    let out = ssl(&["md5", "/dev/null", "/dev/null"]);
+    assert_eq!(stderr(&out).matches(WARNING).count(), 1);
+    let out = ssl(&["sha256", "/dev/null"]);
+    assert!(!stderr(&out).contains(WARNING));
+    let out = ssl(&["md5", "--no-warn", "WEAK_DIGEST", "/dev/null"]);
+    assert!(!stderr(&out).contains(WARNING));
+}
+
+#[test]
+fn not_when_checking() {
+    let dir = TempDir::new("warnings", "check");
+    let manifest = dir.join("MD5SUMS");
+    std::fs::write(&manifest, "d41d8cd98f00b204e9800998ecf8427e  /dev/null\n").unwrap();
+    let out = ssl(&["md5", "-c", manifest.to_str().unwrap()]);
+    assert!(!stderr(&out).contains(WARNING));
+}
@@ -0,0 +1,86 @@
+//! behavior that matters on Windows: CRLF text files and manifests,
+//! binary-marked checksum lines and long paths. The tests run on every
+//! platform, the long path test only on Windows.
+
+mod common;
+
+use common::{ssl, stdout, TempDir, HELLO_SHA256};
+use std::fs;
+
+/// fresh temporary directory for the test `name`.
+fn dir(name: &str) -> TempDir {
+    TempDir::new("windows", name)
+}
+
+#[test]
+fn text_mode_ignores_crlf() {
+    let dir = dir("text");
+    let crlf = dir.join("crlf.txt");
+    fs::write(&crlf, b"hello\r\n").unwrap();
+    let out = ssl(&["sha256", "--text", crlf.to_str().unwrap()]);
+    assert!(stdout(&out).starts_with(HELLO_SHA256));
+    let out = ssl(&["sha256", crlf.to_str().unwrap()]);
+    assert!(!stdout(&out).starts_with(HELLO_SHA256));
+}
+
+#[test]
+fn check_crlf_manifest() {
+    let dir = dir("manifest");
+    let file = dir.join("a.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let manifest = dir.join("a.sha256");
+    fs::write(
+        &manifest,
+        format!("{}  {}\r\n", HELLO_SHA256, file.to_str().unwrap()),
+    )
+    .unwrap();
+    let out = ssl(&["sha256", "-c", manifest.to_str().unwrap()]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
+
+#[test]
+fn check_binary_marker() {
+    let dir = dir("binary");
+    let file = dir.join("a.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let manifest = dir.join("a.sha256");
+    fs::write(
+        &manifest,
+        format!("{} *{}\n", HELLO_SHA256, file.to_str().unwrap()),
+    )
+    .unwrap();
+    let out = ssl(&["sha256", "-c", manifest.to_str().unwrap()]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
+
+#[test]
+fn check_text_mode() {
+    let dir = dir("check-text");
+    let file = dir.join("a.txt");
+    fs::write(&file, b"hello\r\n").unwrap();
+    let manifest = dir.join("a.sha256");
+    fs::write(
+        &manifest,
+        format!("{}  {}\n", HELLO_SHA256, file.to_str().unwrap()),
+    )
+    .unwrap();
+    let out = ssl(&["sha256", "-c", manifest.to_str().unwrap()]);
+    assert!(!out.status.success());
+    let out = ssl(&["sha256", "--text", "-c", manifest.to_str().unwrap()]);
+    assert!(stdout(&out).ends_with("OK\n"));
+}
+
+#[cfg(windows)]
+#[test]
+fn long_path() {
+    let dir = dir("long");
+    let mut path = dir.to_path_buf();
+    for _ in 0..10 {
+        path.push("a".repeat(40));
+    }
+    fs::create_dir_all(&path).unwrap();
+    let file = path.join("a.txt");
+    fs::write(&file, b"hello\n").unwrap();
+    let out = ssl(&["sha256", file.to_str().unwrap()]);
+    assert!(stdout(&out).starts_with(HELLO_SHA256));
+}